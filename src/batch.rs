@@ -0,0 +1,194 @@
+//! Read the tags and properties of many songs concurrently
+
+/// Shared flag for stopping a batch early. Clones refer to the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// Songs already being read are finished, the rest are skipped
+    pub fn cancel(&self) {
+        self.cancelled
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BatchOptions {
+    /// Number of worker threads. 0 uses the available parallelism.
+    pub threads: usize,
+    pub cancel: CancelToken,
+}
+
+#[derive(Clone, Debug)]
+pub struct BatchItem {
+    pub metadata: crate::types::SongMetadata,
+    pub properties: crate::properties::SongProperties,
+}
+
+/// Passed to the progress callback each time a song has been read
+#[derive(Clone, Copy, Debug)]
+pub struct Progress<'a> {
+    pub path: &'a str,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Reads every song and returns the results in the order of `paths`. The progress
+/// callback runs on the calling thread. Songs skipped because the batch was
+/// cancelled fail with `ErrorKind::Interrupted`.
+pub fn read_batch<F: FnMut(&Progress)>(
+    paths: &[String],
+    options: &BatchOptions,
+    mut progress: F,
+) -> Vec<Result<BatchItem, std::io::Error>> {
+    let threads = match options.threads {
+        0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    }
+    .min(paths.len())
+    .max(1);
+
+    let mut results: Vec<Option<Result<BatchItem, std::io::Error>>> =
+        paths.iter().map(|_| None).collect();
+    let next = std::sync::atomic::AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || {
+                while !options.cancel.is_cancelled() {
+                    let index = next.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    if index >= paths.len() {
+                        break;
+                    }
+                    if sender.send((index, read_item(&paths[index]))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for (completed, (index, result)) in receiver.into_iter().enumerate() {
+            results[index] = Some(result);
+            progress(&Progress {
+                path: &paths[index],
+                completed: completed + 1,
+                total: paths.len(),
+            });
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| match result {
+            Some(result) => result,
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "Batch was cancelled",
+            )),
+        })
+        .collect()
+}
+
+fn read_item(path: &String) -> Result<BatchItem, std::io::Error> {
+    match crate::meta::metadata::get_all_meta(path) {
+        Ok(metadata) => match crate::properties::get_song_properties(path) {
+            Ok(properties) => Ok(BatchItem {
+                metadata,
+                properties,
+            }),
+            Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    fn track_paths() -> Vec<String> {
+        (1..=3)
+            .map(|track| {
+                util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(track)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_batch() {
+        let mut paths = track_paths();
+        paths.insert(1, String::from("does/not/exist.flac"));
+
+        let options = super::BatchOptions {
+            threads: 2,
+            ..Default::default()
+        };
+        let mut reported = Vec::new();
+        let results = super::read_batch(&paths, &options, |progress| {
+            assert_eq!(4, progress.total);
+            reported.push((progress.completed, String::from(progress.path)));
+        });
+
+        assert_eq!(4, results.len());
+        let titles: Vec<Option<String>> = results
+            .iter()
+            .map(|result| match result {
+                Ok(item) => item.metadata.title.clone(),
+                Err(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                Some(String::from("Just roll it")),
+                None,
+                Some(String::from("Here we go!")),
+                Some(String::from("Closer"))
+            ],
+            titles
+        );
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            results[1].as_ref().unwrap_err().kind()
+        );
+        assert_eq!(48000, results[0].as_ref().unwrap().properties.sample_rate);
+
+        let completed: Vec<usize> = reported.iter().map(|(completed, _)| *completed).collect();
+        assert_eq!(vec![1, 2, 3, 4], completed);
+        let mut reported_paths: Vec<String> = reported.into_iter().map(|(_, path)| path).collect();
+        reported_paths.sort();
+        paths.sort();
+        assert_eq!(paths, reported_paths);
+    }
+
+    #[test]
+    fn test_read_batch_cancelled() {
+        let paths = track_paths();
+        let options = super::BatchOptions::default();
+        options.cancel.clone().cancel();
+
+        let mut calls = 0;
+        let results = super::read_batch(&paths, &options, |_| calls += 1);
+        assert_eq!(0, calls);
+        assert_eq!(3, results.len());
+        assert!(results.iter().all(|result| match result {
+            Ok(_) => false,
+            Err(err) => err.kind() == std::io::ErrorKind::Interrupted,
+        }));
+
+        assert!(super::read_batch(&[], &super::BatchOptions::default(), |_| {}).is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+//! Inspects and edits the tags, cover art and properties of songs from the command line
+
+use std::io::Write;
+
+use icarus_meta::{detection, meta, properties, types};
+
+const USAGE: &str = "Usage: icarus-meta [--json] <command>
+
+Commands:
+  show <file>                  Show every tag, custom field and picture
+  get <file> <field>           Print the value of a field
+  set <file> <field> <value>   Write a field
+  remove <file> <field>        Remove a field
+  art list <file>              Describe the embedded pictures
+  art get <file> <output>      Save the cover art to a file
+  art set <file> <image>       Embed an image as the cover art
+  art remove <file>            Remove the cover art
+  props <file>                 Show the audio properties
+  detect <file>                Detect the type of a song or image
+
+Fields are Vorbis comment keys (ALBUMARTIST) or field names (album_artist).
+Keys that are not a known field are read and written as custom comments.";
+
+/// Exit code for failures while reading or writing files
+const EXIT_FAILURE: u8 = 1;
+/// Exit code for malformed command lines
+const EXIT_USAGE: u8 = 2;
+
+#[derive(Debug)]
+struct CliError {
+    code: u8,
+    message: String,
+}
+
+impl CliError {
+    fn usage(message: &str) -> CliError {
+        CliError {
+            code: EXIT_USAGE,
+            message: String::from(message),
+        }
+    }
+
+    fn failure(message: String) -> CliError {
+        CliError {
+            code: EXIT_FAILURE,
+            message,
+        }
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> CliError {
+        CliError::failure(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(err: serde_json::Error) -> CliError {
+        CliError::failure(err.to_string())
+    }
+}
+
+enum Field {
+    Known(types::Type),
+    Custom(String),
+}
+
+fn main() -> std::process::ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (json, args) = split_flags(&args);
+
+    match run(args) {
+        Ok(output) => {
+            let text = if json {
+                match serde_json::to_string_pretty(&output) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        eprintln!("icarus-meta: {err}");
+                        return std::process::ExitCode::from(EXIT_FAILURE);
+                    }
+                }
+            } else {
+                render(&output)
+            };
+
+            // A closed pipe, as with `icarus-meta show song.flac | head`, is not an error
+            if !text.is_empty() {
+                let _ = writeln!(std::io::stdout(), "{text}");
+            }
+            std::process::ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("icarus-meta: {}", err.message);
+            if err.code == EXIT_USAGE {
+                eprintln!("\n{USAGE}");
+            }
+            std::process::ExitCode::from(err.code)
+        }
+    }
+}
+
+/// Takes `--json` off the front of the arguments. After the command it is an
+/// ordinary argument, such as a value to set.
+fn split_flags(args: &[String]) -> (bool, &[String]) {
+    match args {
+        [flag, rest @ ..] if flag == "--json" => (true, rest),
+        _ => (false, args),
+    }
+}
+
+/// Runs a command and returns its output, without the program name and `--json`
+fn run(args: &[String]) -> Result<serde_json::Value, CliError> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["show", file] => {
+            let song_metadata = meta::metadata::get_all_meta(&file.to_string())?;
+            Ok(serde_json::to_value(song_metadata)?)
+        }
+        ["get", file, field] => get_field(file, parse_field(field)?),
+        ["set", file, field, value] => set_field(file, parse_field(field)?, value),
+        ["remove", file, field] => remove_field(file, parse_field(field)?),
+        ["art", "list", file] => {
+            let song_metadata = meta::metadata::get_all_meta(&file.to_string())?;
+            Ok(serde_json::to_value(song_metadata.pictures)?)
+        }
+        ["art", "get", file, output] => {
+            let data = meta::coverart::get_coverart(&file.to_string())?;
+            if data.is_empty() {
+                return Err(CliError::failure(format!("{file} has no cover art")));
+            }
+            std::fs::write(output, &data)?;
+            Ok(serde_json::json!({ "path": output, "size": data.len() }))
+        }
+        ["art", "set", file, image] => {
+            detection::coverart::file_type_from_filepath(image)?;
+            let data = meta::coverart::set_coverart(&file.to_string(), &image.to_string())?;
+            Ok(serde_json::json!({ "path": image, "size": data.len() }))
+        }
+        ["art", "remove", file] => {
+            let data = meta::coverart::remove_coverart(&file.to_string())?;
+            if data.is_empty() {
+                return Err(CliError::failure(format!("{file} has no cover art")));
+            }
+            Ok(serde_json::json!({ "size": data.len() }))
+        }
+        ["props", file] => Ok(serde_json::to_value(properties::get_song_properties(
+            file,
+        )?)?),
+        ["detect", file] => match detection::song::file_type_from_filepath(file) {
+            Ok(file_type) => Ok(serde_json::to_value(file_type)?),
+            Err(song_err) => match detection::coverart::file_type_from_filepath(file) {
+                Ok(file_type) => Ok(serde_json::to_value(file_type)?),
+                Err(_) => Err(CliError::from(song_err)),
+            },
+        },
+        ["help"] | ["--help"] | ["-h"] => Ok(serde_json::Value::String(String::from(USAGE))),
+        [] => Err(CliError::usage("No command given")),
+        [command, ..] => match *command {
+            "show" | "get" | "set" | "remove" | "art" | "props" | "detect" => {
+                Err(CliError::usage(&format!("Wrong arguments for {command}")))
+            }
+            _ => Err(CliError::usage(&format!("Unknown command {command}"))),
+        },
+    }
+}
+
+/// Accepts a known Vorbis key, a field name as serialized by serde, or any other
+/// valid Vorbis key as a custom field
+fn parse_field(name: &str) -> Result<Field, CliError> {
+    if let Ok(t) = serde_json::from_value(serde_json::Value::String(name.to_lowercase())) {
+        return Ok(Field::Known(t));
+    }
+    if let Ok(t) = types::access::get_type_from_str(name) {
+        return Ok(Field::Known(t));
+    }
+
+    let valid = !name.is_empty() && name.chars().all(|c| (' '..='}').contains(&c) && c != '=');
+    if valid {
+        Ok(Field::Custom(name.to_uppercase()))
+    } else {
+        Err(CliError::usage(&format!("Invalid field {name:?}")))
+    }
+}
+
+fn get_field(file: &str, field: Field) -> Result<serde_json::Value, CliError> {
+    match field {
+        Field::Known(t) => match meta::metadata::get_all_meta(&file.to_string())?.get(t) {
+            Some(value) => Ok(serde_json::Value::String(value.to_string())),
+            None => Err(CliError::failure(format!(
+                "{} is not set",
+                types::access::get_type(t)?
+            ))),
+        },
+        Field::Custom(key) => {
+            let values = meta::metadata::get_comments(&file.to_string(), &key)?;
+            if values.is_empty() {
+                Err(CliError::failure(format!("{key} is not set")))
+            } else {
+                Ok(serde_json::to_value(values)?)
+            }
+        }
+    }
+}
+
+fn set_field(file: &str, field: Field, value: &str) -> Result<serde_json::Value, CliError> {
+    match field {
+        Field::Known(t) => {
+            let written = meta::metadata::set_meta(t, &file.to_string(), value)?;
+            Ok(serde_json::Value::String(written))
+        }
+        Field::Custom(key) => {
+            meta::metadata::set_comments(&file.to_string(), &key, &[String::from(value)])?;
+            Ok(serde_json::Value::String(String::from(value)))
+        }
+    }
+}
+
+fn remove_field(file: &str, field: Field) -> Result<serde_json::Value, CliError> {
+    let (key, removed) = match field {
+        Field::Known(t) => (
+            types::access::get_type(t)?,
+            meta::metadata::remove_meta(t, &file.to_string())?,
+        ),
+        Field::Custom(key) => {
+            let removed =
+                meta::metadata::remove_comments(&file.to_string(), std::slice::from_ref(&key))?;
+            (key, removed)
+        }
+    };
+
+    if removed.is_empty() {
+        Err(CliError::failure(format!("{key} is not set")))
+    } else {
+        Ok(serde_json::to_value(removed)?)
+    }
+}
+
+/// Renders the output as `name: value` lines, naming nested values by their path
+fn render(value: &serde_json::Value) -> String {
+    let mut lines = Vec::new();
+    render_into(&mut lines, "", value);
+    lines.join("\n")
+}
+
+fn render_into(lines: &mut Vec<String>, name: &str, value: &serde_json::Value) {
+    let child = |key: &str| {
+        if name.is_empty() {
+            String::from(key)
+        } else {
+            format!("{name}.{key}")
+        }
+    };
+
+    match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                render_into(lines, &child(key), value);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                let index_name = if values.len() == 1 && !value.is_object() {
+                    String::from(name)
+                } else {
+                    child(&index.to_string())
+                };
+                render_into(lines, &index_name, value);
+            }
+        }
+        serde_json::Value::String(text) if name.is_empty() => lines.push(text.clone()),
+        serde_json::Value::String(text) => lines.push(format!("{name}: {text}")),
+        other if name.is_empty() => lines.push(other.to_string()),
+        other => lines.push(format!("{name}: {other}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icarus_meta::test_util::util;
+
+    fn run(args: &[&str]) -> Result<serde_json::Value, super::CliError> {
+        let args: Vec<String> = args.iter().map(|arg| String::from(*arg)).collect();
+        super::run(&args)
+    }
+
+    #[test]
+    fn test_get_set_remove() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        assert_eq!("Just roll it", run(&["get", &filepath, "TITLE"]).unwrap());
+        assert_eq!("KD", run(&["get", &filepath, "album_artist"]).unwrap());
+
+        assert_eq!(
+            "2019-05-03",
+            run(&["set", &filepath, "date", "2019-05-03T00:00:00"]).unwrap()
+        );
+        assert_eq!("2019-05-03", run(&["get", &filepath, "DATE"]).unwrap());
+
+        run(&["set", &filepath, "comment", "Mastered twice"]).unwrap();
+        assert_eq!(
+            serde_json::json!(["Mastered twice"]),
+            run(&["get", &filepath, "COMMENT"]).unwrap()
+        );
+
+        assert_eq!(
+            serde_json::json!(["Mastered twice"]),
+            run(&["remove", &filepath, "comment"]).unwrap()
+        );
+        let err = run(&["get", &filepath, "COMMENT"]).unwrap_err();
+        assert_eq!(super::EXIT_FAILURE, err.code);
+
+        let err = run(&["set", &filepath, "track", "three"]).unwrap_err();
+        assert_eq!(super::EXIT_FAILURE, err.code);
+        assert_eq!("1", run(&["get", &filepath, "track"]).unwrap());
+    }
+
+    #[test]
+    fn test_raw_comments() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        run(&["set", &filepath, "comment", "first\nTITLE=Injected"]).unwrap();
+        assert_eq!(
+            serde_json::json!(["first\nTITLE=Injected"]),
+            run(&["get", &filepath, "COMMENT"]).unwrap()
+        );
+        assert_eq!("Just roll it", run(&["get", &filepath, "title"]).unwrap());
+
+        run(&["set", &filepath, "track_count", "12"]).unwrap();
+        assert_eq!(
+            serde_json::json!(["12"]),
+            run(&["get", &filepath, "TRACKTOTAL"]).unwrap()
+        );
+        icarus_meta::meta::metadata::import_comments(
+            &filepath,
+            b"YEAR=2019\n",
+            icarus_meta::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::json!(["2019"]),
+            run(&["get", &filepath, "year"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_split_flags() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+
+        let given = args(&["--json", "show", "song.flac"]);
+        assert_eq!((true, &given[1..]), super::split_flags(&given));
+
+        let given = args(&["set", "song.flac", "comment", "--json"]);
+        assert_eq!((false, &given[..]), super::split_flags(&given));
+    }
+
+    #[test]
+    fn test_show_and_art() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        let shown = run(&["show", &filepath]).unwrap();
+        assert_eq!("Sample Tracks 3", shown["album"]);
+        assert_eq!(1, shown["pictures"].as_array().unwrap().len());
+
+        let rendered = super::render(&shown);
+        assert!(rendered.contains("title: Just roll it"), "{rendered}");
+        assert!(
+            rendered.contains("pictures.0.picture_type: 3"),
+            "{rendered}"
+        );
+
+        let output = util::get_full_path(&test_dir, "cover.png").unwrap();
+        let saved = run(&["art", "get", &filepath, &output]).unwrap();
+        assert!(util::file_exists(&test_dir, "cover.png").unwrap());
+        assert_eq!(
+            serde_json::json!(std::fs::metadata(&output).unwrap().len()),
+            saved["size"]
+        );
+
+        run(&["art", "remove", &filepath]).unwrap();
+        assert_eq!(
+            serde_json::json!([]),
+            run(&["art", "list", &filepath]).unwrap()
+        );
+        let err = run(&["art", "get", &filepath, &output]).unwrap_err();
+        assert_eq!(super::EXIT_FAILURE, err.code);
+
+        run(&["art", "set", &filepath, &output]).unwrap();
+        let pictures = run(&["art", "list", &filepath]).unwrap();
+        assert_eq!("image/png", pictures[0]["mime"]);
+    }
+
+    #[test]
+    fn test_props_and_detect() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+
+        let props = run(&["props", &filepath]).unwrap();
+        assert_eq!(48000, props["sample_rate"]);
+
+        let detected = run(&["detect", &filepath]).unwrap();
+        assert_eq!("flac", detected["file_type"]);
+
+        let image = util::get_full_path(util::TESTFILEDIRECTORY, "Sample Tracks 3.png").unwrap();
+        let detected = run(&["detect", &image]).unwrap();
+        assert_eq!("png", detected["file_type"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(super::EXIT_USAGE, run(&[]).unwrap_err().code);
+        assert_eq!(super::EXIT_USAGE, run(&["shout"]).unwrap_err().code);
+        assert_eq!(
+            super::EXIT_USAGE,
+            run(&["get", "song.flac"]).unwrap_err().code
+        );
+        assert_eq!(
+            super::EXIT_USAGE,
+            run(&["get", "song.flac", "BAD=KEY"]).unwrap_err().code
+        );
+        assert_eq!(
+            super::EXIT_FAILURE,
+            run(&["show", "does/not/exist.flac"]).unwrap_err().code
+        );
+    }
+}
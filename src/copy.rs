@@ -0,0 +1,622 @@
+//! Copy the tags and pictures of a song to another file, which may be in a
+//! different container such as MP3, Opus or MP4
+
+use lofty::file::AudioFile;
+use lofty::ogg::OggPictureStorage;
+use lofty::tag::TagExt;
+
+/// Name reported for the pictures when the destination cannot hold them
+pub const PICTURES: &str = "PICTURES";
+
+/// Which fields to copy. Fields are named by their Vorbis key and compared
+/// without regard to case.
+#[derive(Clone, Debug)]
+pub struct CopyOptions {
+    /// Only copy these fields. Empty copies every field.
+    pub include: Vec<String>,
+    /// Never copy these fields, even when they are included
+    pub exclude: Vec<String>,
+    pub pictures: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> CopyOptions {
+        CopyOptions {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            pictures: true,
+        }
+    }
+}
+
+impl CopyOptions {
+    fn wants(&self, key: &str) -> bool {
+        let listed = |keys: &[String]| keys.iter().any(|k| k.eq_ignore_ascii_case(key));
+        (self.include.is_empty() || listed(&self.include)) && !listed(&self.exclude)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CopyReport {
+    /// Fields written to the destination, in the order of the source
+    pub copied: Vec<String>,
+    /// Fields the destination's tag format has no place for. Contains `PICTURES`
+    /// when there were pictures to copy and the format cannot store them.
+    pub unrepresentable: Vec<String>,
+    pub pictures: usize,
+}
+
+/// Copies the tags and pictures of the song at `src` into the native tag of the
+/// file at `dst`, keeping frames the copy does not touch. Copied fields replace
+/// those of the same name at the destination and the pictures replace all of
+/// its pictures. Fields without a standard mapping are written as TXXX frames to
+/// ID3v2 tags and under their own key to Vorbis comments and APE tags.
+pub fn copy_metadata(
+    src: &String,
+    dst: &String,
+    options: &CopyOptions,
+) -> Result<CopyReport, std::io::Error> {
+    let source = match std::fs::File::open(src) {
+        Ok(mut file) => {
+            match lofty::flac::FlacFile::read_from(&mut file, lofty::config::ParseOptions::new()) {
+                Ok(flac_file) => flac_file,
+                Err(err) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        err.to_string(),
+                    ));
+                }
+            }
+        }
+        Err(err) => return Err(err),
+    };
+
+    let mut fields: Vec<(String, Vec<String>)> = Vec::new();
+    if let Some(vorbis_comments) = source.vorbis_comments() {
+        for (key, value) in vorbis_comments.items() {
+            let key = key.to_uppercase();
+            if !options.wants(&key) {
+                continue;
+            }
+            match fields.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, values)) => values.push(String::from(value)),
+                None => fields.push((key, vec![String::from(value)])),
+            }
+        }
+    }
+
+    let pictures: Vec<lofty::picture::Picture> = if options.pictures {
+        source
+            .pictures()
+            .iter()
+            .map(|(picture, _)| picture.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    match lofty::file::FileType::from_path(dst) {
+        Some(lofty::file::FileType::Flac) => {
+            let options = crate::flac::WriteOptions::default();
+            let (report, _) = crate::flac::modify(dst, &options, |flac_file| {
+                let mut vorbis_comments = flac_file.remove_vorbis_comments().unwrap_or_default();
+                let mut report = copy_comments(&mut vorbis_comments, &fields);
+                flac_file.set_vorbis_comments(vorbis_comments);
+                replace_pictures(flac_file, &pictures, &mut report)?;
+                Ok(report)
+            })?;
+            Ok(report)
+        }
+        Some(lofty::file::FileType::Opus) => {
+            let file: lofty::ogg::OpusFile = read_native(dst)?;
+            copy_to_ogg(dst, file.vorbis_comments().clone(), &fields, &pictures)
+        }
+        Some(lofty::file::FileType::Vorbis) => {
+            let file: lofty::ogg::VorbisFile = read_native(dst)?;
+            copy_to_ogg(dst, file.vorbis_comments().clone(), &fields, &pictures)
+        }
+        Some(lofty::file::FileType::Speex) => {
+            let file: lofty::ogg::SpeexFile = read_native(dst)?;
+            copy_to_ogg(dst, file.vorbis_comments().clone(), &fields, &pictures)
+        }
+        Some(lofty::file::FileType::Mpeg)
+        | Some(lofty::file::FileType::Wav)
+        | Some(lofty::file::FileType::Aiff) => {
+            let id3v2 = crate::lyrics::read_id3v2(dst)?.unwrap_or_default();
+            let (mut id3v2, report, user_text) = merge_native(id3v2, &fields, &pictures);
+            for (key, values) in user_text {
+                // ID3v2.4 separates multiple values of a text frame with a null
+                let _ = id3v2.insert_user_text(key, values.join("\0"));
+            }
+            save_native(&id3v2, dst)?;
+            Ok(report)
+        }
+        Some(lofty::file::FileType::Mp4) => {
+            let file: lofty::mp4::Mp4File = read_native(dst)?;
+            let ilst = file.ilst().cloned().unwrap_or_default();
+            let (ilst, report, _) = merge_native(ilst, &fields, &pictures);
+            save_native(&ilst, dst)?;
+            Ok(report)
+        }
+        Some(lofty::file::FileType::Ape)
+        | Some(lofty::file::FileType::WavPack)
+        | Some(lofty::file::FileType::Mpc) => {
+            let ape = match lofty::file::FileType::from_path(dst) {
+                Some(lofty::file::FileType::Ape) => {
+                    read_native::<lofty::ape::ApeFile>(dst)?.ape().cloned()
+                }
+                Some(lofty::file::FileType::WavPack) => {
+                    read_native::<lofty::wavpack::WavPackFile>(dst)?
+                        .ape()
+                        .cloned()
+                }
+                _ => read_native::<lofty::musepack::MpcFile>(dst)?.ape().cloned(),
+            };
+            let (ape, report, _) = merge_native(ape.unwrap_or_default(), &fields, &pictures);
+            save_native(&ape, dst)?;
+            Ok(report)
+        }
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("Cannot copy tags to {dst}"),
+        )),
+    }
+}
+
+fn read_native<F: lofty::file::AudioFile>(filepath: &String) -> Result<F, std::io::Error> {
+    let mut file = std::fs::File::open(filepath)?;
+    let parse_options = lofty::config::ParseOptions::new().read_properties(false);
+    match F::read_from(&mut file, parse_options) {
+        Ok(native) => Ok(native),
+        Err(err) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            err.to_string(),
+        )),
+    }
+}
+
+fn save_native<T: TagExt<Err = lofty::error::LoftyError>>(
+    tag: &T,
+    filepath: &String,
+) -> Result<(), std::io::Error> {
+    match tag.save_to_path(filepath, lofty::config::WriteOptions::default()) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            err.to_string(),
+        )),
+    }
+}
+
+/// Fills the generic part of a native tag and merges it back, so frames the
+/// generic tag cannot hold, such as SYLT, are kept
+fn merge_native<T>(
+    native: T,
+    fields: &[(String, Vec<String>)],
+    pictures: &[lofty::picture::Picture],
+) -> (T, CopyReport, Vec<(String, Vec<String>)>)
+where
+    T: lofty::tag::SplitTag,
+    T::Remainder: lofty::tag::MergeTag<Merged = T>,
+{
+    use lofty::tag::MergeTag;
+
+    let (remainder, mut tag) = native.split_tag();
+    let (report, user_text) = fill_tag(&mut tag, fields, pictures);
+    (remainder.merge_tag(tag), report, user_text)
+}
+
+/// Vorbis comments take every key as it is, so the fields are copied raw
+fn copy_comments(
+    vorbis_comments: &mut lofty::ogg::VorbisComments,
+    fields: &[(String, Vec<String>)],
+) -> CopyReport {
+    let mut report = CopyReport::default();
+    for (key, values) in fields {
+        let _ = vorbis_comments.remove(key);
+        for value in values {
+            vorbis_comments.push(key.clone(), value.clone());
+        }
+        report.copied.push(key.clone());
+    }
+    report
+}
+
+fn replace_pictures<S: OggPictureStorage>(
+    storage: &mut S,
+    pictures: &[lofty::picture::Picture],
+    report: &mut CopyReport,
+) -> Result<(), std::io::Error> {
+    if pictures.is_empty() {
+        return Ok(());
+    }
+
+    storage.remove_pictures();
+    for picture in pictures {
+        if let Err(err) = storage.insert_picture(picture.clone(), None) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                err.to_string(),
+            ));
+        }
+    }
+    report.pictures = pictures.len();
+    Ok(())
+}
+
+fn copy_to_ogg(
+    filepath: &String,
+    mut vorbis_comments: lofty::ogg::VorbisComments,
+    fields: &[(String, Vec<String>)],
+    pictures: &[lofty::picture::Picture],
+) -> Result<CopyReport, std::io::Error> {
+    let mut report = copy_comments(&mut vorbis_comments, fields);
+    replace_pictures(&mut vorbis_comments, pictures, &mut report)?;
+    save_native(&vorbis_comments, filepath)?;
+    Ok(report)
+}
+
+/// The generic key of a Type. Keys lofty does not know under their Vorbis name,
+/// such as TRACKCOUNT, are mapped here.
+fn item_key(t: crate::types::Type) -> Option<lofty::tag::ItemKey> {
+    match t {
+        crate::types::Type::Title => Some(lofty::tag::ItemKey::TrackTitle),
+        crate::types::Type::Artist => Some(lofty::tag::ItemKey::TrackArtist),
+        crate::types::Type::Album => Some(lofty::tag::ItemKey::AlbumTitle),
+        crate::types::Type::AlbumArtist => Some(lofty::tag::ItemKey::AlbumArtist),
+        crate::types::Type::Genre => Some(lofty::tag::ItemKey::Genre),
+        crate::types::Type::Date => Some(lofty::tag::ItemKey::RecordingDate),
+        crate::types::Type::Track => Some(lofty::tag::ItemKey::TrackNumber),
+        crate::types::Type::Disc => Some(lofty::tag::ItemKey::DiscNumber),
+        crate::types::Type::TrackCount => Some(lofty::tag::ItemKey::TrackTotal),
+        crate::types::Type::DiscCount => Some(lofty::tag::ItemKey::DiscTotal),
+        crate::types::Type::MusicBrainzTrackId => Some(lofty::tag::ItemKey::MusicBrainzRecordingId),
+        crate::types::Type::MusicBrainzAlbumId => Some(lofty::tag::ItemKey::MusicBrainzReleaseId),
+        crate::types::Type::MusicBrainzArtistId => Some(lofty::tag::ItemKey::MusicBrainzArtistId),
+        crate::types::Type::MusicBrainzAlbumArtistId => {
+            Some(lofty::tag::ItemKey::MusicBrainzReleaseArtistId)
+        }
+        crate::types::Type::MusicBrainzReleaseGroupId => {
+            Some(lofty::tag::ItemKey::MusicBrainzReleaseGroupId)
+        }
+        crate::types::Type::AcoustId => {
+            Some(lofty::tag::ItemKey::Unknown(String::from("ACOUSTID_ID")))
+        }
+    }
+}
+
+/// Writes the fields and pictures into `tag`. Fields that only fit an ID3v2 tag
+/// as TXXX frames are returned instead.
+fn fill_tag(
+    tag: &mut lofty::tag::Tag,
+    fields: &[(String, Vec<String>)],
+    pictures: &[lofty::picture::Picture],
+) -> (CopyReport, Vec<(String, Vec<String>)>) {
+    let tag_type = tag.tag_type();
+    let mut report = CopyReport::default();
+    let mut user_text = Vec::new();
+
+    for (key, values) in fields {
+        let item_key = match crate::types::access::get_type_from_str(key) {
+            Ok(t) => item_key(t),
+            Err(_) => None,
+        }
+        .unwrap_or_else(|| lofty::tag::ItemKey::from_key(lofty::tag::TagType::VorbisComments, key));
+        let representable = match item_key {
+            lofty::tag::ItemKey::Unknown(_) => match tag_type {
+                lofty::tag::TagType::VorbisComments | lofty::tag::TagType::Ape => {
+                    tag.remove_key(&item_key);
+                    for value in values {
+                        tag.push_unchecked(lofty::tag::TagItem::new(
+                            item_key.clone(),
+                            lofty::tag::ItemValue::Text(value.clone()),
+                        ));
+                    }
+                    true
+                }
+                lofty::tag::TagType::Id3v2 => {
+                    user_text.push((key.clone(), values.clone()));
+                    true
+                }
+                _ => false,
+            },
+            _ => {
+                let mut saved = tag.clone();
+                tag.remove_key(&item_key);
+                let pushed = values.iter().all(|value| {
+                    tag.push(lofty::tag::TagItem::new(
+                        item_key.clone(),
+                        lofty::tag::ItemValue::Text(value.clone()),
+                    ))
+                });
+                if !pushed {
+                    std::mem::swap(tag, &mut saved);
+                }
+                pushed
+            }
+        };
+
+        if representable {
+            report.copied.push(key.clone());
+        } else {
+            report.unrepresentable.push(key.clone());
+        }
+    }
+
+    if !pictures.is_empty() {
+        match tag_type {
+            lofty::tag::TagType::Id3v1
+            | lofty::tag::TagType::RiffInfo
+            | lofty::tag::TagType::AiffText => {
+                report.unrepresentable.push(String::from(PICTURES));
+            }
+            _ => {
+                while !tag.pictures().is_empty() {
+                    tag.remove_picture(0);
+                }
+                for picture in pictures {
+                    tag.push_picture(picture.clone());
+                }
+                report.pictures = pictures.len();
+            }
+        }
+    }
+
+    (report, user_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+    use lofty::file::TaggedFileExt;
+    use lofty::ogg::OggPictureStorage;
+    use lofty::tag::Accessor;
+
+    /// A short silent 16 bit mono WAV file
+    /// Ten silent MPEG-1 Layer III frames at 128 kbit/s and 44.1 kHz
+    fn mp3_data() -> Vec<u8> {
+        let mut data = Vec::new();
+        for _ in 0..10 {
+            let mut frame = vec![0u8; 417];
+            frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x64]);
+            data.extend_from_slice(&frame);
+        }
+        data
+    }
+
+    fn ogg_crc(data: &[u8]) -> u32 {
+        let mut crc = 0u32;
+        for byte in data {
+            crc ^= (*byte as u32) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    (crc << 1) ^ 0x04C1_1DB7
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        crc
+    }
+
+    fn ogg_page(header_type: u8, granule: u64, sequence: u32, packet: &[u8]) -> Vec<u8> {
+        let mut page = Vec::new();
+        page.extend_from_slice(b"OggS");
+        page.push(0);
+        page.push(header_type);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&1u32.to_le_bytes());
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&0u32.to_le_bytes());
+        let mut segments = vec![255u8; packet.len() / 255];
+        segments.push((packet.len() % 255) as u8);
+        page.push(segments.len() as u8);
+        page.extend_from_slice(&segments);
+        page.extend_from_slice(packet);
+        let crc = ogg_crc(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    /// A mono Opus stream holding one silent 20 ms frame
+    fn opus_data() -> Vec<u8> {
+        let mut head = Vec::new();
+        head.extend_from_slice(b"OpusHead");
+        head.push(1);
+        head.push(1);
+        head.extend_from_slice(&312u16.to_le_bytes());
+        head.extend_from_slice(&48000u32.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes());
+        head.push(0);
+
+        let vendor = b"icarus";
+        let mut tags = Vec::new();
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor);
+        tags.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut data = ogg_page(0x02, 0, 0, &head);
+        data.extend_from_slice(&ogg_page(0x00, 0, 1, &tags));
+        data.extend_from_slice(&ogg_page(0x04, 960 + 312, 2, &[0xF8, 0xFF, 0xFE]));
+        data
+    }
+
+    const ACOUSTID: &str = "4a6ff7d2-8d8e-4d0e-9b5e-3f1c1c0a2b3d";
+
+    /// A copy of the first test track with an AcoustID added
+    fn source_track(test_dir: &str) -> String {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(test_dir, &util::get_filename(1)).unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        crate::meta::metadata::import_comments(
+            &new_filepath,
+            format!("ACOUSTID_ID={ACOUSTID}\n").as_bytes(),
+            crate::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+        new_filepath
+    }
+
+    #[test]
+    fn test_copy_to_wav() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        crate::meta::metadata::import_comments(
+            &new_filepath,
+            b"UMID=0a1b\n",
+            crate::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+        let wav_filepath = util::get_full_path(&test_dir, "track01.wav").unwrap();
+        util::save_bytes_to_file(&util::wav_data(), &wav_filepath).unwrap();
+
+        let options = super::CopyOptions {
+            exclude: vec![String::from("genre")],
+            ..Default::default()
+        };
+        let report = super::copy_metadata(&new_filepath, &wav_filepath, &options).unwrap();
+        assert!(report.copied.contains(&String::from("TITLE")));
+        assert!(report.copied.contains(&String::from("UMID")));
+        assert!(!report.copied.contains(&String::from("GENRE")));
+        assert!(report.unrepresentable.is_empty(), "{report:?}");
+        assert_eq!(1, report.pictures);
+
+        let tagged_file = lofty::read_from_path(&wav_filepath).unwrap();
+        let tag = tagged_file.primary_tag().unwrap();
+        assert_eq!(lofty::tag::TagType::Id3v2, tag.tag_type());
+        assert_eq!(Some("Just roll it"), tag.title().as_deref());
+        assert_eq!(Some("KD"), tag.artist().as_deref());
+        assert_eq!(Some("Sample Tracks 3"), tag.album().as_deref());
+        assert_eq!(Some(1), tag.track());
+        assert_eq!(Some(3), tag.track_total());
+        assert_eq!(None, tag.genre());
+        assert_eq!(1, tag.pictures().len());
+        assert_eq!(
+            crate::meta::coverart::get_coverart(&filepath).unwrap(),
+            tag.pictures()[0].data()
+        );
+
+        let id3v2: lofty::id3::v2::Id3v2Tag = tag.clone().into();
+        assert_eq!(Some("0a1b"), id3v2.get_user_text("UMID"));
+    }
+
+    #[test]
+    fn test_copy_to_flac() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let other_filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(2)).unwrap();
+        let new_filepath = util::get_full_path(&test_dir, &util::get_filename(2)).unwrap();
+        util::copy_file(&other_filepath, &new_filepath).unwrap();
+        crate::meta::coverart::remove_coverart(&new_filepath).unwrap();
+
+        let options = super::CopyOptions {
+            include: vec![String::from("TITLE"), String::from("TRACKNUMBER")],
+            ..Default::default()
+        };
+        let report = super::copy_metadata(&filepath, &new_filepath, &options).unwrap();
+        assert_eq!(
+            vec![String::from("TITLE"), String::from("TRACKNUMBER")],
+            report.copied
+        );
+
+        let song_metadata = crate::meta::metadata::get_all_meta(&new_filepath).unwrap();
+        assert_eq!(Some(String::from("Just roll it")), song_metadata.title);
+        assert_eq!(Some(1), song_metadata.track);
+        assert_eq!(Some(String::from("Metal")), song_metadata.genre);
+        assert_eq!(1, song_metadata.pictures.len());
+    }
+
+    #[test]
+    fn test_copy_to_mp3() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = source_track(&test_dir);
+        let mp3_filepath = util::get_full_path(&test_dir, "track01.mp3").unwrap();
+        util::save_bytes_to_file(&mp3_data(), &mp3_filepath).unwrap();
+        let lrc = crate::lyrics::parse_lrc("[00:01.00]Just roll it\n").unwrap();
+        crate::lyrics::set_id3_synced_lyrics(&mp3_filepath, &lrc).unwrap();
+
+        let report =
+            super::copy_metadata(&filepath, &mp3_filepath, &super::CopyOptions::default()).unwrap();
+        assert!(report.copied.contains(&String::from("ACOUSTID_ID")));
+        assert!(report.unrepresentable.is_empty(), "{report:?}");
+        assert_eq!(1, report.pictures);
+
+        let id3v2 = crate::lyrics::read_id3v2(&mp3_filepath).unwrap().unwrap();
+        assert_eq!(Some("Just roll it"), id3v2.title().as_deref());
+        assert_eq!(Some("Metal"), id3v2.genre().as_deref());
+        assert_eq!(Some(ACOUSTID), id3v2.get_user_text("ACOUSTID_ID"));
+        assert_eq!(
+            Some(lrc),
+            crate::lyrics::get_id3_synced_lyrics(&mp3_filepath).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_copy_to_opus() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = source_track(&test_dir);
+        let opus_filepath = util::get_full_path(&test_dir, "track01.opus").unwrap();
+        util::save_bytes_to_file(&opus_data(), &opus_filepath).unwrap();
+
+        let report =
+            super::copy_metadata(&filepath, &opus_filepath, &super::CopyOptions::default())
+                .unwrap();
+        assert!(report.copied.contains(&String::from("ACOUSTID_ID")));
+        assert!(report.unrepresentable.is_empty(), "{report:?}");
+        assert_eq!(1, report.pictures);
+
+        let opus_file: lofty::ogg::OpusFile = super::read_native(&opus_filepath).unwrap();
+        let vorbis_comments = opus_file.vorbis_comments();
+        assert_eq!("icarus", vorbis_comments.vendor());
+        assert_eq!(Some("Just roll it"), vorbis_comments.get("TITLE"));
+        assert_eq!(Some("Lavf61.7.100"), vorbis_comments.get("ENCODER"));
+        assert_eq!(Some(ACOUSTID), vorbis_comments.get("ACOUSTID_ID"));
+        assert_eq!(1, vorbis_comments.pictures().len());
+        assert_eq!(
+            crate::meta::coverart::get_coverart(&filepath).unwrap(),
+            vorbis_comments.pictures()[0].0.data()
+        );
+    }
+
+    #[test]
+    fn test_unrepresentable() {
+        let fields = vec![
+            (String::from("TITLE"), vec![String::from("Closer")]),
+            (String::from("UMID"), vec![String::from("0a1b")]),
+        ];
+        let picture = lofty::picture::Picture::new_unchecked(
+            lofty::picture::PictureType::CoverFront,
+            Some(lofty::picture::MimeType::Png),
+            None,
+            vec![0x89, b'P', b'N', b'G'],
+        );
+
+        let mut tag = lofty::tag::Tag::new(lofty::tag::TagType::RiffInfo);
+        let (report, user_text) =
+            super::fill_tag(&mut tag, &fields, std::slice::from_ref(&picture));
+        assert_eq!(vec![String::from("TITLE")], report.copied);
+        assert_eq!(
+            vec![String::from("UMID"), String::from(super::PICTURES)],
+            report.unrepresentable
+        );
+        assert_eq!(0, report.pictures);
+        assert!(user_text.is_empty());
+
+        let mut tag = lofty::tag::Tag::new(lofty::tag::TagType::Id3v2);
+        let (report, user_text) = super::fill_tag(&mut tag, &fields, &[picture]);
+        assert!(report.unrepresentable.is_empty());
+        assert_eq!(vec![fields[1].clone()], user_text);
+        assert_eq!(1, tag.pictures().len());
+    }
+}
@@ -0,0 +1,793 @@
+//! Read and write cue sheets in the .cue text format and convert them to and from
+//! FLAC CUESHEET blocks
+
+use crate::flac;
+
+/// CD frames per second, the unit of INDEX times
+pub const FRAMES_PER_SECOND: u64 = 75;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CueFile {
+    pub catalog: Option<String>,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    /// The audio file the sheet describes, as written after FILE
+    pub file: Option<String>,
+    /// REM lines such as GENRE and DATE, with uppercased keys
+    pub remarks: Vec<(String, String)>,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CueTrack {
+    pub number: u8,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub isrc: Option<String>,
+    pub is_audio: bool,
+    pub pre_emphasis: bool,
+    pub indices: Vec<CueIndex>,
+}
+
+impl CueTrack {
+    pub fn new(number: u8) -> CueTrack {
+        CueTrack {
+            number,
+            title: None,
+            performer: None,
+            isrc: None,
+            is_audio: true,
+            pre_emphasis: false,
+            indices: Vec::new(),
+        }
+    }
+
+    /// Time of INDEX 01, where the track starts playing
+    pub fn start(&self) -> Option<u64> {
+        self.indices
+            .iter()
+            .find(|index| index.number == 1)
+            .map(|index| index.frames)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CueIndex {
+    pub number: u8,
+    /// CD frames from the start of the file
+    pub frames: u64,
+}
+
+fn invalid(line: usize, message: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Line {line}: {message}"),
+    )
+}
+
+/// Splits a line into words, keeping quoted text together without the quotes
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    let mut in_token = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_token = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut token));
+                    in_token = false;
+                }
+            }
+            c => {
+                token.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// Parses `mm:ss:ff` into CD frames
+pub fn parse_time(time: &str) -> Result<u64, std::io::Error> {
+    let parts: Vec<&str> = time.split(':').collect();
+    let numbers: Vec<u64> = parts
+        .iter()
+        .filter_map(|part| part.parse::<u64>().ok())
+        .collect();
+
+    match numbers.as_slice() {
+        [minutes, seconds, frames] if parts.len() == 3 && *seconds < 60 && *frames < 75 => {
+            Ok((minutes * 60 + seconds) * FRAMES_PER_SECOND + frames)
+        }
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid cue time: {time:?}"),
+        )),
+    }
+}
+
+pub fn format_time(frames: u64) -> String {
+    let seconds = frames / FRAMES_PER_SECOND;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 60,
+        seconds % 60,
+        frames % FRAMES_PER_SECOND
+    )
+}
+
+/// Parses the text of a .cue file. Only sheets describing a single file are
+/// supported. Commands other than REM, CATALOG, TITLE, PERFORMER, FILE, TRACK,
+/// ISRC, FLAGS and INDEX are ignored.
+pub fn parse(text: &str) -> Result<CueFile, std::io::Error> {
+    let mut cue_file = CueFile::default();
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let tokens = tokenize(line);
+        let Some(command) = tokens.first() else {
+            continue;
+        };
+        let argument = |index: usize| -> Result<String, std::io::Error> {
+            match tokens.get(index) {
+                Some(token) => Ok(token.clone()),
+                None => Err(invalid(number, &format!("{command} is missing a value"))),
+            }
+        };
+
+        match command.to_uppercase().as_str() {
+            "REM" => {
+                if let Some(key) = tokens.get(1) {
+                    cue_file
+                        .remarks
+                        .push((key.to_uppercase(), tokens[2..].join(" ")));
+                }
+            }
+            "CATALOG" => cue_file.catalog = Some(argument(1)?),
+            "FILE" => {
+                if cue_file.file.is_some() {
+                    return Err(invalid(number, "Only one FILE is supported"));
+                }
+                cue_file.file = Some(argument(1)?);
+            }
+            "TRACK" => {
+                let track_number = match argument(1)?.parse::<u8>() {
+                    Ok(track_number) if (1..=99).contains(&track_number) => track_number,
+                    _ => return Err(invalid(number, "Track numbers go from 1 to 99")),
+                };
+                let mut track = CueTrack::new(track_number);
+                track.is_audio = argument(2)?.eq_ignore_ascii_case("AUDIO");
+                cue_file.tracks.push(track);
+            }
+            "TITLE" | "PERFORMER" | "ISRC" | "FLAGS" | "INDEX" => {
+                let value = argument(1)?;
+                let track = cue_file.tracks.last_mut();
+                match (command.to_uppercase().as_str(), track) {
+                    ("TITLE", Some(track)) => track.title = Some(value),
+                    ("TITLE", None) => cue_file.title = Some(value),
+                    ("PERFORMER", Some(track)) => track.performer = Some(value),
+                    ("PERFORMER", None) => cue_file.performer = Some(value),
+                    ("ISRC", Some(track)) => track.isrc = Some(value),
+                    ("FLAGS", Some(track)) => {
+                        track.pre_emphasis = tokens[1..]
+                            .iter()
+                            .any(|flag| flag.eq_ignore_ascii_case("PRE"))
+                    }
+                    ("INDEX", Some(track)) => {
+                        let index_number = match value.parse::<u8>() {
+                            Ok(index_number) if index_number <= 99 => index_number,
+                            _ => return Err(invalid(number, "Index numbers go from 0 to 99")),
+                        };
+                        let frames = match parse_time(&argument(2)?) {
+                            Ok(frames) => frames,
+                            Err(err) => return Err(invalid(number, &err.to_string())),
+                        };
+                        track.indices.push(CueIndex {
+                            number: index_number,
+                            frames,
+                        });
+                    }
+                    (command, None) => {
+                        return Err(invalid(number, &format!("{command} outside of a TRACK")));
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for track in &cue_file.tracks {
+        if track.start().is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Track {} has no INDEX 01", track.number),
+            ));
+        }
+    }
+
+    Ok(cue_file)
+}
+
+/// Reads a .cue file, as Latin-1 when it is not valid UTF-8
+pub fn read_cue_file(filepath: &String) -> Result<CueFile, std::io::Error> {
+    match String::from_utf8(std::fs::read(filepath)?) {
+        Ok(text) => parse(&text),
+        Err(err) => parse(
+            &err.into_bytes()
+                .iter()
+                .map(|b| *b as char)
+                .collect::<String>(),
+        ),
+    }
+}
+
+pub fn write_cue_file(filepath: &String, cue_file: &CueFile) -> Result<(), std::io::Error> {
+    std::fs::write(filepath, cue_file.to_string())
+}
+
+/// Converts to a CUESHEET block for a stream with the given sample rate and
+/// length. Track offsets are those of their first index. 44.1 kHz streams are
+/// marked as CDs, with the standard two second lead-in.
+pub fn to_cue_sheet(
+    cue_file: &CueFile,
+    sample_rate: u32,
+    total_samples: u64,
+) -> Result<flac::CueSheet, std::io::Error> {
+    let is_cd = sample_rate == 44100;
+    let to_samples = |frames: u64| frames * u64::from(sample_rate) / FRAMES_PER_SECOND;
+
+    let mut tracks = Vec::new();
+    for track in &cue_file.tracks {
+        let offset = match track.indices.iter().map(|index| index.frames).min() {
+            Some(frames) => to_samples(frames),
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Track {} has no indices", track.number),
+                ));
+            }
+        };
+        if offset > total_samples {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Track {} starts after the end of the audio", track.number),
+            ));
+        }
+        tracks.push(flac::CueSheetTrack {
+            offset,
+            number: track.number,
+            isrc: track.isrc.clone().unwrap_or_default(),
+            is_audio: track.is_audio,
+            pre_emphasis: track.pre_emphasis,
+            indices: track
+                .indices
+                .iter()
+                .map(|index| flac::CueSheetIndex {
+                    offset: to_samples(index.frames) - offset,
+                    number: index.number,
+                })
+                .collect(),
+        });
+    }
+    tracks.push(flac::CueSheetTrack {
+        offset: total_samples,
+        number: if is_cd { 170 } else { 255 },
+        isrc: String::new(),
+        is_audio: true,
+        pre_emphasis: false,
+        indices: Vec::new(),
+    });
+
+    Ok(flac::CueSheet {
+        media_catalog_number: cue_file.catalog.clone().unwrap_or_default(),
+        lead_in_samples: if is_cd { 88200 } else { 0 },
+        is_cd,
+        tracks,
+    })
+}
+
+/// Converts a CUESHEET block into a .cue file for `file`. The block has no titles
+/// or performers, so those are left empty.
+pub fn from_cue_sheet(cue_sheet: &flac::CueSheet, sample_rate: u32, file: &str) -> CueFile {
+    let to_frames = |samples: u64| samples * FRAMES_PER_SECOND / u64::from(sample_rate);
+
+    CueFile {
+        catalog: if cue_sheet.media_catalog_number.is_empty() {
+            None
+        } else {
+            Some(cue_sheet.media_catalog_number.clone())
+        },
+        file: Some(String::from(file)),
+        tracks: cue_sheet
+            .tracks
+            .iter()
+            .filter(|track| track.number != 170 && track.number != 255)
+            .map(|track| CueTrack {
+                isrc: if track.isrc.is_empty() {
+                    None
+                } else {
+                    Some(track.isrc.clone())
+                },
+                is_audio: track.is_audio,
+                pre_emphasis: track.pre_emphasis,
+                indices: track
+                    .indices
+                    .iter()
+                    .map(|index| CueIndex {
+                        number: index.number,
+                        frames: to_frames(track.offset + index.offset),
+                    })
+                    .collect(),
+                ..CueTrack::new(track.number)
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Embeds the .cue file at `cue_filepath` into the song as a CUESHEET block
+pub fn import_cue(
+    song_filepath: &String,
+    cue_filepath: &String,
+    options: &flac::WriteOptions,
+) -> Result<flac::WriteReport, std::io::Error> {
+    let cue_file = read_cue_file(cue_filepath)?;
+    let stream_info = flac::read_stream_info(song_filepath)?;
+    let cue_sheet = to_cue_sheet(
+        &cue_file,
+        stream_info.sample_rate,
+        stream_info.total_samples,
+    )?;
+    flac::write_cue_sheet(song_filepath, &cue_sheet, options)
+}
+
+/// The CUESHEET block of the song as a .cue file referring to the song, if it has
+/// one
+pub fn export_cue(song_filepath: &String) -> Result<Option<CueFile>, std::io::Error> {
+    let file = match std::path::Path::new(song_filepath).file_name() {
+        Some(file) => file.to_string_lossy().into_owned(),
+        None => song_filepath.clone(),
+    };
+
+    match flac::read_cue_sheet(song_filepath)? {
+        Some(cue_sheet) => {
+            let stream_info = flac::read_stream_info(song_filepath)?;
+            Ok(Some(from_cue_sheet(
+                &cue_sheet,
+                stream_info.sample_rate,
+                &file,
+            )))
+        }
+        None => Ok(None),
+    }
+}
+
+/// A track of a single-file album, described by the cue sheet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VirtualTrack {
+    pub number: u8,
+    pub title: Option<String>,
+    /// The performer of the track, or of the album when the track has none
+    pub artist: Option<String>,
+    /// First sample, at INDEX 01
+    pub start_sample: u64,
+    /// Sample after the last one. Gaps before the next track's INDEX 01 belong to
+    /// this track.
+    pub end_sample: u64,
+    pub duration: std::time::Duration,
+}
+
+/// The audio tracks of the cue sheet, given the properties of the file it
+/// describes
+pub fn virtual_tracks(
+    cue_file: &CueFile,
+    properties: &crate::properties::SongProperties,
+) -> Result<Vec<VirtualTrack>, std::io::Error> {
+    let total_samples = (properties.duration.as_nanos() * u128::from(properties.sample_rate)
+        / 1_000_000_000) as u64;
+    tracks_from(cue_file, properties.sample_rate, total_samples)
+}
+
+/// The tracks of a single-file album. The cue sheet is read from `cue_filepath`
+/// when given, otherwise from a CUESHEET comment holding .cue text and then from
+/// the CUESHEET block, which has no titles. Sample positions come from STREAMINFO.
+pub fn virtual_tracks_from_song(
+    song_filepath: &String,
+    cue_filepath: Option<&String>,
+) -> Result<Vec<VirtualTrack>, std::io::Error> {
+    let cue_file = load_cue(song_filepath, cue_filepath)?;
+    let stream_info = flac::read_stream_info(song_filepath)?;
+    if stream_info.total_samples == 0 {
+        let properties = crate::properties::get_song_properties(song_filepath)?;
+        virtual_tracks(&cue_file, &properties)
+    } else {
+        tracks_from(
+            &cue_file,
+            stream_info.sample_rate,
+            stream_info.total_samples,
+        )
+    }
+}
+
+/// The cue sheet at `cue_filepath`, or the one embedded in the song
+pub(crate) fn load_cue(
+    song_filepath: &String,
+    cue_filepath: Option<&String>,
+) -> Result<CueFile, std::io::Error> {
+    match cue_filepath {
+        Some(cue_filepath) => read_cue_file(cue_filepath),
+        None => match crate::meta::metadata::get_comments(song_filepath, "CUESHEET")?.first() {
+            Some(text) => parse(text),
+            None => match export_cue(song_filepath)? {
+                Some(cue_file) => Ok(cue_file),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "The song has no cue sheet",
+                )),
+            },
+        },
+    }
+}
+
+pub(crate) fn tracks_from(
+    cue_file: &CueFile,
+    sample_rate: u32,
+    total_samples: u64,
+) -> Result<Vec<VirtualTrack>, std::io::Error> {
+    let to_samples = |frames: u64| frames * u64::from(sample_rate) / FRAMES_PER_SECOND;
+    let audio_tracks: Vec<&CueTrack> = cue_file
+        .tracks
+        .iter()
+        .filter(|track| track.is_audio)
+        .collect();
+
+    let mut tracks = Vec::new();
+    for (position, track) in audio_tracks.iter().enumerate() {
+        let start_sample = match track.start() {
+            Some(frames) => to_samples(frames),
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Track {} has no INDEX 01", track.number),
+                ));
+            }
+        };
+        let end_sample = match audio_tracks.get(position + 1).and_then(|next| next.start()) {
+            Some(frames) => to_samples(frames),
+            None => total_samples,
+        };
+        if end_sample <= start_sample {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Track {} starts at sample {start_sample}, after its end at sample {end_sample}",
+                    track.number
+                ),
+            ));
+        }
+
+        tracks.push(VirtualTrack {
+            number: track.number,
+            title: track.title.clone(),
+            artist: track.performer.clone().or(cue_file.performer.clone()),
+            start_sample,
+            end_sample,
+            duration: std::time::Duration::from_nanos(
+                ((end_sample - start_sample) as u128 * 1_000_000_000 / u128::from(sample_rate))
+                    as u64,
+            ),
+        });
+    }
+
+    Ok(tracks)
+}
+
+/// Puts the value in double quotes. The format has no escapes, so double quotes in
+/// the value become single quotes.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'"))
+}
+
+/// Writes the sheet in the .cue format
+impl std::fmt::Display for CueFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in &self.remarks {
+            writeln!(f, "REM {key} {value}")?;
+        }
+        if let Some(catalog) = &self.catalog {
+            writeln!(f, "CATALOG {catalog}")?;
+        }
+        if let Some(performer) = &self.performer {
+            writeln!(f, "PERFORMER {}", quote(performer))?;
+        }
+        if let Some(title) = &self.title {
+            writeln!(f, "TITLE {}", quote(title))?;
+        }
+        if let Some(file) = &self.file {
+            writeln!(f, "FILE {} WAVE", quote(file))?;
+        }
+
+        for track in &self.tracks {
+            let mode = if track.is_audio {
+                "AUDIO"
+            } else {
+                "MODE1/2352"
+            };
+            writeln!(f, "  TRACK {:02} {mode}", track.number)?;
+            if let Some(title) = &track.title {
+                writeln!(f, "    TITLE {}", quote(title))?;
+            }
+            if let Some(performer) = &track.performer {
+                writeln!(f, "    PERFORMER {}", quote(performer))?;
+            }
+            if let Some(isrc) = &track.isrc {
+                writeln!(f, "    ISRC {isrc}")?;
+            }
+            if track.pre_emphasis {
+                writeln!(f, "    FLAGS PRE")?;
+            }
+            for index in &track.indices {
+                writeln!(
+                    f,
+                    "    INDEX {:02} {}",
+                    index.number,
+                    format_time(index.frames)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    const SAMPLE_CUE: &str = "\u{feff}REM GENRE Metal
+REM DATE 2025
+PERFORMER \"KD\"
+TITLE \"Sample Tracks 3\"
+FILE \"track01.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"Just roll it\"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE \"Here we go!\"
+    PERFORMER \"KD & Friends\"
+    ISRC USABC2500002
+    INDEX 01 00:10:00
+  TRACK 03 AUDIO
+    TITLE \"Closer\"
+    FLAGS DCP PRE
+    INDEX 00 00:24:00
+    INDEX 01 00:25:37
+";
+
+    #[test]
+    fn test_parse() {
+        let cue_file = super::parse(SAMPLE_CUE).unwrap();
+        assert_eq!(Some(String::from("Sample Tracks 3")), cue_file.title);
+        assert_eq!(Some(String::from("KD")), cue_file.performer);
+        assert_eq!(Some(String::from("track01.flac")), cue_file.file);
+        assert_eq!(
+            vec![
+                (String::from("GENRE"), String::from("Metal")),
+                (String::from("DATE"), String::from("2025"))
+            ],
+            cue_file.remarks
+        );
+        assert_eq!(3, cue_file.tracks.len());
+        assert_eq!(
+            Some(String::from("KD & Friends")),
+            cue_file.tracks[1].performer
+        );
+        assert_eq!(Some(750), cue_file.tracks[1].start());
+        assert!(cue_file.tracks[2].pre_emphasis);
+        assert_eq!(Some(25 * 75 + 37), cue_file.tracks[2].start());
+
+        assert_eq!(cue_file, super::parse(&cue_file.to_string()).unwrap());
+
+        let mut quoted = cue_file.clone();
+        quoted.title = Some(String::from("The \"Best\" Of"));
+        quoted.tracks[0].performer = Some(String::from("\"Weird\" Al"));
+        let parsed = super::parse(&quoted.to_string()).unwrap();
+        assert_eq!(Some(String::from("The 'Best' Of")), parsed.title);
+        assert_eq!(Some(String::from("'Weird' Al")), parsed.tracks[0].performer);
+
+        for text in [
+            "TRACK 01 AUDIO\n",
+            "INDEX 01 00:00:00\n",
+            "FILE a.wav WAVE\nTRACK 01 AUDIO\nINDEX 01 00:61:00\n",
+            "FILE a.wav WAVE\nFILE b.wav WAVE\n",
+        ] {
+            assert_eq!(
+                std::io::ErrorKind::InvalidData,
+                super::parse(text).unwrap_err().kind(),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cue_sheet_conversion() {
+        let cue_file = super::parse(SAMPLE_CUE).unwrap();
+        let cue_sheet = super::to_cue_sheet(&cue_file, 44100, 41 * 44100).unwrap();
+        assert!(cue_sheet.is_cd);
+        assert_eq!(88200, cue_sheet.lead_in_samples);
+        let offsets: Vec<(u8, u64)> = cue_sheet
+            .tracks
+            .iter()
+            .map(|track| (track.number, track.offset))
+            .collect();
+        assert_eq!(
+            vec![(1, 0), (2, 441000), (3, 24 * 44100), (170, 41 * 44100)],
+            offsets
+        );
+        assert_eq!(
+            vec![0, (75 + 37) * 588],
+            cue_sheet.tracks[2]
+                .indices
+                .iter()
+                .map(|index| index.offset)
+                .collect::<Vec<u64>>()
+        );
+
+        let data = crate::flac::encode_cue_sheet(&cue_sheet).unwrap();
+        assert_eq!(
+            crate::flac::Block::CueSheet(cue_sheet.clone()),
+            crate::flac::decode(crate::flac::BlockType::CueSheet, &data).unwrap()
+        );
+
+        let converted = super::from_cue_sheet(&cue_sheet, 44100, "track01.flac");
+        assert_eq!(cue_file.tracks.len(), converted.tracks.len());
+        for (track, converted_track) in cue_file.tracks.iter().zip(&converted.tracks) {
+            assert_eq!(track.indices, converted_track.indices);
+            assert_eq!(track.isrc, converted_track.isrc);
+        }
+    }
+
+    #[test]
+    fn test_import_export() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        let cue_filepath = util::get_full_path(&test_dir, "album.cue").unwrap();
+        super::write_cue_file(&cue_filepath, &super::parse(SAMPLE_CUE).unwrap()).unwrap();
+        let latin1_filepath = util::get_full_path(&test_dir, "latin1.cue").unwrap();
+        std::fs::write(&latin1_filepath, b"TITLE \"Caf\xE9\"\n").unwrap();
+        assert_eq!(
+            Some(String::from("Caf\u{e9}")),
+            super::read_cue_file(&latin1_filepath).unwrap().title
+        );
+
+        assert_eq!(None, super::export_cue(&new_filepath).unwrap());
+        super::import_cue(
+            &new_filepath,
+            &cue_filepath,
+            &crate::flac::WriteOptions::default(),
+        )
+        .unwrap();
+
+        let cue_sheet = crate::flac::read_cue_sheet(&new_filepath).unwrap().unwrap();
+        assert!(!cue_sheet.is_cd);
+        assert_eq!(255, cue_sheet.tracks.last().unwrap().number);
+        assert_eq!(480000, cue_sheet.tracks[1].offset);
+
+        let exported = super::export_cue(&new_filepath).unwrap().unwrap();
+        assert_eq!(Some(String::from("track01.flac")), exported.file);
+        assert_eq!(Some(750), exported.tracks[1].start());
+        assert_eq!(
+            Some(String::from("Just roll it")),
+            crate::meta::metadata::get_all_meta(&new_filepath)
+                .unwrap()
+                .title
+        );
+        assert!(crate::strip::verify_audio(&filepath, &new_filepath).unwrap());
+
+        let (removed, _) =
+            crate::flac::remove_cue_sheet(&new_filepath, &crate::flac::WriteOptions::default())
+                .unwrap();
+        assert!(removed);
+        assert_eq!(None, crate::flac::read_cue_sheet(&new_filepath).unwrap());
+    }
+
+    #[test]
+    fn test_virtual_tracks() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let properties = crate::properties::get_song_properties(&filepath).unwrap();
+        let cue_file = super::parse(SAMPLE_CUE).unwrap();
+
+        let tracks = super::virtual_tracks(&cue_file, &properties).unwrap();
+        assert_eq!(3, tracks.len());
+        assert_eq!(
+            super::VirtualTrack {
+                number: 2,
+                title: Some(String::from("Here we go!")),
+                artist: Some(String::from("KD & Friends")),
+                start_sample: 480000,
+                end_sample: (25 * 75 + 37) * 640,
+                duration: std::time::Duration::from_nanos(15_493_333_333),
+            },
+            tracks[1]
+        );
+        assert_eq!(Some(String::from("KD")), tracks[0].artist);
+        assert_eq!(tracks[1].end_sample, tracks[2].start_sample);
+
+        let mut late = cue_file.clone();
+        late.tracks[2].indices[1].frames = 60 * 75;
+        assert_eq!(
+            std::io::ErrorKind::InvalidData,
+            super::virtual_tracks(&late, &properties)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn test_virtual_tracks_from_song() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        let cue_filepath = util::get_full_path(&test_dir, "album.cue").unwrap();
+        super::write_cue_file(&cue_filepath, &super::parse(SAMPLE_CUE).unwrap()).unwrap();
+
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            super::virtual_tracks_from_song(&new_filepath, None)
+                .unwrap_err()
+                .kind()
+        );
+
+        let external = super::virtual_tracks_from_song(&new_filepath, Some(&cue_filepath)).unwrap();
+        let total_samples = crate::flac::read_stream_info(&new_filepath)
+            .unwrap()
+            .total_samples;
+        assert_eq!(total_samples, external[2].end_sample);
+
+        // Without a CUESHEET comment the block is used, which has no titles
+        super::import_cue(
+            &new_filepath,
+            &cue_filepath,
+            &crate::flac::WriteOptions::default(),
+        )
+        .unwrap();
+        let embedded = super::virtual_tracks_from_song(&new_filepath, None).unwrap();
+        assert_eq!(None, embedded[0].title);
+        let samples = |tracks: &[super::VirtualTrack]| -> Vec<(u64, u64)> {
+            tracks
+                .iter()
+                .map(|track| (track.start_sample, track.end_sample))
+                .collect()
+        };
+        assert_eq!(samples(&external), samples(&embedded));
+
+        crate::meta::metadata::import_comments(
+            &new_filepath,
+            format!("CUESHEET={SAMPLE_CUE}").as_bytes(),
+            crate::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+        let commented = super::virtual_tracks_from_song(&new_filepath, None).unwrap();
+        assert_eq!(external, commented);
+    }
+}
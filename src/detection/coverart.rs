@@ -45,6 +45,7 @@ pub mod constants {
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     #[test]
     fn test_coverart_file_type() {
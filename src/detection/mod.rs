@@ -2,6 +2,7 @@ pub mod coverart;
 pub mod song;
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FileType {
     pub mime: String,
     pub file_type: String,
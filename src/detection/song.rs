@@ -46,6 +46,7 @@ pub mod constants {
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     #[test]
     fn test_song_file_type() {
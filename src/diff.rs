@@ -0,0 +1,440 @@
+//! Compare the tags and pictures of two songs, or of a song and a wanted state
+
+use crate::types;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "change", rename_all = "snake_case")
+)]
+pub enum Change {
+    /// A field only the second side has. Fields are named by their Vorbis key.
+    Added {
+        field: String,
+        values: Vec<String>,
+    },
+    /// A field only the first side has
+    Removed {
+        field: String,
+        values: Vec<String>,
+    },
+    Changed {
+        field: String,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+    PictureAdded(types::PictureInfo),
+    PictureRemoved(types::PictureInfo),
+    /// A picture of the same type with different data
+    PictureChanged {
+        old: types::PictureInfo,
+        new: types::PictureInfo,
+    },
+}
+
+/// The changes from one side to the other, known fields first, then custom fields
+/// by key, then pictures
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Every value of each Vorbis key, keys in upper case
+type Comments = std::collections::BTreeMap<String, Vec<String>>;
+
+/// Changes from the song at `a` to the song at `b`. The comments are compared
+/// as stored, every value of every key included.
+pub fn diff(a: &String, b: &String) -> Result<Diff, std::io::Error> {
+    match read_comments(a) {
+        Ok((old, old_pictures)) => match read_comments(b) {
+            Ok((new, new_pictures)) => {
+                let mut changes = Vec::new();
+                diff_comments(&mut changes, &old, &new);
+                diff_pictures(&mut changes, &old_pictures, &new_pictures);
+                Ok(Diff { changes })
+            }
+            Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Changes that writing `target` would make to the song
+pub fn diff_against(
+    filepath: &String,
+    target: &types::SongMetadata,
+) -> Result<Diff, std::io::Error> {
+    match crate::meta::metadata::get_all_meta(filepath) {
+        Ok(current) => Ok(diff_metadata(&current, target)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Compares the comments the SongMetadata stand for: each field under its key,
+/// followed by the `custom` values of the same key
+pub fn diff_metadata(old: &types::SongMetadata, new: &types::SongMetadata) -> Diff {
+    let mut changes = Vec::new();
+    diff_comments(
+        &mut changes,
+        &comments_from_metadata(old),
+        &comments_from_metadata(new),
+    );
+    diff_pictures(&mut changes, &old.pictures, &new.pictures);
+
+    Diff { changes }
+}
+
+fn read_comments(filepath: &String) -> Result<(Comments, Vec<types::PictureInfo>), std::io::Error> {
+    match std::fs::File::open(filepath) {
+        Ok(mut content) => {
+            match <lofty::flac::FlacFile as lofty::file::AudioFile>::read_from(
+                &mut content,
+                lofty::config::ParseOptions::new().read_properties(false),
+            ) {
+                Ok(flac_file) => {
+                    let mut comments = Comments::new();
+                    if let Some(vb) = flac_file.vorbis_comments() {
+                        for (key, value) in vb.items() {
+                            comments
+                                .entry(key.to_uppercase())
+                                .or_default()
+                                .push(value.to_owned());
+                        }
+                    }
+                    let pictures =
+                        crate::meta::metadata::song_metadata_from_file(&flac_file).pictures;
+                    Ok((comments, pictures))
+                }
+                Err(err) => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    err.to_string(),
+                )),
+            }
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn comments_from_metadata(song_metadata: &types::SongMetadata) -> Comments {
+    let mut comments = Comments::new();
+    for t in types::all_metadata_types() {
+        if let Some(value) = song_metadata.get(t) {
+            comments
+                .entry(types::access::get_type(t).unwrap())
+                .or_default()
+                .push(crate::meta::metadata::parse_value(value));
+        }
+    }
+    for (key, values) in &song_metadata.custom {
+        comments
+            .entry(key.to_uppercase())
+            .or_default()
+            .extend(values.iter().cloned());
+    }
+
+    comments
+}
+
+/// Compares the keys of the known fields in field order, then the other keys
+fn diff_comments(changes: &mut Vec<Change>, old: &Comments, new: &Comments) {
+    let mut keys: Vec<String> = Vec::new();
+    for t in types::all_metadata_types() {
+        keys.push(types::access::get_type(t).unwrap());
+        keys.extend(types::access::get_fallback_types(t));
+    }
+    let mut custom_keys: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| !keys.contains(key))
+        .cloned()
+        .collect();
+    custom_keys.sort();
+    custom_keys.dedup();
+    keys.extend(custom_keys);
+
+    for key in keys {
+        let old_values = old.get(&key).cloned().unwrap_or_default();
+        let new_values = new.get(&key).cloned().unwrap_or_default();
+        push_change(changes, key, old_values, new_values);
+    }
+}
+
+fn push_change(changes: &mut Vec<Change>, field: String, old: Vec<String>, new: Vec<String>) {
+    if old == new {
+        return;
+    }
+
+    changes.push(if old.is_empty() {
+        Change::Added { field, values: new }
+    } else if new.is_empty() {
+        Change::Removed { field, values: old }
+    } else {
+        Change::Changed { field, old, new }
+    });
+}
+
+/// Pairs the pictures of each type in order and compares them by hash
+fn diff_pictures(
+    changes: &mut Vec<Change>,
+    old: &[types::PictureInfo],
+    new: &[types::PictureInfo],
+) {
+    let mut picture_types: Vec<u8> = old.iter().chain(new).map(|pic| pic.picture_type).collect();
+    picture_types.sort();
+    picture_types.dedup();
+
+    for picture_type in picture_types {
+        let old_pictures: Vec<&types::PictureInfo> = old
+            .iter()
+            .filter(|pic| pic.picture_type == picture_type)
+            .collect();
+        let new_pictures: Vec<&types::PictureInfo> = new
+            .iter()
+            .filter(|pic| pic.picture_type == picture_type)
+            .collect();
+
+        for index in 0..old_pictures.len().max(new_pictures.len()) {
+            match (old_pictures.get(index), new_pictures.get(index)) {
+                (Some(old_pic), Some(new_pic)) => {
+                    if old_pic.hash != new_pic.hash {
+                        changes.push(Change::PictureChanged {
+                            old: (*old_pic).clone(),
+                            new: (*new_pic).clone(),
+                        });
+                    }
+                }
+                (Some(old_pic), None) => changes.push(Change::PictureRemoved((*old_pic).clone())),
+                (None, Some(new_pic)) => changes.push(Change::PictureAdded((*new_pic).clone())),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+fn describe_values(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| format!("{value:?}"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn describe_picture(pic: &types::PictureInfo) -> String {
+    let hash = match pic.hash.get(..12) {
+        Some(short) => short,
+        None => pic.hash.as_str(),
+    };
+    format!(
+        "type {} {} {}x{} {hash}",
+        pic.picture_type, pic.mime, pic.width, pic.height
+    )
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added { field, values } => write!(f, "+ {field}: {}", describe_values(values)),
+            Change::Removed { field, values } => {
+                write!(f, "- {field}: {}", describe_values(values))
+            }
+            Change::Changed { field, old, new } => write!(
+                f,
+                "~ {field}: {} -> {}",
+                describe_values(old),
+                describe_values(new)
+            ),
+            Change::PictureAdded(pic) => write!(f, "+ picture {}", describe_picture(pic)),
+            Change::PictureRemoved(pic) => write!(f, "- picture {}", describe_picture(pic)),
+            Change::PictureChanged { old, new } => write!(
+                f,
+                "~ picture {} -> {}",
+                describe_picture(old),
+                describe_picture(new)
+            ),
+        }
+    }
+}
+
+/// One change per line
+impl std::fmt::Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+    use crate::types;
+
+    fn copy_tracks(test_dir: &str) -> Vec<String> {
+        (1..=2)
+            .map(|track| util::copy_track(test_dir, &util::get_filename(track)))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_tracks(&test_dir);
+
+        assert!(super::diff(&paths[0], &paths[0]).unwrap().is_empty());
+
+        crate::meta::metadata::remove_meta(types::Type::Genre, &paths[1]).unwrap();
+        crate::meta::metadata::import_comments(
+            &paths[1],
+            b"COMMENT=first\nCOMMENT=second\n",
+            crate::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+        let other_coverart =
+            util::get_full_path(util::TESTFILEDIRECTORY, "Sample Tracks 3 - Other one.png")
+                .unwrap();
+        crate::meta::coverart::set_coverart(&paths[1], &other_coverart).unwrap();
+
+        let diff = super::diff(&paths[0], &paths[1]).unwrap();
+        assert!(diff.changes.contains(&super::Change::Changed {
+            field: String::from("TITLE"),
+            old: vec![String::from("Just roll it")],
+            new: vec![String::from("Here we go!")],
+        }));
+        assert!(diff.changes.contains(&super::Change::Removed {
+            field: String::from("GENRE"),
+            values: vec![String::from("Metal")],
+        }));
+        assert!(diff.changes.contains(&super::Change::Added {
+            field: String::from("COMMENT"),
+            values: vec![String::from("first"), String::from("second")],
+        }));
+        // set_coverart embeds the image as the first picture with type Other
+        let pictures: Vec<&super::Change> = diff
+            .changes
+            .iter()
+            .filter(|change| {
+                matches!(
+                    change,
+                    super::Change::PictureAdded(_) | super::Change::PictureRemoved(_)
+                )
+            })
+            .collect();
+        match pictures.as_slice() {
+            [
+                super::Change::PictureAdded(added),
+                super::Change::PictureRemoved(removed),
+            ] => {
+                assert_eq!(0, added.picture_type);
+                assert_eq!(3, removed.picture_type);
+            }
+            other => panic!("Unexpected picture changes {other:?}"),
+        }
+
+        let text = diff.to_string();
+        assert!(text.contains("- GENRE: \"Metal\"\n"), "{text}");
+        assert!(
+            text.contains("+ COMMENT: \"first\", \"second\"\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("- picture type 3 image/png 578x578"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn test_diff_multiple_values() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_tracks(&test_dir);
+        util::copy_file(&paths[0], &paths[1]).unwrap();
+
+        crate::meta::metadata::import_comments(
+            &paths[1],
+            b"ARTIST=KD\nARTIST=Guest\nYEAR=1999\n",
+            crate::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+
+        let expected = vec![
+            super::Change::Changed {
+                field: String::from("ARTIST"),
+                old: vec![String::from("KD")],
+                new: vec![String::from("KD"), String::from("Guest")],
+            },
+            super::Change::Added {
+                field: String::from("YEAR"),
+                values: vec![String::from("1999")],
+            },
+        ];
+        assert_eq!(expected, super::diff(&paths[0], &paths[1]).unwrap().changes);
+
+        let old = crate::meta::metadata::get_all_meta(&paths[0]).unwrap();
+        let new = crate::meta::metadata::get_all_meta(&paths[1]).unwrap();
+        assert_eq!(expected, super::diff_metadata(&old, &new).changes);
+    }
+
+    #[test]
+    fn test_diff_against() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let mut target = crate::meta::metadata::get_all_meta(&filepath).unwrap();
+        assert!(super::diff_against(&filepath, &target).unwrap().is_empty());
+
+        target.set(types::Type::Track, "4").unwrap();
+        target.title = None;
+        let removed = target.pictures.remove(0);
+        let diff = super::diff_against(&filepath, &target).unwrap();
+        assert_eq!(
+            vec![
+                super::Change::Removed {
+                    field: String::from("TITLE"),
+                    values: vec![String::from("Just roll it")],
+                },
+                super::Change::Changed {
+                    field: String::from("TRACKNUMBER"),
+                    old: vec![String::from("1")],
+                    new: vec![String::from("4")],
+                },
+                super::Change::PictureRemoved(removed),
+            ],
+            diff.changes
+        );
+    }
+
+    #[test]
+    fn test_diff_pictures() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let old = crate::meta::metadata::get_all_meta(&filepath).unwrap();
+        let mut new = old.clone();
+        new.pictures[0].hash = String::from("0123456789abcdef");
+        new.pictures[0].width = 100;
+
+        let diff = super::diff_metadata(&old, &new);
+        assert_eq!(
+            vec![super::Change::PictureChanged {
+                old: old.pictures[0].clone(),
+                new: new.pictures[0].clone(),
+            }],
+            diff.changes
+        );
+        assert_eq!(
+            format!(
+                "~ picture type 3 image/png 578x578 {} -> type 3 image/png 100x578 0123456789ab\n",
+                &old.pictures[0].hash[..12]
+            ),
+            diff.to_string()
+        );
+    }
+}
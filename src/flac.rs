@@ -0,0 +1,1714 @@
+//! Raw access to the metadata blocks of a FLAC stream, for tools that need to see
+//! exactly how a file is laid out
+
+pub const STREAMINFO: u8 = 0;
+pub const PADDING: u8 = 1;
+pub const APPLICATION: u8 = 2;
+pub const SEEKTABLE: u8 = 3;
+pub const VORBIS_COMMENT: u8 = 4;
+pub const CUESHEET: u8 = 5;
+pub const PICTURE: u8 = 6;
+
+/// Largest block body the 24 bit length field can describe
+pub const MAX_BLOCK_LENGTH: usize = 0xFF_FFFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockType {
+    StreamInfo,
+    Padding,
+    Application,
+    SeekTable,
+    VorbisComment,
+    CueSheet,
+    Picture,
+    /// Types 7 to 126 are reserved and 127 is invalid
+    Reserved(u8),
+}
+
+impl BlockType {
+    pub fn from_u8(block_type: u8) -> BlockType {
+        match block_type {
+            STREAMINFO => BlockType::StreamInfo,
+            PADDING => BlockType::Padding,
+            APPLICATION => BlockType::Application,
+            SEEKTABLE => BlockType::SeekTable,
+            VORBIS_COMMENT => BlockType::VorbisComment,
+            CUESHEET => BlockType::CueSheet,
+            PICTURE => BlockType::Picture,
+            other => BlockType::Reserved(other),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            BlockType::StreamInfo => STREAMINFO,
+            BlockType::Padding => PADDING,
+            BlockType::Application => APPLICATION,
+            BlockType::SeekTable => SEEKTABLE,
+            BlockType::VorbisComment => VORBIS_COMMENT,
+            BlockType::CueSheet => CUESHEET,
+            BlockType::Picture => PICTURE,
+            BlockType::Reserved(other) => other,
+        }
+    }
+}
+
+impl std::fmt::Display for BlockType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockType::StreamInfo => write!(f, "STREAMINFO"),
+            BlockType::Padding => write!(f, "PADDING"),
+            BlockType::Application => write!(f, "APPLICATION"),
+            BlockType::SeekTable => write!(f, "SEEKTABLE"),
+            BlockType::VorbisComment => write!(f, "VORBIS_COMMENT"),
+            BlockType::CueSheet => write!(f, "CUESHEET"),
+            BlockType::Picture => write!(f, "PICTURE"),
+            BlockType::Reserved(other) => write!(f, "RESERVED({other})"),
+        }
+    }
+}
+
+/// Where a metadata block sits in the file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub block_type: BlockType,
+    /// Offset of the 4 byte block header from the start of the file
+    pub offset: u64,
+    /// Length of the block body, without the header
+    pub length: u32,
+    pub is_last: bool,
+}
+
+/// A metadata block without its header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RawBlock {
+    pub block_type: u8,
+    pub data: Vec<u8>,
+}
+
+/// The parts of a FLAC file. `audio` is everything after the last metadata block.
+/// An ID3v2 tag some taggers put in front of the stream marker is skipped.
+#[derive(Clone, Debug)]
+pub(crate) struct RawFlac {
+    pub blocks: Vec<RawBlock>,
+    pub audio: Vec<u8>,
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Length of the ID3v2 tag at the start of the data, 0 when there is none
+fn id3v2_length(data: &[u8]) -> usize {
+    match data.get(..10) {
+        Some(header) if header.starts_with(b"ID3") => {
+            let size = header[6..10]
+                .iter()
+                .fold(0usize, |size, byte| (size << 7) | usize::from(byte & 0x7F));
+            let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+            10 + size + footer
+        }
+        _ => 0,
+    }
+}
+
+/// Lists the metadata blocks and returns the offset of the first audio frame
+fn walk(data: &[u8]) -> Result<(Vec<BlockInfo>, usize), std::io::Error> {
+    let start = id3v2_length(data);
+    match data.get(start..start + 4) {
+        Some(marker) if marker == b"fLaC" => {}
+        _ => return Err(invalid("Not a FLAC stream")),
+    }
+
+    let mut infos = Vec::new();
+    let mut offset = start + 4;
+    loop {
+        let header = match data.get(offset..offset + 4) {
+            Some(header) => header,
+            None => {
+                return Err(invalid(&format!(
+                    "Metadata block header at offset {offset} is truncated"
+                )));
+            }
+        };
+        let info = BlockInfo {
+            block_type: BlockType::from_u8(header[0] & 0x7F),
+            offset: offset as u64,
+            length: u32::from_be_bytes([0, header[1], header[2], header[3]]),
+            is_last: header[0] & 0x80 != 0,
+        };
+        offset += 4 + info.length as usize;
+        if offset > data.len() {
+            return Err(invalid(&format!(
+                "{} block at offset {} is truncated",
+                info.block_type, info.offset
+            )));
+        }
+        infos.push(info);
+        if info.is_last {
+            break;
+        }
+    }
+
+    Ok((infos, offset))
+}
+
+/// Lists every metadata block of the FLAC file in order
+pub fn blocks(filepath: &String) -> Result<Vec<BlockInfo>, std::io::Error> {
+    match walk(&std::fs::read(filepath)?) {
+        Ok((infos, _)) => Ok(infos),
+        Err(err) => Err(err),
+    }
+}
+
+/// A block and the result of decoding its body
+pub type DecodedBlock = (BlockInfo, Result<Block, std::io::Error>);
+
+/// Lists and decodes every metadata block. A block that cannot be decoded does
+/// not stop the others from being read.
+pub fn read_blocks(filepath: &String) -> Result<Vec<DecodedBlock>, std::io::Error> {
+    let data = std::fs::read(filepath)?;
+    let (infos, _) = walk(&data)?;
+
+    Ok(infos
+        .into_iter()
+        .map(|info| {
+            let start = info.offset as usize + 4;
+            let block = decode(info.block_type, &data[start..start + info.length as usize]);
+            (info, block)
+        })
+        .collect())
+}
+
+pub(crate) fn parse(data: &[u8]) -> Result<RawFlac, std::io::Error> {
+    let (infos, audio_offset) = walk(data)?;
+    match infos.first() {
+        Some(info) if info.block_type == BlockType::StreamInfo => {}
+        _ => return Err(invalid("The first metadata block is not STREAMINFO")),
+    }
+
+    Ok(RawFlac {
+        blocks: infos
+            .iter()
+            .map(|info| {
+                let start = info.offset as usize + 4;
+                RawBlock {
+                    block_type: info.block_type.to_u8(),
+                    data: data[start..start + info.length as usize].to_vec(),
+                }
+            })
+            .collect(),
+        audio: data[audio_offset..].to_vec(),
+    })
+}
+
+/// Serializes the stream with its metadata blocks in order, marking the last one
+pub(crate) fn serialize(blocks: &[RawBlock], audio: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut data = Vec::from(*b"fLaC");
+    for (index, block) in blocks.iter().enumerate() {
+        if block.data.len() > MAX_BLOCK_LENGTH {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Metadata block is too large",
+            ));
+        }
+        let last = if index + 1 == blocks.len() { 0x80 } else { 0 };
+        data.push(last | block.block_type);
+        data.extend_from_slice(&(block.data.len() as u32).to_be_bytes()[1..]);
+        data.extend_from_slice(&block.data);
+    }
+    data.extend_from_slice(audio);
+    Ok(data)
+}
+
+/// Padding left after the metadata when a file is rewritten, the same as the
+/// reference encoder
+pub const DEFAULT_PADDING: u32 = 8192;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Padding added when the metadata no longer fits and the file has to be
+    /// rewritten. 0 adds none.
+    pub padding: u32,
+}
+
+impl Default for WriteOptions {
+    fn default() -> WriteOptions {
+        WriteOptions {
+            padding: DEFAULT_PADDING,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteMode {
+    /// The metadata fit in the space of the old metadata and padding, and only
+    /// that region was overwritten
+    InPlace,
+    /// The whole file was written again
+    Rewrite,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteReport {
+    pub mode: WriteMode,
+    /// Padding left after the write
+    pub padding: u32,
+}
+
+/// Reads everything in front of the first audio frame
+pub(crate) fn read_metadata_region<R: std::io::Read>(
+    reader: &mut R,
+) -> Result<Vec<u8>, std::io::Error> {
+    let mut data = Vec::new();
+    loop {
+        let (length, complete) = metadata_region_length(&data);
+        if data.len() < length {
+            let start = data.len();
+            data.resize(length, 0);
+            reader.read_exact(&mut data[start..])?;
+        }
+        if complete {
+            data.truncate(length);
+            return Ok(data);
+        }
+    }
+}
+
+/// Like `read_metadata_region`, for async readers
+#[cfg(feature = "tokio")]
+pub(crate) async fn read_metadata_region_async<R>(reader: &mut R) -> Result<Vec<u8>, std::io::Error>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut data = Vec::new();
+    loop {
+        let (length, complete) = metadata_region_length(&data);
+        if data.len() < length {
+            let start = data.len();
+            data.resize(length, 0);
+            reader.read_exact(&mut data[start..]).await?;
+        }
+        if complete {
+            data.truncate(length);
+            return Ok(data);
+        }
+    }
+}
+
+/// How many bytes of the start of the file have to be read to go on walking the
+/// metadata blocks in `data`, and whether that is the end of the last block
+fn metadata_region_length(data: &[u8]) -> (usize, bool) {
+    if data.len() < 10 {
+        return (10, false);
+    }
+
+    let mut offset = id3v2_length(data) + 4;
+    loop {
+        let Some(header) = data.get(offset..offset + 4) else {
+            return (offset + 4, false);
+        };
+        let is_last = header[0] & 0x80 != 0;
+        offset += 4 + u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        if is_last {
+            return (offset, true);
+        }
+    }
+}
+
+/// Lets `f` change the tags and pictures, then writes the metadata back. Only the
+/// metadata region is read and, when the new metadata fits in the old metadata
+/// and padding, only that region is overwritten. Otherwise the file is rewritten
+/// with the padding of the options.
+pub(crate) fn modify<T, F>(
+    filepath: &String,
+    options: &WriteOptions,
+    f: F,
+) -> Result<(T, WriteReport), std::io::Error>
+where
+    F: FnOnce(&mut lofty::flac::FlacFile) -> Result<T, std::io::Error>,
+{
+    let region = read_metadata_region(&mut std::fs::File::open(filepath)?)?;
+    let mut flac_file = match <lofty::flac::FlacFile as lofty::file::AudioFile>::read_from(
+        &mut std::io::Cursor::new(&region),
+        lofty::config::ParseOptions::new().read_properties(false),
+    ) {
+        Ok(flac_file) => flac_file,
+        Err(err) => return Err(invalid(&err.to_string())),
+    };
+    let result = f(&mut flac_file)?;
+
+    // Saving through the FlacFile keeps the picture blocks intact. lofty only marks
+    // the last block correctly when it ends the metadata with padding, which is
+    // dropped when the blocks are written.
+    // lofty cannot save a song whose only block is STREAMINFO, so it is given an
+    // empty padding block after it
+    let mut content = match split_region(&region)? {
+        (prefix, blocks) if blocks.len() == 1 => {
+            let mut padded = prefix.to_vec();
+            padded.extend(serialize(
+                &[
+                    blocks[0].clone(),
+                    RawBlock {
+                        block_type: PADDING,
+                        data: Vec::new(),
+                    },
+                ],
+                &[],
+            )?);
+            std::io::Cursor::new(padded)
+        }
+        _ => std::io::Cursor::new(region.clone()),
+    };
+    if let Err(err) = lofty::file::AudioFile::save_to(
+        &flac_file,
+        &mut content,
+        lofty::config::WriteOptions::default(),
+    ) {
+        return Err(invalid(&err.to_string()));
+    }
+    let written = content.into_inner();
+    let (prefix, blocks) = split_region(&written)?;
+
+    match write_region(filepath, options, region.len(), prefix, blocks) {
+        Ok(report) => Ok((result, report)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Like `modify`, for changes to the raw blocks. Padding blocks are not passed to
+/// `f` and are replaced when writing.
+pub(crate) fn modify_blocks<T, F>(
+    filepath: &String,
+    options: &WriteOptions,
+    f: F,
+) -> Result<(T, WriteReport), std::io::Error>
+where
+    F: FnOnce(&mut Vec<RawBlock>) -> Result<T, std::io::Error>,
+{
+    let region = read_metadata_region(&mut std::fs::File::open(filepath)?)?;
+    let (prefix, mut blocks) = split_region(&region)?;
+    let result = f(&mut blocks)?;
+
+    match write_region(filepath, options, region.len(), prefix, blocks) {
+        Ok(report) => Ok((result, report)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Splits a metadata region into the bytes in front of the stream marker and the
+/// blocks other than padding
+fn split_region(region: &[u8]) -> Result<(&[u8], Vec<RawBlock>), std::io::Error> {
+    let (infos, _) = walk(region)?;
+    let prefix_length = infos[0].offset as usize - 4;
+
+    let blocks = infos
+        .iter()
+        .filter(|info| info.block_type != BlockType::Padding)
+        .map(|info| {
+            let start = info.offset as usize + 4;
+            RawBlock {
+                block_type: info.block_type.to_u8(),
+                data: region[start..start + info.length as usize].to_vec(),
+            }
+        })
+        .collect();
+
+    Ok((&region[..prefix_length], blocks))
+}
+
+/// Writes the blocks over a metadata region of `region_length` bytes, in place
+/// when they fit
+fn write_region(
+    filepath: &String,
+    options: &WriteOptions,
+    region_length: usize,
+    prefix: &[u8],
+    mut blocks: Vec<RawBlock>,
+) -> Result<WriteReport, std::io::Error> {
+    let needed = blocks
+        .iter()
+        .fold(4, |needed, block| needed + 4 + block.data.len());
+    let available = region_length - prefix.len();
+
+    let (mode, padding) = if needed == available {
+        (WriteMode::InPlace, None)
+    } else if needed + 4 <= available && available - needed - 4 <= MAX_BLOCK_LENGTH {
+        (WriteMode::InPlace, Some(available - needed - 4))
+    } else if options.padding > 0 {
+        (
+            WriteMode::Rewrite,
+            Some((options.padding as usize).min(MAX_BLOCK_LENGTH)),
+        )
+    } else {
+        (WriteMode::Rewrite, None)
+    };
+    if let Some(padding) = padding {
+        blocks.push(RawBlock {
+            block_type: PADDING,
+            data: vec![0; padding],
+        });
+    }
+
+    let mut metadata = prefix.to_vec();
+    metadata.extend(serialize(&blocks, &[])?);
+    match mode {
+        WriteMode::InPlace => {
+            use std::io::Write;
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(filepath)?
+                .write_all(&metadata)?;
+        }
+        WriteMode::Rewrite => rewrite(filepath, &metadata, region_length as u64)?,
+    }
+
+    Ok(WriteReport {
+        mode,
+        padding: padding.unwrap_or(0) as u32,
+    })
+}
+
+/// Writes the metadata and the audio from `audio_offset` on to a temporary file
+/// next to the song and moves it over the song
+fn rewrite(filepath: &String, metadata: &[u8], audio_offset: u64) -> Result<(), std::io::Error> {
+    use std::io::{Seek, Write};
+
+    let temp_filepath = format!("{filepath}.{}.tmp", std::process::id());
+    let result = (|| {
+        let mut original = std::fs::File::open(filepath)?;
+        original.seek(std::io::SeekFrom::Start(audio_offset))?;
+        let mut temp_file = std::fs::File::create(&temp_filepath)?;
+        temp_file.write_all(metadata)?;
+        std::io::copy(&mut original, &mut temp_file)?;
+        temp_file.sync_all()?;
+        std::fs::set_permissions(&temp_filepath, original.metadata()?.permissions())?;
+        std::fs::rename(&temp_filepath, filepath)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_filepath);
+    }
+    result
+}
+
+/// Reads big endian fields from a block body
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    block_type: BlockType,
+}
+
+impl<'a> Reader<'a> {
+    fn new(block_type: BlockType, data: &'a [u8]) -> Reader<'a> {
+        Reader {
+            data,
+            offset: 0,
+            block_type,
+        }
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], std::io::Error> {
+        match self.data.get(self.offset..self.offset + length) {
+            Some(bytes) => {
+                self.offset += length;
+                Ok(bytes)
+            }
+            None => Err(invalid(&format!(
+                "{} block ends at byte {} but {} more bytes are needed at byte {}",
+                self.block_type,
+                self.data.len(),
+                length,
+                self.offset
+            ))),
+        }
+    }
+
+    fn uint(&mut self, length: usize) -> Result<u64, std::io::Error> {
+        Ok(self
+            .bytes(length)?
+            .iter()
+            .fold(0u64, |value, byte| (value << 8) | u64::from(*byte)))
+    }
+
+    fn u32_le(&mut self) -> Result<u32, std::io::Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self, length: usize) -> Result<String, std::io::Error> {
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+
+    /// Text padded with nulls, such as the catalog number of a cue sheet
+    fn padded_string(&mut self, length: usize) -> Result<String, std::io::Error> {
+        let bytes = self.bytes(length)?;
+        let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(length);
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+
+    /// Fails when bytes are left after the last field
+    fn finish(&self) -> Result<(), std::io::Error> {
+        if self.offset == self.data.len() {
+            Ok(())
+        } else {
+            Err(invalid(&format!(
+                "{} block has {} unexpected bytes at byte {}",
+                self.block_type,
+                self.data.len() - self.offset,
+                self.offset
+            )))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamInfo {
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+    /// 0 when unknown
+    pub min_frame_size: u32,
+    /// 0 when unknown
+    pub max_frame_size: u32,
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub bits_per_sample: u8,
+    /// 0 when unknown
+    pub total_samples: u64,
+    pub md5: [u8; 16],
+}
+
+/// A SEEKTABLE entry. Placeholder points have `sample_number` set to `u64::MAX`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeekPoint {
+    pub sample_number: u64,
+    /// Offset of the target frame from the first audio frame
+    pub offset: u64,
+    pub samples: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Application {
+    pub id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VorbisComment {
+    pub vendor: String,
+    /// Keys are uppercased, values are kept as written
+    pub comments: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CueSheet {
+    pub media_catalog_number: String,
+    pub lead_in_samples: u64,
+    pub is_cd: bool,
+    /// The last track is the lead-out
+    pub tracks: Vec<CueSheetTrack>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CueSheetTrack {
+    /// Offset in samples from the start of the audio
+    pub offset: u64,
+    /// 170 is the lead-out of a CD, 255 of other sheets
+    pub number: u8,
+    pub isrc: String,
+    pub is_audio: bool,
+    pub pre_emphasis: bool,
+    pub indices: Vec<CueSheetIndex>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CueSheetIndex {
+    /// Offset in samples from the start of the track
+    pub offset: u64,
+    pub number: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Picture {
+    pub picture_type: u32,
+    pub mime: String,
+    pub description: String,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    /// Number of colours of an indexed image, 0 otherwise
+    pub colors: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    StreamInfo(StreamInfo),
+    /// Number of padding bytes
+    Padding(u32),
+    Application(Application),
+    SeekTable(Vec<SeekPoint>),
+    VorbisComment(VorbisComment),
+    CueSheet(CueSheet),
+    Picture(Picture),
+    Reserved {
+        block_type: u8,
+        data: Vec<u8>,
+    },
+}
+
+/// Decodes a block body. The error names the field where the body deviates from
+/// the specification.
+pub fn decode(block_type: BlockType, data: &[u8]) -> Result<Block, std::io::Error> {
+    match block_type {
+        BlockType::StreamInfo => Ok(Block::StreamInfo(decode_stream_info(data)?)),
+        BlockType::Padding => match data.iter().position(|byte| *byte != 0) {
+            Some(position) => Err(invalid(&format!(
+                "PADDING block has a non-zero byte at byte {position}"
+            ))),
+            None => Ok(Block::Padding(data.len() as u32)),
+        },
+        BlockType::Application => {
+            let mut reader = Reader::new(block_type, data);
+            let id = reader.bytes(4)?;
+            Ok(Block::Application(Application {
+                id: [id[0], id[1], id[2], id[3]],
+                data: data[4..].to_vec(),
+            }))
+        }
+        BlockType::SeekTable => {
+            if !data.len().is_multiple_of(18) {
+                return Err(invalid(&format!(
+                    "SEEKTABLE block length {} is not a multiple of 18",
+                    data.len()
+                )));
+            }
+            let mut reader = Reader::new(block_type, data);
+            let mut points = Vec::new();
+            while reader.offset < data.len() {
+                points.push(SeekPoint {
+                    sample_number: reader.uint(8)?,
+                    offset: reader.uint(8)?,
+                    samples: reader.uint(2)? as u16,
+                });
+            }
+            Ok(Block::SeekTable(points))
+        }
+        BlockType::VorbisComment => Ok(Block::VorbisComment(decode_vorbis_comment(data)?)),
+        BlockType::CueSheet => Ok(Block::CueSheet(decode_cue_sheet(data)?)),
+        BlockType::Picture => Ok(Block::Picture(decode_picture(data)?)),
+        BlockType::Reserved(block_type) => Ok(Block::Reserved {
+            block_type,
+            data: data.to_vec(),
+        }),
+    }
+}
+
+pub(crate) fn decode_stream_info(data: &[u8]) -> Result<StreamInfo, std::io::Error> {
+    if data.len() != 34 {
+        return Err(invalid(&format!(
+            "STREAMINFO block is {} bytes instead of 34",
+            data.len()
+        )));
+    }
+    let mut reader = Reader::new(BlockType::StreamInfo, data);
+    let min_block_size = reader.uint(2)? as u16;
+    let max_block_size = reader.uint(2)? as u16;
+    let min_frame_size = reader.uint(3)? as u32;
+    let max_frame_size = reader.uint(3)? as u32;
+    // 20 bits sample rate, 3 bits channels - 1, 5 bits bits per sample - 1 and
+    // 36 bits total samples
+    let packed = reader.uint(8)?;
+    let mut md5 = [0u8; 16];
+    md5.copy_from_slice(reader.bytes(16)?);
+
+    let stream_info = StreamInfo {
+        min_block_size,
+        max_block_size,
+        min_frame_size,
+        max_frame_size,
+        sample_rate: (packed >> 44) as u32,
+        channels: ((packed >> 41) & 0x7) as u8 + 1,
+        bits_per_sample: ((packed >> 36) & 0x1F) as u8 + 1,
+        total_samples: packed & 0xF_FFFF_FFFF,
+        md5,
+    };
+    if stream_info.min_block_size < 16 {
+        return Err(invalid(&format!(
+            "STREAMINFO minimum block size {} is below 16",
+            stream_info.min_block_size
+        )));
+    }
+    if stream_info.max_block_size < stream_info.min_block_size {
+        return Err(invalid(&format!(
+            "STREAMINFO maximum block size {} is below the minimum {}",
+            stream_info.max_block_size, stream_info.min_block_size
+        )));
+    }
+    if stream_info.sample_rate == 0 {
+        return Err(invalid("STREAMINFO sample rate is 0"));
+    }
+    Ok(stream_info)
+}
+
+/// Serializes a STREAMINFO block body. Fields that do not fit the block or that
+/// `decode` would reject give an InvalidInput error.
+pub fn encode_stream_info(stream_info: &StreamInfo) -> Result<Vec<u8>, std::io::Error> {
+    let invalid_input =
+        |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+
+    if stream_info.min_block_size < 16 {
+        return Err(invalid_input(format!(
+            "Minimum block size {} is below 16",
+            stream_info.min_block_size
+        )));
+    }
+    if stream_info.max_block_size < stream_info.min_block_size {
+        return Err(invalid_input(format!(
+            "Maximum block size {} is below the minimum {}",
+            stream_info.max_block_size, stream_info.min_block_size
+        )));
+    }
+    if stream_info.min_frame_size > 0xFF_FFFF || stream_info.max_frame_size > 0xFF_FFFF {
+        return Err(invalid_input(format!(
+            "Frame sizes {} and {} do not fit in 24 bits",
+            stream_info.min_frame_size, stream_info.max_frame_size
+        )));
+    }
+    if stream_info.sample_rate == 0 || stream_info.sample_rate > 0xF_FFFF {
+        return Err(invalid_input(format!(
+            "Sample rate {} is not between 1 and {}",
+            stream_info.sample_rate, 0xF_FFFF
+        )));
+    }
+    if !(1..=8).contains(&stream_info.channels) {
+        return Err(invalid_input(format!(
+            "{} channels is not between 1 and 8",
+            stream_info.channels
+        )));
+    }
+    if !(1..=32).contains(&stream_info.bits_per_sample) {
+        return Err(invalid_input(format!(
+            "{} bits per sample is not between 1 and 32",
+            stream_info.bits_per_sample
+        )));
+    }
+    if stream_info.total_samples > 0xF_FFFF_FFFF {
+        return Err(invalid_input(format!(
+            "Total samples {} do not fit in 36 bits",
+            stream_info.total_samples
+        )));
+    }
+
+    let mut data = Vec::with_capacity(34);
+    data.extend_from_slice(&stream_info.min_block_size.to_be_bytes());
+    data.extend_from_slice(&stream_info.max_block_size.to_be_bytes());
+    data.extend_from_slice(&stream_info.min_frame_size.to_be_bytes()[1..]);
+    data.extend_from_slice(&stream_info.max_frame_size.to_be_bytes()[1..]);
+    let packed = (u64::from(stream_info.sample_rate) << 44)
+        | (u64::from(stream_info.channels - 1) << 41)
+        | (u64::from(stream_info.bits_per_sample - 1) << 36)
+        | stream_info.total_samples;
+    data.extend_from_slice(&packed.to_be_bytes());
+    data.extend_from_slice(&stream_info.md5);
+    Ok(data)
+}
+
+pub(crate) fn decode_vorbis_comment(data: &[u8]) -> Result<VorbisComment, std::io::Error> {
+    let mut reader = Reader::new(BlockType::VorbisComment, data);
+    let length = reader.u32_le()? as usize;
+    let vendor = reader.string(length)?;
+    let count = reader.u32_le()?;
+
+    let mut comments = Vec::new();
+    for index in 0..count {
+        let length = reader.u32_le()? as usize;
+        let comment = reader.string(length)?;
+        match comment.split_once('=') {
+            Some((key, value)) => comments.push((key.to_uppercase(), String::from(value))),
+            None => {
+                return Err(invalid(&format!(
+                    "VORBIS_COMMENT comment {index} has no '='"
+                )));
+            }
+        }
+    }
+    reader.finish()?;
+
+    Ok(VorbisComment { vendor, comments })
+}
+
+pub(crate) fn serialize_vorbis_comment(vendor: &str, comments: &[(String, String)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    data.extend_from_slice(vendor.as_bytes());
+    data.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for (key, value) in comments {
+        let comment = format!("{key}={value}");
+        data.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        data.extend_from_slice(comment.as_bytes());
+    }
+    data
+}
+
+fn decode_cue_sheet(data: &[u8]) -> Result<CueSheet, std::io::Error> {
+    let mut reader = Reader::new(BlockType::CueSheet, data);
+    let media_catalog_number = reader.padded_string(128)?;
+    let lead_in_samples = reader.uint(8)?;
+    let is_cd = reader.uint(1)? & 0x80 != 0;
+    reader.bytes(258)?;
+    let track_count = reader.uint(1)?;
+
+    let mut tracks = Vec::new();
+    for _ in 0..track_count {
+        let offset = reader.uint(8)?;
+        let number = reader.uint(1)? as u8;
+        let isrc = reader.padded_string(12)?;
+        let flags = reader.uint(1)?;
+        reader.bytes(13)?;
+        let index_count = reader.uint(1)?;
+
+        let mut indices = Vec::new();
+        for _ in 0..index_count {
+            let offset = reader.uint(8)?;
+            let number = reader.uint(1)? as u8;
+            reader.bytes(3)?;
+            indices.push(CueSheetIndex { offset, number });
+        }
+        tracks.push(CueSheetTrack {
+            offset,
+            number,
+            isrc,
+            is_audio: flags & 0x80 == 0,
+            pre_emphasis: flags & 0x40 != 0,
+            indices,
+        });
+    }
+    reader.finish()?;
+
+    match tracks.last() {
+        Some(track) if track.number == 170 || track.number == 255 => Ok(CueSheet {
+            media_catalog_number,
+            lead_in_samples,
+            is_cd,
+            tracks,
+        }),
+        _ => Err(invalid("CUESHEET block has no lead-out track")),
+    }
+}
+
+/// The STREAMINFO block of the song, read without the audio
+pub fn read_stream_info(filepath: &String) -> Result<StreamInfo, std::io::Error> {
+    let region = read_metadata_region(&mut std::fs::File::open(filepath)?)?;
+    let (_, blocks) = split_region(&region)?;
+    match blocks.first() {
+        Some(block) if block.block_type == STREAMINFO => decode_stream_info(&block.data),
+        _ => Err(invalid("The first metadata block is not STREAMINFO")),
+    }
+}
+
+/// Serializes a cue sheet into a CUESHEET block body
+pub fn encode_cue_sheet(cue_sheet: &CueSheet) -> Result<Vec<u8>, std::io::Error> {
+    let invalid_input =
+        |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+    let padded = |text: &str, length: usize, name: &str| -> Result<Vec<u8>, std::io::Error> {
+        if !text.is_ascii() || text.len() > length {
+            return Err(invalid_input(format!(
+                "{name} {text:?} is not ASCII of at most {length} characters"
+            )));
+        }
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(length, 0);
+        Ok(bytes)
+    };
+
+    match cue_sheet.tracks.last() {
+        Some(track) if track.number == 170 || track.number == 255 => {}
+        _ => {
+            return Err(invalid_input(String::from(
+                "Cue sheet has no lead-out track",
+            )));
+        }
+    }
+    if cue_sheet.tracks.len() > 100 {
+        return Err(invalid_input(String::from(
+            "Cue sheet has more than 99 tracks",
+        )));
+    }
+
+    let mut data = padded(&cue_sheet.media_catalog_number, 128, "Media catalog number")?;
+    data.extend_from_slice(&cue_sheet.lead_in_samples.to_be_bytes());
+    data.push(if cue_sheet.is_cd { 0x80 } else { 0 });
+    data.extend_from_slice(&[0; 258]);
+    data.push(cue_sheet.tracks.len() as u8);
+
+    for track in &cue_sheet.tracks {
+        if !track.isrc.is_empty() && track.isrc.len() != 12 {
+            return Err(invalid_input(format!(
+                "ISRC {:?} of track {} is not 12 characters",
+                track.isrc, track.number
+            )));
+        }
+        if track.indices.len() > 100 {
+            return Err(invalid_input(format!(
+                "Track {} has more than 100 indices",
+                track.number
+            )));
+        }
+        data.extend_from_slice(&track.offset.to_be_bytes());
+        data.push(track.number);
+        data.extend(padded(&track.isrc, 12, "ISRC")?);
+        let mut flags = 0;
+        if !track.is_audio {
+            flags |= 0x80;
+        }
+        if track.pre_emphasis {
+            flags |= 0x40;
+        }
+        data.push(flags);
+        data.extend_from_slice(&[0; 13]);
+        data.push(track.indices.len() as u8);
+        for index in &track.indices {
+            data.extend_from_slice(&index.offset.to_be_bytes());
+            data.push(index.number);
+            data.extend_from_slice(&[0; 3]);
+        }
+    }
+
+    Ok(data)
+}
+
+/// The first CUESHEET block of the song, if it has one
+pub fn read_cue_sheet(filepath: &String) -> Result<Option<CueSheet>, std::io::Error> {
+    let region = read_metadata_region(&mut std::fs::File::open(filepath)?)?;
+    let (_, blocks) = split_region(&region)?;
+
+    match blocks.iter().find(|block| block.block_type == CUESHEET) {
+        Some(block) => Ok(Some(decode_cue_sheet(&block.data)?)),
+        None => Ok(None),
+    }
+}
+
+/// Replaces the CUESHEET block of the song, or adds one after the STREAMINFO and
+/// SEEKTABLE blocks
+pub fn write_cue_sheet(
+    filepath: &String,
+    cue_sheet: &CueSheet,
+    options: &WriteOptions,
+) -> Result<WriteReport, std::io::Error> {
+    let data = encode_cue_sheet(cue_sheet)?;
+
+    match modify_blocks(filepath, options, |blocks| {
+        match blocks.iter_mut().find(|block| block.block_type == CUESHEET) {
+            Some(block) => block.data = data,
+            None => {
+                let position = blocks
+                    .iter()
+                    .position(|block| {
+                        block.block_type != STREAMINFO && block.block_type != SEEKTABLE
+                    })
+                    .unwrap_or(blocks.len());
+                blocks.insert(
+                    position,
+                    RawBlock {
+                        block_type: CUESHEET,
+                        data,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }) {
+        Ok((_, report)) => Ok(report),
+        Err(err) => Err(err),
+    }
+}
+
+/// Removes every CUESHEET block and returns whether there was one
+pub fn remove_cue_sheet(
+    filepath: &String,
+    options: &WriteOptions,
+) -> Result<(bool, WriteReport), std::io::Error> {
+    modify_blocks(filepath, options, |blocks| {
+        let count = blocks.len();
+        blocks.retain(|block| block.block_type != CUESHEET);
+        Ok(blocks.len() != count)
+    })
+}
+
+/// The seek points of the first SEEKTABLE block of the song, if it has one
+pub fn seektable(filepath: &String) -> Result<Option<Vec<SeekPoint>>, std::io::Error> {
+    let region = read_metadata_region(&mut std::fs::File::open(filepath)?)?;
+    let (_, blocks) = split_region(&region)?;
+
+    match blocks.iter().find(|block| block.block_type == SEEKTABLE) {
+        Some(block) => match decode(BlockType::SeekTable, &block.data)? {
+            Block::SeekTable(points) => Ok(Some(points)),
+            _ => Err(invalid("SEEKTABLE block did not decode to seek points")),
+        },
+        None => Ok(None),
+    }
+}
+
+/// A problem with one seek point, identified by its position in the table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeekPointIssue {
+    /// The sample number is not above the one of the point before it
+    Unsorted { index: usize },
+    /// The sample number is past the end of the stream
+    SampleOutOfRange { index: usize },
+    /// The offset is past the end of the audio
+    OffsetOutOfRange { index: usize },
+    /// No frame starts at the offset
+    NotAFrame { index: usize },
+}
+
+/// Checks the seek points of the song against its audio. Placeholder points are
+/// skipped. A song without a SEEKTABLE has no issues.
+pub fn validate_seektable(filepath: &String) -> Result<Vec<SeekPointIssue>, std::io::Error> {
+    let raw = parse(&std::fs::read(filepath)?)?;
+    let points = match raw
+        .blocks
+        .iter()
+        .find(|block| block.block_type == SEEKTABLE)
+    {
+        Some(block) => match decode(BlockType::SeekTable, &block.data)? {
+            Block::SeekTable(points) => points,
+            _ => return Err(invalid("SEEKTABLE block did not decode to seek points")),
+        },
+        None => return Ok(Vec::new()),
+    };
+    let total_samples = match raw.blocks.first() {
+        Some(block) if block.block_type == STREAMINFO => {
+            decode_stream_info(&block.data)?.total_samples
+        }
+        _ => return Err(invalid("The first metadata block is not STREAMINFO")),
+    };
+
+    let mut issues = Vec::new();
+    let mut previous = None;
+    for (index, point) in points.iter().enumerate() {
+        if point.sample_number == u64::MAX {
+            continue;
+        }
+        if previous.is_some_and(|previous| point.sample_number <= previous) {
+            issues.push(SeekPointIssue::Unsorted { index });
+        }
+        previous = Some(point.sample_number);
+
+        if total_samples > 0 && point.sample_number >= total_samples {
+            issues.push(SeekPointIssue::SampleOutOfRange { index });
+        }
+        match usize::try_from(point.offset) {
+            Ok(offset) if offset < raw.audio.len() => {
+                if crate::frame::parse_header(&raw.audio[offset..]).is_none() {
+                    issues.push(SeekPointIssue::NotAFrame { index });
+                }
+            }
+            _ => issues.push(SeekPointIssue::OffsetOutOfRange { index }),
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Distance between the seek points of a generated table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeekInterval {
+    Samples(u64),
+    Seconds(u32),
+}
+
+impl Default for SeekInterval {
+    /// Every 10 seconds, like the reference encoder
+    fn default() -> SeekInterval {
+        SeekInterval::Seconds(10)
+    }
+}
+
+/// Builds a seek table from the frame headers of the song, with one point for the
+/// frame holding each multiple of the interval, and writes it in place of the old
+/// SEEKTABLE or right after STREAMINFO. A 65536 sample frame cannot be described
+/// by a seek point, so the point goes to the frame after it.
+pub fn regenerate_seektable(
+    filepath: &String,
+    interval: SeekInterval,
+    options: &WriteOptions,
+) -> Result<(Vec<SeekPoint>, WriteReport), std::io::Error> {
+    let raw = parse(&std::fs::read(filepath)?)?;
+    let interval = match interval {
+        SeekInterval::Samples(samples) => samples,
+        SeekInterval::Seconds(seconds) => match raw.blocks.first() {
+            Some(block) if block.block_type == STREAMINFO => {
+                u64::from(seconds) * u64::from(decode_stream_info(&block.data)?.sample_rate)
+            }
+            _ => return Err(invalid("The first metadata block is not STREAMINFO")),
+        },
+    };
+    if interval == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The seek point interval is 0",
+        ));
+    }
+
+    let mut points: Vec<SeekPoint> = Vec::new();
+    let mut target = 0;
+    for frame in crate::frame::scan(&raw.audio)? {
+        let frame_end = frame.first_sample + u64::from(frame.header.block_size);
+        if target >= frame_end {
+            continue;
+        }
+        // A frame may hold 65536 samples, one more than a seek point can count, so
+        // the point goes to the next frame instead
+        let Ok(samples) = u16::try_from(frame.header.block_size) else {
+            continue;
+        };
+        points.push(SeekPoint {
+            sample_number: frame.first_sample,
+            offset: frame.offset as u64,
+            samples,
+        });
+        target = frame_end.div_ceil(interval) * interval;
+    }
+    if points.len() * 18 > MAX_BLOCK_LENGTH {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} seek points do not fit in a SEEKTABLE block",
+                points.len()
+            ),
+        ));
+    }
+
+    let mut data = Vec::new();
+    for point in &points {
+        data.extend_from_slice(&point.sample_number.to_be_bytes());
+        data.extend_from_slice(&point.offset.to_be_bytes());
+        data.extend_from_slice(&point.samples.to_be_bytes());
+    }
+    let (_, report) = modify_blocks(filepath, options, |blocks| {
+        let position = match blocks
+            .iter()
+            .position(|block| block.block_type == SEEKTABLE)
+        {
+            Some(position) => position,
+            None => blocks
+                .iter()
+                .position(|block| block.block_type != STREAMINFO)
+                .unwrap_or(blocks.len()),
+        };
+        blocks.retain(|block| block.block_type != SEEKTABLE);
+        if !data.is_empty() {
+            blocks.insert(
+                position.min(blocks.len()),
+                RawBlock {
+                    block_type: SEEKTABLE,
+                    data,
+                },
+            );
+        }
+        Ok(())
+    })?;
+
+    Ok((points, report))
+}
+
+fn decode_picture(data: &[u8]) -> Result<Picture, std::io::Error> {
+    let mut reader = Reader::new(BlockType::Picture, data);
+    let picture_type = reader.uint(4)? as u32;
+    let length = reader.uint(4)? as usize;
+    let mime = reader.string(length)?;
+    let length = reader.uint(4)? as usize;
+    let description = reader.string(length)?;
+    let width = reader.uint(4)? as u32;
+    let height = reader.uint(4)? as u32;
+    let depth = reader.uint(4)? as u32;
+    let colors = reader.uint(4)? as u32;
+    let length = reader.uint(4)? as usize;
+    let picture_data = reader.bytes(length)?.to_vec();
+    reader.finish()?;
+
+    Ok(Picture {
+        picture_type,
+        mime,
+        description,
+        width,
+        height,
+        depth,
+        colors,
+        data: picture_data,
+    })
+}
+
+/// The picture type of a PICTURE block
+pub(crate) fn picture_type(data: &[u8]) -> Option<u32> {
+    data.get(..4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    #[test]
+    fn test_blocks() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let infos = super::blocks(&filepath).unwrap();
+        assert_eq!(
+            vec![
+                super::BlockInfo {
+                    block_type: super::BlockType::StreamInfo,
+                    offset: 4,
+                    length: 34,
+                    is_last: false,
+                },
+                super::BlockInfo {
+                    block_type: super::BlockType::VorbisComment,
+                    offset: 42,
+                    length: 411,
+                    is_last: false,
+                },
+                super::BlockInfo {
+                    block_type: super::BlockType::Picture,
+                    offset: 457,
+                    length: 39642,
+                    is_last: true,
+                },
+            ],
+            infos
+        );
+        assert_eq!("VORBIS_COMMENT", infos[1].block_type.to_string());
+        assert_eq!(super::BlockType::Reserved(9), super::BlockType::from_u8(9));
+    }
+
+    #[test]
+    fn test_read_blocks() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let blocks: Vec<super::Block> = super::read_blocks(&filepath)
+            .unwrap()
+            .into_iter()
+            .map(|(_, block)| block.unwrap())
+            .collect();
+
+        match blocks.as_slice() {
+            [
+                super::Block::StreamInfo(stream_info),
+                super::Block::VorbisComment(vorbis_comment),
+                super::Block::Picture(picture),
+            ] => {
+                assert_eq!(48000, stream_info.sample_rate);
+                assert_eq!(24, stream_info.bits_per_sample);
+                assert_eq!(41, stream_info.total_samples / 48000);
+                assert!(
+                    vorbis_comment
+                        .comments
+                        .contains(&(String::from("TITLE"), String::from("Just roll it")))
+                );
+                assert_eq!(3, picture.picture_type);
+                assert_eq!("image/png", picture.mime);
+                assert_eq!(578, picture.width);
+                assert_eq!(39582, picture.data.len());
+            }
+            other => panic!("Unexpected blocks {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_encode_stream_info() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let stream_info = super::read_stream_info(&filepath).unwrap();
+        let data = super::encode_stream_info(&stream_info).unwrap();
+        assert_eq!(stream_info, super::decode_stream_info(&data).unwrap());
+
+        let cases = [
+            (
+                super::StreamInfo {
+                    channels: 0,
+                    ..stream_info.clone()
+                },
+                "0 channels is not between 1 and 8",
+            ),
+            (
+                super::StreamInfo {
+                    bits_per_sample: 0,
+                    ..stream_info.clone()
+                },
+                "0 bits per sample is not between 1 and 32",
+            ),
+            (
+                super::StreamInfo {
+                    min_block_size: 4,
+                    ..stream_info.clone()
+                },
+                "Minimum block size 4 is below 16",
+            ),
+            (
+                super::StreamInfo {
+                    total_samples: 1 << 36,
+                    ..stream_info.clone()
+                },
+                "Total samples 68719476736 do not fit in 36 bits",
+            ),
+        ];
+        for (stream_info, message) in cases {
+            match super::encode_stream_info(&stream_info) {
+                Ok(data) => panic!("{stream_info:?} encoded as {data:?}"),
+                Err(err) => {
+                    assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+                    assert_eq!(message, err.to_string());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        match super::decode(super::BlockType::StreamInfo, &[0; 20]) {
+            Ok(block) => panic!("Short STREAMINFO decoded as {block:?}"),
+            Err(err) => assert_eq!(
+                "STREAMINFO block is 20 bytes instead of 34",
+                err.to_string()
+            ),
+        }
+        match super::decode(super::BlockType::SeekTable, &[0; 20]) {
+            Ok(block) => panic!("Short SEEKTABLE decoded as {block:?}"),
+            Err(err) => assert_eq!(
+                "SEEKTABLE block length 20 is not a multiple of 18",
+                err.to_string()
+            ),
+        }
+        match super::decode(super::BlockType::Padding, &[0, 0, 1]) {
+            Ok(block) => panic!("Dirty PADDING decoded as {block:?}"),
+            Err(err) => assert_eq!(
+                "PADDING block has a non-zero byte at byte 2",
+                err.to_string()
+            ),
+        }
+        assert_eq!(
+            super::Block::Padding(3),
+            super::decode(super::BlockType::Padding, &[0; 3]).unwrap()
+        );
+
+        let mut data = super::serialize_vorbis_comment("vendor", &[]);
+        data.push(0);
+        assert!(super::decode(super::BlockType::VorbisComment, &data).is_err());
+
+        let mut flac = b"fLaC".to_vec();
+        flac.extend_from_slice(&[0x80, 0, 0, 34]);
+        assert_eq!(
+            "STREAMINFO block at offset 4 is truncated",
+            super::walk(&flac).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_in_place() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        let options = super::WriteOptions { padding: 4096 };
+
+        // The fixture has no padding, so the first write has to make room
+        let (_, report) = crate::meta::metadata::set_meta_with(
+            crate::types::Type::Title,
+            &new_filepath,
+            "Just roll it again",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            super::WriteReport {
+                mode: super::WriteMode::Rewrite,
+                padding: 4096,
+            },
+            report
+        );
+        let length = util::get_data_from_file(&new_filepath).unwrap().len();
+
+        let (_, report) = crate::meta::metadata::set_meta_with(
+            crate::types::Type::Title,
+            &new_filepath,
+            "Just roll it again!",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            super::WriteReport {
+                mode: super::WriteMode::InPlace,
+                padding: 4095,
+            },
+            report
+        );
+        assert_eq!(
+            length,
+            util::get_data_from_file(&new_filepath).unwrap().len()
+        );
+        assert_eq!(
+            "Just roll it again!",
+            crate::meta::metadata::get_meta(crate::types::Type::Title, &new_filepath).unwrap()
+        );
+        let infos = super::blocks(&new_filepath).unwrap();
+        assert_eq!(super::BlockType::Padding, infos[infos.len() - 1].block_type);
+        assert!(infos[infos.len() - 1].is_last);
+
+        // The smaller cover leaves more padding behind
+        let coverart_filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, "Sample Tracks 3 - Other one.png")
+                .unwrap();
+        let (_, report) =
+            crate::meta::coverart::set_coverart_with(&new_filepath, &coverart_filepath, &options)
+                .unwrap();
+        assert_eq!(super::WriteMode::InPlace, report.mode);
+        assert!(report.padding > 4095 + 39582 - 9627, "{report:?}");
+
+        // Metadata larger than the padding forces another rewrite
+        let (_, report) = crate::meta::metadata::set_meta_with(
+            crate::types::Type::Title,
+            &new_filepath,
+            &"Just roll it".repeat(4000),
+            &super::WriteOptions { padding: 0 },
+        )
+        .unwrap();
+        assert_eq!(
+            super::WriteReport {
+                mode: super::WriteMode::Rewrite,
+                padding: 0,
+            },
+            report
+        );
+        assert_ne!(
+            super::BlockType::Padding,
+            super::blocks(&new_filepath)
+                .unwrap()
+                .last()
+                .unwrap()
+                .block_type
+        );
+
+        assert!(crate::strip::verify_audio(&filepath, &new_filepath).unwrap());
+        assert_eq!(
+            crate::properties::get_duration(&filepath).unwrap(),
+            crate::properties::get_duration(&new_filepath).unwrap()
+        );
+        assert_eq!(
+            util::get_data_from_file(&coverart_filepath).unwrap(),
+            crate::meta::coverart::get_coverart(&new_filepath).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_in_place_with() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        let options = super::WriteOptions { padding: 4096 };
+
+        let (count, report) = crate::meta::metadata::import_comments_with(
+            &new_filepath,
+            b"UMID=0a1b\n",
+            crate::meta::metadata::ImportMode::Merge,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(1, count);
+        assert!(report.padding > 0, "{report:?}");
+        let length = util::get_data_from_file(&new_filepath).unwrap().len();
+
+        let (removed, report) = crate::meta::metadata::remove_comments_with(
+            &new_filepath,
+            &[String::from("UMID")],
+            &options,
+        )
+        .unwrap();
+        assert_eq!(vec![String::from("0a1b")], removed);
+        assert_eq!(super::WriteMode::InPlace, report.mode);
+
+        let mut song_metadata = crate::meta::metadata::get_all_meta(&new_filepath).unwrap();
+        song_metadata.title = Some(String::from("Just roll it again"));
+        let (_, report) =
+            crate::meta::metadata::set_all_meta_with(&new_filepath, &song_metadata, &options)
+                .unwrap();
+        assert_eq!(super::WriteMode::InPlace, report.mode);
+
+        let (data, report) =
+            crate::meta::coverart::remove_coverart_with(&new_filepath, &options).unwrap();
+        assert_eq!(super::WriteMode::InPlace, report.mode);
+        let (_, report) =
+            crate::meta::coverart::copy_coverart_with(&filepath, &new_filepath, &options).unwrap();
+        assert_eq!(super::WriteMode::InPlace, report.mode);
+
+        assert_eq!(
+            length,
+            util::get_data_from_file(&new_filepath).unwrap().len()
+        );
+        assert_eq!(
+            data,
+            crate::meta::coverart::get_coverart(&new_filepath).unwrap()
+        );
+        assert_eq!(
+            "Just roll it again",
+            crate::meta::metadata::get_meta(crate::types::Type::Title, &new_filepath).unwrap()
+        );
+        assert!(crate::strip::verify_audio(&filepath, &new_filepath).unwrap());
+    }
+
+    #[test]
+    fn test_modify_stream_info_only() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::copy_track(&test_dir, &util::get_filename(1));
+        crate::strip::strip(&new_filepath, &crate::strip::StripPolicy::KeepNothing).unwrap();
+        let data = util::get_data_from_file(&new_filepath).unwrap();
+        assert_eq!(1, super::parse(&data).unwrap().blocks.len());
+
+        crate::meta::metadata::set_comments(&new_filepath, "UMID", &[String::from("0a1b")])
+            .unwrap();
+        crate::meta::metadata::set_meta(crate::types::Type::Title, &new_filepath, "Just roll it")
+            .unwrap();
+        assert_eq!(
+            "Just roll it",
+            crate::meta::metadata::get_meta(crate::types::Type::Title, &new_filepath).unwrap()
+        );
+        assert_eq!(
+            vec![String::from("0a1b")],
+            crate::meta::metadata::get_comments(&new_filepath, "UMID").unwrap()
+        );
+        assert!(crate::strip::verify_audio(&filepath, &new_filepath).unwrap());
+    }
+
+    #[test]
+    fn test_seektable_long_frame() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::get_full_path(&test_dir, "long.flac").unwrap();
+        let stream_info = super::StreamInfo {
+            min_block_size: 16,
+            max_block_size: 65535,
+            min_frame_size: 0,
+            max_frame_size: 0,
+            sample_rate: 44100,
+            channels: 1,
+            bits_per_sample: 16,
+            total_samples: 69632,
+            md5: [0; 16],
+        };
+        let mut audio = crate::frame::encode_verbatim(&[vec![0; 65536]], 0, &stream_info).unwrap();
+        let offset = audio.len() as u64;
+        audio.extend(crate::frame::encode_verbatim(&[vec![0; 4096]], 65536, &stream_info).unwrap());
+        let blocks = [super::RawBlock {
+            block_type: super::STREAMINFO,
+            data: super::encode_stream_info(&stream_info).unwrap(),
+        }];
+        util::save_bytes_to_file(&super::serialize(&blocks, &audio).unwrap(), &filepath).unwrap();
+
+        // The long frame gets no seek point, the frame after it does
+        let (points, _) = super::regenerate_seektable(
+            &filepath,
+            super::SeekInterval::Seconds(10),
+            &super::WriteOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![super::SeekPoint {
+                sample_number: 65536,
+                offset,
+                samples: 4096,
+            }],
+            points
+        );
+        assert_eq!(Some(points), super::seektable(&filepath).unwrap());
+        assert!(super::validate_seektable(&filepath).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_seektable() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        assert_eq!(None, super::seektable(&new_filepath).unwrap());
+        assert!(super::validate_seektable(&new_filepath).unwrap().is_empty());
+
+        // 2012481 samples at 48 kHz in frames of 4608 samples
+        let (points, report) = super::regenerate_seektable(
+            &new_filepath,
+            super::SeekInterval::default(),
+            &super::WriteOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(super::WriteMode::Rewrite, report.mode);
+        assert_eq!(
+            vec![0, 479232, 958464, 1437696, 1916928],
+            points
+                .iter()
+                .map(|point| point.sample_number)
+                .collect::<Vec<u64>>()
+        );
+        assert!(points.iter().all(|point| point.samples == 4608));
+        assert_eq!(Some(points), super::seektable(&new_filepath).unwrap());
+        assert_eq!(
+            super::BlockType::SeekTable,
+            super::blocks(&new_filepath).unwrap()[1].block_type
+        );
+        assert!(super::validate_seektable(&new_filepath).unwrap().is_empty());
+        assert!(crate::strip::verify_audio(&filepath, &new_filepath).unwrap());
+
+        // A denser table replaces the old one in place
+        let (points, report) = super::regenerate_seektable(
+            &new_filepath,
+            super::SeekInterval::Samples(4608 * 100),
+            &super::WriteOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(super::WriteMode::InPlace, report.mode);
+        assert_eq!(5, points.len());
+        assert_eq!(Some(points), super::seektable(&new_filepath).unwrap());
+
+        let bad_points = [(9216, 0), (4608, 0), (5_000_000, 0), (0, 1 << 40), (0, 7)];
+        super::modify_blocks(&new_filepath, &super::WriteOptions::default(), |blocks| {
+            let table = blocks
+                .iter_mut()
+                .find(|block| block.block_type == super::SEEKTABLE)
+                .unwrap();
+            table.data.clear();
+            for (sample_number, offset) in bad_points {
+                table
+                    .data
+                    .extend_from_slice(&u64::to_be_bytes(sample_number));
+                table.data.extend_from_slice(&u64::to_be_bytes(offset));
+                table.data.extend_from_slice(&u16::to_be_bytes(4608));
+            }
+            table.data.extend_from_slice(&u64::MAX.to_be_bytes());
+            table.data.extend_from_slice(&[0; 10]);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            vec![
+                super::SeekPointIssue::Unsorted { index: 1 },
+                super::SeekPointIssue::SampleOutOfRange { index: 2 },
+                super::SeekPointIssue::Unsorted { index: 3 },
+                super::SeekPointIssue::OffsetOutOfRange { index: 3 },
+                super::SeekPointIssue::Unsorted { index: 4 },
+                super::SeekPointIssue::NotAFrame { index: 4 },
+            ],
+            super::validate_seektable(&new_filepath).unwrap()
+        );
+    }
+}
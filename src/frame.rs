@@ -0,0 +1,778 @@
+//! Finding, decoding and writing FLAC audio frames
+
+use crate::flac;
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// CRC-8 of frame headers, polynomial x^8 + x^2 + x + 1
+pub(crate) fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |crc, byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+const CRC16_TABLE: [u16; 256] = {
+    let mut table = [0u16; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = (index as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+fn crc16_update(crc: u16, byte: u8) -> u16 {
+    (crc << 8) ^ CRC16_TABLE[usize::from((crc >> 8) as u8 ^ byte)]
+}
+
+/// CRC-16 of whole frames, polynomial x^16 + x^15 + x^2 + 1
+pub(crate) fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, byte| crc16_update(crc, *byte))
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position in bits
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data, position: 0 }
+    }
+
+    fn read(&mut self, bits: u32) -> Result<u64, std::io::Error> {
+        let mut value = 0u64;
+        let mut remaining = bits;
+        while remaining > 0 {
+            let byte = match self.data.get(self.position / 8) {
+                Some(byte) => *byte,
+                None => return Err(invalid("Frame ends early")),
+            };
+            let available = 8 - (self.position % 8) as u32;
+            let taken = available.min(remaining);
+            let chunk = (byte >> (available - taken)) & (0xFF >> (8 - taken));
+            value = (value << taken) | u64::from(chunk);
+            self.position += taken as usize;
+            remaining -= taken;
+        }
+        Ok(value)
+    }
+
+    fn read_signed(&mut self, bits: u32) -> Result<i64, std::io::Error> {
+        let value = self.read(bits)?;
+        if bits == 0 {
+            return Ok(0);
+        }
+        Ok(((value << (64 - bits)) as i64) >> (64 - bits))
+    }
+
+    /// Counts zero bits up to the next one bit
+    fn read_unary(&mut self) -> Result<u64, std::io::Error> {
+        let mut count = 0;
+        while self.read(1)? == 0 {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn align(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct BitWriter {
+    pub data: Vec<u8>,
+    /// Bits used in the last byte, 0 when it is full
+    used: u32,
+}
+
+impl BitWriter {
+    pub fn write(&mut self, value: u64, bits: u32) {
+        let mut remaining = bits;
+        while remaining > 0 {
+            if self.used == 0 {
+                self.data.push(0);
+            }
+            let available = 8 - self.used;
+            let taken = available.min(remaining);
+            let chunk = ((value >> (remaining - taken)) & (0xFF >> (8 - taken))) as u8;
+            let last = self.data.len() - 1;
+            self.data[last] |= chunk << (available - taken);
+            self.used = (self.used + taken) % 8;
+            remaining -= taken;
+        }
+    }
+
+    pub fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64 & (u64::MAX >> (64 - bits)), bits);
+    }
+
+    pub fn align(&mut self) {
+        self.used = 0;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FrameHeader {
+    /// Whether `number` counts samples rather than frames
+    pub variable_block_size: bool,
+    pub block_size: u32,
+    /// None when the rate is taken from STREAMINFO
+    pub sample_rate: Option<u32>,
+    /// 0 to 7 are independent channels, 8 left/side, 9 right/side and 10 mid/side
+    pub channel_assignment: u8,
+    /// None when the depth is taken from STREAMINFO
+    pub bits_per_sample: Option<u32>,
+    pub number: u64,
+    /// Bytes of the coded frame or sample number
+    pub number_length: usize,
+    /// Bytes of the whole header, including its CRC-8
+    pub length: usize,
+}
+
+impl FrameHeader {
+    pub fn channels(&self) -> usize {
+        match self.channel_assignment {
+            8..=10 => 2,
+            assignment => usize::from(assignment) + 1,
+        }
+    }
+}
+
+const SAMPLE_RATES: [u32; 11] = [
+    88200, 176400, 192000, 8000, 16000, 22050, 24000, 32000, 44100, 48000, 96000,
+];
+const SAMPLE_SIZES: [Option<u32>; 8] = [
+    None,
+    Some(8),
+    Some(12),
+    None,
+    Some(16),
+    Some(20),
+    Some(24),
+    Some(32),
+];
+
+/// Reads the UTF-8 style coded number. Returns the number and its length.
+fn read_coded_number(data: &[u8]) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let length = match first.leading_ones() {
+        0 => return Some((u64::from(first), 1)),
+        1 => return None,
+        ones if ones <= 7 => ones as usize,
+        _ => return None,
+    };
+
+    let mut number = u64::from(first) & (0x7F >> length);
+    for byte in data.get(1..length)? {
+        if byte & 0xC0 != 0x80 {
+            return None;
+        }
+        number = (number << 6) | u64::from(byte & 0x3F);
+    }
+    Some((number, length))
+}
+
+fn write_coded_number(number: u64) -> Vec<u8> {
+    if number < 0x80 {
+        return vec![number as u8];
+    }
+
+    let length = match 64 - number.leading_zeros() {
+        0..=11 => 2,
+        12..=16 => 3,
+        17..=21 => 4,
+        22..=26 => 5,
+        27..=31 => 6,
+        _ => 7,
+    };
+    let mut bytes = vec![0u8; length];
+    let mut rest = number;
+    for byte in bytes[1..].iter_mut().rev() {
+        *byte = 0x80 | (rest & 0x3F) as u8;
+        rest >>= 6;
+    }
+    bytes[0] = (0xFF00u16 >> length) as u8 | rest as u8;
+    bytes
+}
+
+/// Parses the frame header at the start of `data`, None when there is no valid
+/// header there
+pub(crate) fn parse_header(data: &[u8]) -> Option<FrameHeader> {
+    let fixed = data.get(..4)?;
+    if fixed[0] != 0xFF || fixed[1] & 0xFE != 0xF8 || fixed[3] & 0x01 != 0 {
+        return None;
+    }
+    let (number, number_length) = read_coded_number(&data[4..])?;
+    let mut length = 4 + number_length;
+
+    let block_size = match fixed[2] >> 4 {
+        0 => return None,
+        1 => 192,
+        code @ 2..=5 => 576 << (code - 2),
+        6 => {
+            length += 1;
+            u32::from(*data.get(length - 1)?) + 1
+        }
+        7 => {
+            length += 2;
+            u32::from(u16::from_be_bytes([
+                *data.get(length - 2)?,
+                *data.get(length - 1)?,
+            ])) + 1
+        }
+        code => 256 << (code - 8),
+    };
+    let sample_rate = match fixed[2] & 0x0F {
+        0 => None,
+        code @ 1..=11 => Some(SAMPLE_RATES[usize::from(code - 1)]),
+        12 => {
+            length += 1;
+            Some(u32::from(*data.get(length - 1)?) * 1000)
+        }
+        13 => {
+            length += 2;
+            Some(u32::from(u16::from_be_bytes([
+                *data.get(length - 2)?,
+                *data.get(length - 1)?,
+            ])))
+        }
+        14 => {
+            length += 2;
+            Some(
+                u32::from(u16::from_be_bytes([
+                    *data.get(length - 2)?,
+                    *data.get(length - 1)?,
+                ])) * 10,
+            )
+        }
+        _ => return None,
+    };
+    let channel_assignment = fixed[3] >> 4;
+    if channel_assignment > 10 {
+        return None;
+    }
+    let size_code = (fixed[3] >> 1) & 0x07;
+    if size_code == 3 {
+        return None;
+    }
+
+    if crc8(data.get(..length)?) != *data.get(length)? {
+        return None;
+    }
+
+    Some(FrameHeader {
+        variable_block_size: fixed[1] & 0x01 != 0,
+        block_size,
+        sample_rate,
+        channel_assignment,
+        bits_per_sample: SAMPLE_SIZES[usize::from(size_code)],
+        number,
+        number_length,
+        length: length + 1,
+    })
+}
+
+/// A frame found in the audio of a stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Frame {
+    /// Offset from the first frame
+    pub offset: usize,
+    pub length: usize,
+    pub header: FrameHeader,
+    pub first_sample: u64,
+}
+
+/// Finds every frame of the audio. A frame ends where the next valid header starts
+/// and the CRC-16 of the bytes in between checks out.
+pub(crate) fn scan(audio: &[u8]) -> Result<Vec<Frame>, std::io::Error> {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+    let mut first_sample = 0;
+
+    while offset < audio.len() {
+        let header = match parse_header(&audio[offset..]) {
+            Some(header) => header,
+            None => {
+                return Err(invalid(&format!(
+                    "No valid frame header at audio byte {offset}"
+                )));
+            }
+        };
+
+        let mut crc = 0;
+        let mut end = None;
+        for position in offset..audio.len() {
+            if position >= offset + header.length + 2
+                && crc == 0
+                && audio[position] == 0xFF
+                && parse_header(&audio[position..]).is_some()
+            {
+                end = Some(position);
+                break;
+            }
+            crc = crc16_update(crc, audio[position]);
+        }
+        let end = match end {
+            Some(end) => end,
+            None if crc == 0 => audio.len(),
+            None => {
+                return Err(invalid(&format!(
+                    "Frame at audio byte {offset} fails its CRC-16"
+                )));
+            }
+        };
+
+        frames.push(Frame {
+            offset,
+            length: end - offset,
+            header,
+            first_sample,
+        });
+        first_sample += u64::from(header.block_size);
+        offset = end;
+    }
+
+    Ok(frames)
+}
+
+/// Decodes a frame into one vector of samples per channel
+pub(crate) fn decode(
+    data: &[u8],
+    stream_info: &flac::StreamInfo,
+) -> Result<Vec<Vec<i64>>, std::io::Error> {
+    let header = match parse_header(data) {
+        Some(header) => header,
+        None => return Err(invalid("No valid frame header")),
+    };
+    let bits_per_sample = header
+        .bits_per_sample
+        .unwrap_or(u32::from(stream_info.bits_per_sample));
+    let block_size = header.block_size as usize;
+
+    let mut reader = BitReader::new(&data[header.length..]);
+    let mut channels = Vec::new();
+    for channel in 0..header.channels() {
+        let side = matches!(
+            (header.channel_assignment, channel),
+            (8, 1) | (9, 0) | (10, 1)
+        );
+        let bits = if side {
+            bits_per_sample + 1
+        } else {
+            bits_per_sample
+        };
+        channels.push(decode_subframe(&mut reader, bits, block_size)?);
+    }
+    reader.align();
+    let footer = header.length + reader.position / 8;
+    match data.get(footer..footer + 2) {
+        Some(stored) if crc16(&data[..footer]) == u16::from_be_bytes([stored[0], stored[1]]) => {}
+        _ => return Err(invalid("Frame fails its CRC-16")),
+    }
+
+    if let [first, second] = channels.as_mut_slice() {
+        for (a, b) in first.iter_mut().zip(second.iter_mut()) {
+            match header.channel_assignment {
+                8 => *b = *a - *b,
+                9 => *a += *b,
+                10 => {
+                    let mid = (*a << 1) | (*b & 1);
+                    let side = *b;
+                    *a = (mid + side) >> 1;
+                    *b = (mid - side) >> 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(channels)
+}
+
+fn decode_subframe(
+    reader: &mut BitReader,
+    bits: u32,
+    block_size: usize,
+) -> Result<Vec<i64>, std::io::Error> {
+    if reader.read(1)? != 0 {
+        return Err(invalid("Subframe padding bit is set"));
+    }
+    let subframe_type = reader.read(6)?;
+    let wasted = if reader.read(1)? == 1 {
+        reader.read_unary()? as u32 + 1
+    } else {
+        0
+    };
+    if wasted >= bits {
+        return Err(invalid("Subframe wastes every bit"));
+    }
+    let bits = bits - wasted;
+
+    let mut samples = match subframe_type {
+        0 => vec![reader.read_signed(bits)?; block_size],
+        1 => {
+            let mut samples = Vec::with_capacity(block_size);
+            for _ in 0..block_size {
+                samples.push(reader.read_signed(bits)?);
+            }
+            samples
+        }
+        8..=12 => {
+            let order = (subframe_type - 8) as usize;
+            let coefficients: &[i64] = match order {
+                0 => &[],
+                1 => &[1],
+                2 => &[2, -1],
+                3 => &[3, -3, 1],
+                _ => &[4, -6, 4, -1],
+            };
+            decode_predicted(reader, bits, block_size, coefficients, 0)?
+        }
+        32..=63 => {
+            let order = (subframe_type - 31) as usize;
+            let mut warmup = Vec::with_capacity(order);
+            for _ in 0..order {
+                warmup.push(reader.read_signed(bits)?);
+            }
+            let precision = reader.read(4)? as u32 + 1;
+            if precision == 16 {
+                return Err(invalid("Invalid LPC coefficient precision"));
+            }
+            let shift = reader.read_signed(5)?;
+            if shift < 0 {
+                return Err(invalid("Negative LPC shift"));
+            }
+            let mut coefficients = Vec::with_capacity(order);
+            for _ in 0..order {
+                coefficients.push(reader.read_signed(precision)?);
+            }
+            predict(reader, warmup, block_size, &coefficients, shift as u32)?
+        }
+        _ => return Err(invalid("Reserved subframe type")),
+    };
+
+    if wasted > 0 {
+        for sample in samples.iter_mut() {
+            *sample <<= wasted;
+        }
+    }
+    Ok(samples)
+}
+
+fn decode_predicted(
+    reader: &mut BitReader,
+    bits: u32,
+    block_size: usize,
+    coefficients: &[i64],
+    shift: u32,
+) -> Result<Vec<i64>, std::io::Error> {
+    let mut warmup = Vec::with_capacity(coefficients.len());
+    for _ in 0..coefficients.len() {
+        warmup.push(reader.read_signed(bits)?);
+    }
+    predict(reader, warmup, block_size, coefficients, shift)
+}
+
+/// Reads the residual after the warm-up samples and adds the prediction. The
+/// first coefficient applies to the previous sample.
+fn predict(
+    reader: &mut BitReader,
+    mut samples: Vec<i64>,
+    block_size: usize,
+    coefficients: &[i64],
+    shift: u32,
+) -> Result<Vec<i64>, std::io::Error> {
+    let order = coefficients.len();
+    if order > block_size {
+        return Err(invalid("Predictor order exceeds the block size"));
+    }
+    let residual = decode_residual(reader, block_size, order)?;
+
+    samples.reserve(block_size - order);
+    for (i, residual) in residual.into_iter().enumerate() {
+        let position = order + i;
+        let prediction: i64 = coefficients
+            .iter()
+            .enumerate()
+            .map(|(j, coefficient)| coefficient * samples[position - 1 - j])
+            .sum();
+        samples.push((prediction >> shift) + residual);
+    }
+    Ok(samples)
+}
+
+fn decode_residual(
+    reader: &mut BitReader,
+    block_size: usize,
+    order: usize,
+) -> Result<Vec<i64>, std::io::Error> {
+    let parameter_bits = match reader.read(2)? {
+        0 => 4,
+        1 => 5,
+        _ => return Err(invalid("Reserved residual coding method")),
+    };
+    let partition_order = reader.read(4)? as u32;
+    let partitions = 1usize << partition_order;
+    if !block_size.is_multiple_of(partitions) || block_size / partitions < order {
+        return Err(invalid("Invalid residual partition order"));
+    }
+
+    let mut residual = Vec::with_capacity(block_size - order);
+    for partition in 0..partitions {
+        let count = block_size / partitions - if partition == 0 { order } else { 0 };
+        let parameter = reader.read(parameter_bits)? as u32;
+        if parameter == (1 << parameter_bits) - 1 {
+            let bits = reader.read(5)? as u32;
+            for _ in 0..count {
+                residual.push(reader.read_signed(bits)?);
+            }
+        } else {
+            for _ in 0..count {
+                let value = (reader.read_unary()? << parameter) | reader.read(parameter)?;
+                residual.push((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+    }
+    Ok(residual)
+}
+
+fn sample_rate_code(sample_rate: u32) -> u8 {
+    match SAMPLE_RATES.iter().position(|rate| *rate == sample_rate) {
+        Some(position) => position as u8 + 1,
+        None => 0,
+    }
+}
+
+fn sample_size_code(bits_per_sample: u32) -> u8 {
+    match SAMPLE_SIZES
+        .iter()
+        .position(|size| *size == Some(bits_per_sample))
+    {
+        Some(position) => position as u8,
+        None => 0,
+    }
+}
+
+/// Encodes the samples as one frame of verbatim subframes with independent
+/// channels, numbered by its first sample. A frame holds 1 to 65536 samples.
+pub(crate) fn encode_verbatim(
+    channels: &[Vec<i64>],
+    first_sample: u64,
+    stream_info: &flac::StreamInfo,
+) -> Result<Vec<u8>, std::io::Error> {
+    let block_size = channels[0].len();
+    if !(1..=65536).contains(&block_size) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("A frame cannot hold {block_size} samples"),
+        ));
+    }
+    let bits_per_sample = u32::from(stream_info.bits_per_sample);
+
+    let mut header = vec![
+        0xFF,
+        0xF9,
+        0x70 | sample_rate_code(stream_info.sample_rate),
+        ((channels.len() as u8 - 1) << 4) | (sample_size_code(bits_per_sample) << 1),
+    ];
+    header.extend(write_coded_number(first_sample));
+    header.extend_from_slice(&((block_size - 1) as u16).to_be_bytes());
+    header.push(crc8(&header));
+
+    let mut writer = BitWriter {
+        data: header,
+        used: 0,
+    };
+    for channel in channels {
+        writer.write(0x02, 8);
+        for sample in channel {
+            writer.write_signed(*sample, bits_per_sample);
+        }
+    }
+    writer.align();
+
+    let mut data = writer.data;
+    let crc = crc16(&data);
+    data.extend_from_slice(&crc.to_be_bytes());
+    Ok(data)
+}
+
+/// The frame with its header switched to sample numbering starting at
+/// `first_sample`, with both CRCs updated
+pub(crate) fn renumber(data: &[u8], header: &FrameHeader, first_sample: u64) -> Vec<u8> {
+    let mut renumbered = vec![0xFF, 0xF9, data[2], data[3]];
+    renumbered.extend(write_coded_number(first_sample));
+    renumbered.extend_from_slice(&data[4 + header.number_length..header.length - 1]);
+    renumbered.push(crc8(&renumbered));
+    renumbered.extend_from_slice(&data[header.length..data.len() - 2]);
+    let crc = crc16(&renumbered);
+    renumbered.extend_from_slice(&crc.to_be_bytes());
+    renumbered
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    fn stream_info(bits_per_sample: u8) -> crate::flac::StreamInfo {
+        crate::flac::StreamInfo {
+            min_block_size: 16,
+            max_block_size: 4096,
+            min_frame_size: 0,
+            max_frame_size: 0,
+            sample_rate: 44100,
+            channels: 2,
+            bits_per_sample,
+            total_samples: 0,
+            md5: [0; 16],
+        }
+    }
+
+    #[test]
+    fn test_coded_numbers() {
+        for number in [0, 0x7F, 0x80, 0x7FF, 0x800, 0xFFFF, 1 << 30, (1 << 36) - 1] {
+            let bytes = super::write_coded_number(number);
+            assert_eq!(
+                Some((number, bytes.len())),
+                super::read_coded_number(&bytes)
+            );
+        }
+        assert_eq!(vec![0xC2, 0xA9], super::write_coded_number(0xA9));
+    }
+
+    #[test]
+    fn test_decode_fixture() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let data = util::get_data_from_file(&filepath).unwrap();
+        let raw = crate::flac::parse(&data).unwrap();
+        let stream_info = crate::flac::read_stream_info(&filepath).unwrap();
+
+        let frames = super::scan(&raw.audio).unwrap();
+        assert_eq!(
+            raw.audio.len(),
+            frames.iter().map(|frame| frame.length).sum::<usize>()
+        );
+        let mut total = 0;
+        for frame in &frames {
+            let frame_data = &raw.audio[frame.offset..frame.offset + frame.length];
+            let channels = super::decode(frame_data, &stream_info).unwrap();
+            assert_eq!(2, channels.len());
+            assert_eq!(frame.header.block_size as usize, channels[0].len());
+            total += channels[0].len() as u64;
+
+            // Both rewrites decode to the same samples
+            let renumbered = super::renumber(frame_data, &frame.header, 12345);
+            assert_eq!(channels, super::decode(&renumbered, &stream_info).unwrap());
+            assert_eq!(
+                Some(12345),
+                super::parse_header(&renumbered).map(|header| header.number)
+            );
+            let verbatim =
+                super::encode_verbatim(&channels, frame.first_sample, &stream_info).unwrap();
+            assert_eq!(channels, super::decode(&verbatim, &stream_info).unwrap());
+        }
+        assert_eq!(stream_info.total_samples, total);
+    }
+
+    #[test]
+    fn test_encode_verbatim_block_sizes() {
+        let stream_info = stream_info(16);
+        let channels = vec![vec![7i64; 65536], vec![-7i64; 65536]];
+        let data = super::encode_verbatim(&channels, 0, &stream_info).unwrap();
+        assert_eq!(
+            Some(65536),
+            super::parse_header(&data).map(|header| header.block_size)
+        );
+        assert_eq!(channels, super::decode(&data, &stream_info).unwrap());
+
+        for length in [0, 65537] {
+            let channels = vec![vec![0i64; length]; 2];
+            assert_eq!(
+                std::io::ErrorKind::InvalidInput,
+                super::encode_verbatim(&channels, 0, &stream_info)
+                    .unwrap_err()
+                    .kind()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_predicted() {
+        // One FIXED order 2 and one LPC order 1 subframe, with Rice coded residuals
+        let left: Vec<i64> = vec![10, 12, 15, 19, 24, 30, 37, 45];
+        let right: Vec<i64> = vec![-3, -6, -12, -24, -48, -96, -192, -384];
+        let mut writer = super::BitWriter::default();
+        writer.write(0x0000_FFF8, 16);
+        writer.write(0x69, 8);
+        writer.write(0x18, 8);
+        writer.write(0, 8);
+        writer.write(7, 8);
+        let crc = super::crc8(&writer.data);
+        writer.write(u64::from(crc), 8);
+
+        let rice = |writer: &mut super::BitWriter, residual: &[i64], parameter: u32| {
+            writer.write(0, 2);
+            writer.write(0, 4);
+            writer.write(u64::from(parameter), 4);
+            for value in residual {
+                let folded = if *value < 0 {
+                    (-2 * value - 1) as u64
+                } else {
+                    (2 * value) as u64
+                };
+                writer.write(1, (folded >> parameter) as u32 + 1);
+                writer.write(folded & ((1 << parameter) - 1), parameter);
+            }
+        };
+
+        writer.write(0x14, 8);
+        writer.write_signed(left[0], 16);
+        writer.write_signed(left[1], 16);
+        let residual: Vec<i64> = (2..8)
+            .map(|i| left[i] - (2 * left[i - 1] - left[i - 2]))
+            .collect();
+        rice(&mut writer, &residual, 1);
+
+        writer.write(0x40, 8);
+        writer.write_signed(right[0], 16);
+        writer.write(3, 4);
+        writer.write_signed(0, 5);
+        writer.write_signed(2, 4);
+        let residual: Vec<i64> = (1..8).map(|i| right[i] - 2 * right[i - 1]).collect();
+        rice(&mut writer, &residual, 0);
+
+        writer.align();
+        let mut data = writer.data;
+        let crc = super::crc16(&data);
+        data.extend_from_slice(&crc.to_be_bytes());
+
+        assert_eq!(
+            vec![left, right],
+            super::decode(&data, &stream_info(16)).unwrap()
+        );
+        data[9] ^= 0x01;
+        assert!(super::decode(&data, &stream_info(16)).is_err());
+    }
+}
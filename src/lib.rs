@@ -1,7 +1,24 @@
+pub mod batch;
+pub mod copy;
+pub mod cue;
 pub mod detection;
+pub mod diff;
+pub mod flac;
+mod frame;
+pub mod lyrics;
 pub mod meta;
+#[cfg(feature = "tokio")]
+pub mod nonblocking;
 pub mod properties;
+pub mod rename;
+pub mod scan;
+#[cfg(feature = "serde")]
+pub mod sidecar;
+pub mod split;
+pub mod strip;
 pub mod types;
+mod util;
+pub mod validate;
 
 pub mod test_util {
     pub mod util {
@@ -98,6 +115,34 @@ pub mod test_util {
 
         pub const TESTFILEDIRECTORY: &str = "tests/sample_tracks3";
 
+        /// Copies a file of the test directory into `test_dir` and returns the new path
+        pub fn copy_track(test_dir: &str, filename: &str) -> String {
+            let filepath = get_full_path(TESTFILEDIRECTORY, filename).unwrap();
+            let new_filepath = get_full_path(test_dir, filename).unwrap();
+            copy_file(&filepath, &new_filepath).unwrap();
+            new_filepath
+        }
+
+        /// A tenth of a second of silent 16-bit mono WAV at 8 kHz
+        pub fn wav_data() -> Vec<u8> {
+            let samples = vec![0u8; 1600];
+            let mut data = Vec::new();
+            data.extend_from_slice(b"RIFF");
+            data.extend_from_slice(&(36 + samples.len() as u32).to_le_bytes());
+            data.extend_from_slice(b"WAVEfmt ");
+            data.extend_from_slice(&16u32.to_le_bytes());
+            data.extend_from_slice(&1u16.to_le_bytes());
+            data.extend_from_slice(&1u16.to_le_bytes());
+            data.extend_from_slice(&8000u32.to_le_bytes());
+            data.extend_from_slice(&16000u32.to_le_bytes());
+            data.extend_from_slice(&2u16.to_le_bytes());
+            data.extend_from_slice(&16u16.to_le_bytes());
+            data.extend_from_slice(b"data");
+            data.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+            data.extend_from_slice(&samples);
+            data
+        }
+
         pub fn get_filename(track: i32) -> String {
             const FLAC_EXTENSION: &str = ".flac";
 
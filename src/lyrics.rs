@@ -0,0 +1,612 @@
+//! Plain and synchronized lyrics, stored in the Vorbis comments of FLAC songs, in
+//! USLT and SYLT frames of ID3v2 tags, or in `.lrc` files next to the song
+
+use lofty::file::AudioFile;
+use lofty::tag::TagExt;
+
+/// Comment holding the lyrics. Synchronized lyrics are stored in it as LRC.
+pub const LYRICS: &str = "LYRICS";
+/// Comment some taggers use instead of `LYRICS`, read as a fallback
+pub const UNSYNCED_LYRICS: &str = "UNSYNCEDLYRICS";
+
+const SYLT: &str = "SYLT";
+/// ISO-639-2 code for an unknown language
+const UNKNOWN_LANGUAGE: [u8; 3] = *b"XXX";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LyricLine {
+    pub time: std::time::Duration,
+    pub text: String,
+}
+
+/// Synchronized lyrics in LRC format
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lrc {
+    /// ID tags such as `ti`, `ar` and `al`, with lowercased keys. `offset` is kept
+    /// separately.
+    pub tags: Vec<(String, String)>,
+    /// Milliseconds the lines show before their timestamps, from `[offset:]`
+    pub offset: i64,
+    /// Sorted by time. A line with several timestamps appears once for each.
+    pub lines: Vec<LyricLine>,
+}
+
+impl Lrc {
+    /// The lines with the offset applied to their timestamps
+    pub fn adjusted_lines(&self) -> Vec<LyricLine> {
+        self.lines
+            .iter()
+            .map(|line| {
+                let millis = line.time.as_millis() as i64 - self.offset;
+                LyricLine {
+                    time: std::time::Duration::from_millis(millis.max(0) as u64),
+                    text: line.text.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn invalid(line: usize, message: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Line {line}: {message}"),
+    )
+}
+
+/// Parses `mm:ss`, `mm:ss.xx`, `mm:ss.xxx` and `mm:ss:xx`
+fn parse_timestamp(text: &str) -> Option<std::time::Duration> {
+    let (minutes, rest) = text.split_once(':')?;
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, Some(fraction)),
+        None => (rest, None),
+    };
+    let digits = |value: &str| !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit());
+    if !digits(minutes) || seconds.len() != 2 || !digits(seconds) {
+        return None;
+    }
+
+    let seconds: u64 = seconds.parse().ok()?;
+    if seconds >= 60 {
+        return None;
+    }
+    let millis = match fraction {
+        Some(fraction) if fraction.len() <= 3 && digits(fraction) => {
+            fraction.parse::<u64>().ok()? * 10u64.pow(3 - fraction.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    Some(std::time::Duration::from_millis(
+        (minutes.parse::<u64>().ok()? * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+fn format_timestamp(time: std::time::Duration) -> String {
+    let millis = time.as_millis();
+    let (minutes, seconds, millis) = (millis / 60000, millis / 1000 % 60, millis % 1000);
+    match millis % 10 {
+        0 => format!("{minutes:02}:{seconds:02}.{:02}", millis / 10),
+        _ => format!("{minutes:02}:{seconds:02}.{millis:03}"),
+    }
+}
+
+/// Parses LRC text. Lines are a tag like `[ar:KD]`, one or more timestamps
+/// followed by the text, or empty. Errors name the line.
+pub fn parse_lrc(text: &str) -> Result<Lrc, std::io::Error> {
+    let mut lrc = Lrc::default();
+
+    for (index, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut rest = line;
+        let mut times = Vec::new();
+        while let Some(bracketed) = rest.strip_prefix('[') {
+            let (inner, after) = match bracketed.split_once(']') {
+                Some(split) => split,
+                None => return Err(invalid(number, "Unclosed [")),
+            };
+            match parse_timestamp(inner.trim()) {
+                Some(time) => times.push(time),
+                None => break,
+            }
+            rest = after;
+        }
+
+        if times.is_empty() {
+            let tag = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                .and_then(|tag| tag.split_once(':'));
+            match tag {
+                Some((key, value)) if key.trim().eq_ignore_ascii_case("offset") => {
+                    match value.trim().parse::<i64>() {
+                        Ok(offset) => lrc.offset = offset,
+                        Err(_) => return Err(invalid(number, "Invalid offset")),
+                    }
+                }
+                Some((key, value)) => lrc
+                    .tags
+                    .push((key.trim().to_lowercase(), String::from(value.trim()))),
+                None => return Err(invalid(number, "Expected a tag or a timestamp")),
+            }
+            continue;
+        }
+
+        for time in times {
+            lrc.lines.push(LyricLine {
+                time,
+                text: String::from(rest.trim()),
+            });
+        }
+    }
+
+    lrc.lines.sort_by_key(|line| line.time);
+    Ok(lrc)
+}
+
+/// Whether the text is LRC with at least one timed line
+pub fn is_lrc(text: &str) -> bool {
+    parse_lrc(text).is_ok_and(|lrc| !lrc.lines.is_empty())
+}
+
+impl std::fmt::Display for Lrc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in &self.tags {
+            writeln!(f, "[{key}:{value}]")?;
+        }
+        if self.offset != 0 {
+            writeln!(f, "[offset:{:+}]", self.offset)?;
+        }
+        for line in &self.lines {
+            writeln!(f, "[{}]{}", format_timestamp(line.time), line.text)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_flac_file(filepath: &String) -> Result<lofty::flac::FlacFile, std::io::Error> {
+    let mut content = std::fs::File::open(filepath)?;
+    match lofty::flac::FlacFile::read_from(
+        &mut content,
+        lofty::config::ParseOptions::new().read_properties(false),
+    ) {
+        Ok(flac_file) => Ok(flac_file),
+        Err(err) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            err.to_string(),
+        )),
+    }
+}
+
+/// The lyrics of the song from `LYRICS`, or else `UNSYNCEDLYRICS`. Synchronized
+/// lyrics are returned as their LRC text.
+pub fn get_lyrics(filepath: &String) -> Result<Option<String>, std::io::Error> {
+    match read_flac_file(filepath)?.vorbis_comments() {
+        Some(vb) => Ok(vb
+            .get(LYRICS)
+            .or_else(|| vb.get(UNSYNCED_LYRICS))
+            .map(String::from)),
+        None => Ok(None),
+    }
+}
+
+/// Stores the lyrics under `LYRICS` and removes `UNSYNCEDLYRICS`, creating the
+/// comment block if the song has none
+pub fn set_lyrics(filepath: &String, lyrics: &str) -> Result<(), std::io::Error> {
+    match crate::flac::modify(
+        filepath,
+        &crate::flac::WriteOptions::default(),
+        |flac_file| {
+            let mut vb = flac_file.remove_vorbis_comments().unwrap_or_default();
+            vb.remove(UNSYNCED_LYRICS).for_each(drop);
+            vb.insert(String::from(LYRICS), String::from(lyrics));
+            flac_file.set_vorbis_comments(vb);
+            Ok(())
+        },
+    ) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Removes `LYRICS` and `UNSYNCEDLYRICS` and returns the removed values
+pub fn remove_lyrics(filepath: &String) -> Result<Vec<String>, std::io::Error> {
+    crate::meta::metadata::remove_comments(
+        filepath,
+        &[String::from(LYRICS), String::from(UNSYNCED_LYRICS)],
+    )
+}
+
+/// The lyrics of the song when they are LRC
+pub fn get_synced_lyrics(filepath: &String) -> Result<Option<Lrc>, std::io::Error> {
+    match get_lyrics(filepath)? {
+        Some(text) if is_lrc(&text) => Ok(Some(parse_lrc(&text)?)),
+        _ => Ok(None),
+    }
+}
+
+pub fn set_synced_lyrics(filepath: &String, lrc: &Lrc) -> Result<(), std::io::Error> {
+    set_lyrics(filepath, &lrc.to_string())
+}
+
+/// The ID3v2 tag of an MP3, WAV or AIFF file
+pub(crate) fn read_id3v2(
+    filepath: &String,
+) -> Result<Option<lofty::id3::v2::Id3v2Tag>, std::io::Error> {
+    let mut content = std::fs::File::open(filepath)?;
+    let parse_options = lofty::config::ParseOptions::new().read_properties(false);
+    let tag = match lofty::file::FileType::from_path(filepath) {
+        Some(lofty::file::FileType::Mpeg) => {
+            lofty::mpeg::MpegFile::read_from(&mut content, parse_options)
+                .map(|file| file.id3v2().cloned())
+        }
+        Some(lofty::file::FileType::Wav) => {
+            lofty::iff::wav::WavFile::read_from(&mut content, parse_options)
+                .map(|file| file.id3v2().cloned())
+        }
+        Some(lofty::file::FileType::Aiff) => {
+            lofty::iff::aiff::AiffFile::read_from(&mut content, parse_options)
+                .map(|file| file.id3v2().cloned())
+        }
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("{filepath} is not an MP3, WAV or AIFF file"),
+            ));
+        }
+    };
+
+    match tag {
+        Ok(tag) => Ok(tag),
+        Err(err) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            err.to_string(),
+        )),
+    }
+}
+
+fn save_id3v2(filepath: &String, tag: &lofty::id3::v2::Id3v2Tag) -> Result<(), std::io::Error> {
+    match tag.save_to_path(filepath, lofty::config::WriteOptions::default()) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(std::io::Error::other(err.to_string())),
+    }
+}
+
+fn sylt_id() -> lofty::id3::v2::FrameId<'static> {
+    lofty::id3::v2::FrameId::Valid(std::borrow::Cow::Borrowed(SYLT))
+}
+
+/// The first USLT frame of the ID3v2 tag of an MP3, WAV or AIFF file
+pub fn get_id3_lyrics(filepath: &String) -> Result<Option<String>, std::io::Error> {
+    match read_id3v2(filepath)? {
+        Some(tag) => Ok(tag.unsync_text().next().map(|frame| frame.content.clone())),
+        None => Ok(None),
+    }
+}
+
+/// Replaces the USLT frames of the ID3v2 tag with one holding the lyrics
+pub fn set_id3_lyrics(filepath: &String, lyrics: &str) -> Result<(), std::io::Error> {
+    let mut tag = read_id3v2(filepath)?.unwrap_or_default();
+    tag.retain(|frame| !matches!(frame, lofty::id3::v2::Frame::UnsynchronizedText(_)));
+    tag.insert(lofty::id3::v2::Frame::UnsynchronizedText(
+        lofty::id3::v2::UnsynchronizedTextFrame::new(
+            lofty::TextEncoding::UTF8,
+            UNKNOWN_LANGUAGE,
+            String::new(),
+            String::from(lyrics),
+        ),
+    ));
+    save_id3v2(filepath, &tag)
+}
+
+/// The first SYLT frame of the ID3v2 tag of an MP3, WAV or AIFF file. Frames
+/// timed in MPEG frames instead of milliseconds are not supported.
+pub fn get_id3_synced_lyrics(filepath: &String) -> Result<Option<Lrc>, std::io::Error> {
+    let tag = match read_id3v2(filepath)? {
+        Some(tag) => tag,
+        None => return Ok(None),
+    };
+    let data = match tag.get(&sylt_id()) {
+        Some(lofty::id3::v2::Frame::Binary(frame)) => &frame.data,
+        _ => return Ok(None),
+    };
+
+    let frame = match lofty::id3::v2::SynchronizedTextFrame::parse(
+        data,
+        lofty::id3::v2::FrameFlags::default(),
+    ) {
+        Ok(frame) => frame,
+        Err(err) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                err.to_string(),
+            ));
+        }
+    };
+    if frame.timestamp_format != lofty::id3::v2::TimestampFormat::MS {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "SYLT frame is timed in MPEG frames",
+        ));
+    }
+
+    Ok(Some(Lrc {
+        tags: Vec::new(),
+        offset: 0,
+        lines: frame
+            .content
+            .into_iter()
+            .map(|(millis, text)| LyricLine {
+                time: std::time::Duration::from_millis(u64::from(millis)),
+                text,
+            })
+            .collect(),
+    }))
+}
+
+/// Replaces the SYLT frames of the ID3v2 tag with one holding the lines. SYLT has
+/// no offset, so it is applied to the timestamps, and LRC tags are dropped.
+pub fn set_id3_synced_lyrics(filepath: &String, lrc: &Lrc) -> Result<(), std::io::Error> {
+    let content = lrc
+        .adjusted_lines()
+        .into_iter()
+        .map(|line| {
+            (
+                line.time.as_millis().min(u128::from(u32::MAX)) as u32,
+                line.text,
+            )
+        })
+        .collect();
+    let frame = lofty::id3::v2::SynchronizedTextFrame::new(
+        lofty::TextEncoding::UTF8,
+        UNKNOWN_LANGUAGE,
+        lofty::id3::v2::TimestampFormat::MS,
+        lofty::id3::v2::SyncTextContentType::Lyrics,
+        None,
+        content,
+    );
+    let data = match frame.as_bytes() {
+        Ok(data) => data,
+        Err(err) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                err.to_string(),
+            ));
+        }
+    };
+
+    let mut tag = read_id3v2(filepath)?.unwrap_or_default();
+    tag.remove(&sylt_id()).for_each(drop);
+    tag.insert(lofty::id3::v2::Frame::Binary(
+        lofty::id3::v2::BinaryFrame::new(sylt_id(), data),
+    ));
+    save_id3v2(filepath, &tag)
+}
+
+/// The `.lrc` file next to the song, with the same name
+pub fn lrc_path(song_filepath: &String) -> String {
+    std::path::Path::new(song_filepath)
+        .with_extension("lrc")
+        .display()
+        .to_string()
+}
+
+pub fn read_lrc_file(filepath: &String) -> Result<Lrc, std::io::Error> {
+    match String::from_utf8(std::fs::read(filepath)?) {
+        Ok(text) => parse_lrc(&text),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{filepath} is not valid UTF-8"),
+        )),
+    }
+}
+
+pub fn write_lrc_file(filepath: &String, lrc: &Lrc) -> Result<(), std::io::Error> {
+    std::fs::write(filepath, lrc.to_string())
+}
+
+/// Embeds the lyrics of a `.lrc` file, by default the one next to the song, in
+/// the `LYRICS` comment of the song
+pub fn import_lrc(
+    song_filepath: &String,
+    lrc_filepath: Option<&String>,
+) -> Result<Lrc, std::io::Error> {
+    let lrc = match lrc_filepath {
+        Some(lrc_filepath) => read_lrc_file(lrc_filepath)?,
+        None => read_lrc_file(&lrc_path(song_filepath))?,
+    };
+    set_synced_lyrics(song_filepath, &lrc)?;
+    Ok(lrc)
+}
+
+/// Writes the synchronized lyrics of the song to a `.lrc` file, by default next to
+/// the song, and returns its path. Nothing is written when the song has no LRC
+/// lyrics.
+pub fn export_lrc(
+    song_filepath: &String,
+    lrc_filepath: Option<&String>,
+) -> Result<Option<String>, std::io::Error> {
+    match get_synced_lyrics(song_filepath)? {
+        Some(lrc) => {
+            let lrc_filepath = match lrc_filepath {
+                Some(lrc_filepath) => lrc_filepath.clone(),
+                None => lrc_path(song_filepath),
+            };
+            write_lrc_file(&lrc_filepath, &lrc)?;
+            Ok(Some(lrc_filepath))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    const SAMPLE_LRC: &str = "\u{feff}[ti:Just roll it]
+[ar:KD]
+[offset:+250]
+
+[00:12.00]Roll it
+[00:05.5][00:20.123]Just roll it
+[01:02:50]Once more
+";
+
+    fn line(millis: u64, text: &str) -> super::LyricLine {
+        super::LyricLine {
+            time: std::time::Duration::from_millis(millis),
+            text: String::from(text),
+        }
+    }
+
+    #[test]
+    fn test_parse_lrc() {
+        let lrc = super::parse_lrc(SAMPLE_LRC).unwrap();
+        assert_eq!(
+            vec![
+                (String::from("ti"), String::from("Just roll it")),
+                (String::from("ar"), String::from("KD")),
+            ],
+            lrc.tags
+        );
+        assert_eq!(250, lrc.offset);
+        assert_eq!(
+            vec![
+                line(5500, "Just roll it"),
+                line(12000, "Roll it"),
+                line(20123, "Just roll it"),
+                line(62500, "Once more"),
+            ],
+            lrc.lines
+        );
+        assert_eq!(line(5250, "Just roll it"), lrc.adjusted_lines()[0]);
+
+        let text = lrc.to_string();
+        assert_eq!(
+            "[ti:Just roll it]\n[ar:KD]\n[offset:+250]\n[00:05.50]Just roll it\n[00:12.00]Roll it\n[00:20.123]Just roll it\n[01:02.50]Once more\n",
+            text
+        );
+        assert_eq!(lrc, super::parse_lrc(&text).unwrap());
+
+        assert!(super::is_lrc(SAMPLE_LRC));
+        assert!(!super::is_lrc("Just roll it\nRoll it"));
+        assert!(!super::is_lrc("[ar:KD]"));
+        assert_eq!(
+            "Line 2: Expected a tag or a timestamp",
+            super::parse_lrc("[00:01.00]Roll\nRoll it")
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "Line 1: Invalid offset",
+            super::parse_lrc("[offset:soon]").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_lyrics() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::copy_track(&test_dir, &util::get_filename(1));
+        assert_eq!(None, super::get_lyrics(&filepath).unwrap());
+
+        crate::meta::metadata::import_comments(
+            &filepath,
+            b"UNSYNCEDLYRICS=Just roll it\n",
+            crate::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+        assert_eq!(
+            Some(String::from("Just roll it")),
+            super::get_lyrics(&filepath).unwrap()
+        );
+        assert_eq!(None, super::get_synced_lyrics(&filepath).unwrap());
+
+        let lrc = super::parse_lrc(SAMPLE_LRC).unwrap();
+        super::set_synced_lyrics(&filepath, &lrc).unwrap();
+        assert_eq!(Some(lrc), super::get_synced_lyrics(&filepath).unwrap());
+        assert!(
+            crate::meta::metadata::get_comments(&filepath, super::UNSYNCED_LYRICS)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            "Just roll it",
+            crate::meta::metadata::get_meta(crate::types::Type::Title, &filepath).unwrap()
+        );
+
+        assert_eq!(1, super::remove_lyrics(&filepath).unwrap().len());
+        assert_eq!(None, super::get_lyrics(&filepath).unwrap());
+
+        // A song without a comment block gets one
+        crate::strip::strip(&filepath, &crate::strip::StripPolicy::KeepNothing).unwrap();
+        super::set_lyrics(&filepath, "Just roll it").unwrap();
+        assert_eq!(
+            Some(String::from("Just roll it")),
+            super::get_lyrics(&filepath).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_lrc_sidecar() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::copy_track(&test_dir, &util::get_filename(1));
+        let lrc_filepath = util::get_full_path(&test_dir, "track01.lrc").unwrap();
+        assert_eq!(lrc_filepath, super::lrc_path(&filepath));
+        assert_eq!(None, super::export_lrc(&filepath, None).unwrap());
+
+        std::fs::write(&lrc_filepath, SAMPLE_LRC).unwrap();
+        let lrc = super::import_lrc(&filepath, None).unwrap();
+        assert_eq!(4, lrc.lines.len());
+        std::fs::remove_file(&lrc_filepath).unwrap();
+
+        assert_eq!(
+            Some(lrc_filepath.clone()),
+            super::export_lrc(&filepath, None).unwrap()
+        );
+        assert_eq!(lrc, super::read_lrc_file(&lrc_filepath).unwrap());
+
+        std::fs::write(&lrc_filepath, b"[00:01.00]Just roll \xFF\n").unwrap();
+        assert_eq!(
+            std::io::ErrorKind::InvalidData,
+            super::read_lrc_file(&lrc_filepath).unwrap_err().kind()
+        );
+    }
+
+    /// A short silent 16 bit mono WAV file
+    #[test]
+    fn test_id3_lyrics() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let wav_filepath = util::get_full_path(&test_dir, "track01.wav").unwrap();
+        util::save_bytes_to_file(&util::wav_data(), &wav_filepath).unwrap();
+        assert_eq!(None, super::get_id3_lyrics(&wav_filepath).unwrap());
+        assert_eq!(None, super::get_id3_synced_lyrics(&wav_filepath).unwrap());
+
+        super::set_id3_lyrics(&wav_filepath, "Just roll it\nRoll it").unwrap();
+        let lrc = super::parse_lrc(SAMPLE_LRC).unwrap();
+        super::set_id3_synced_lyrics(&wav_filepath, &lrc).unwrap();
+        assert_eq!(
+            Some(String::from("Just roll it\nRoll it")),
+            super::get_id3_lyrics(&wav_filepath).unwrap()
+        );
+        let synced = super::get_id3_synced_lyrics(&wav_filepath)
+            .unwrap()
+            .unwrap();
+        assert_eq!(0, synced.offset);
+        assert_eq!(lrc.adjusted_lines(), synced.lines);
+
+        let flac_filepath = util::copy_track(&test_dir, &util::get_filename(1));
+        assert_eq!(
+            std::io::ErrorKind::Unsupported,
+            super::get_id3_lyrics(&flac_filepath).unwrap_err().kind()
+        );
+    }
+}
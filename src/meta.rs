@@ -1,3 +1,11 @@
+//! Reads and writes the tags and pictures of FLAC songs.
+//!
+//! Most functions that write have a `_with` variant taking `flac::WriteOptions`. When
+//! the new metadata fits in the old metadata and padding, only that region of the
+//! song is overwritten. Otherwise the song is rewritten with the padding of the
+//! options. The returned `flac::WriteReport` tells which happened. The plain
+//! variants use the default options.
+
 pub mod coverart {
 
     use lofty::{file::AudioFile, ogg::OggPictureStorage};
@@ -6,55 +14,30 @@ pub mod coverart {
         song_filepath: &String,
         coverart_filepath: &String,
     ) -> Result<Vec<u8>, std::io::Error> {
-        let coverart_path = std::path::Path::new(coverart_filepath);
-
-        match std::fs::File::open(song_filepath) {
-            Ok(mut file) => {
-                match lofty::flac::FlacFile::read_from(
-                    &mut file,
-                    lofty::config::ParseOptions::new(),
-                ) {
-                    Ok(mut flac_file) => {
-                        let mut coverart_file = std::fs::File::open(coverart_path).unwrap();
-                        match lofty::picture::Picture::from_reader(&mut coverart_file) {
-                            Ok(pic) => {
-                                match lofty::picture::PictureInformation::from_picture(&pic) {
-                                    Ok(info) => {
-                                        flac_file.set_picture(0, pic.clone(), info);
-
-                                        match flac_file.save_to_path(
-                                            song_filepath,
-                                            lofty::config::WriteOptions::default(),
-                                        ) {
-                                            Ok(_) => Ok(pic.into_data()),
-                                            Err(err) => Err(std::io::Error::new(
-                                                std::io::ErrorKind::InvalidData,
-                                                err.to_string(),
-                                            )),
-                                        }
-                                    }
-                                    Err(err) => Err(std::io::Error::new(
-                                        std::io::ErrorKind::InvalidData,
-                                        err.to_string(),
-                                    )),
-                                }
-                            }
-                            Err(err) => Err(std::io::Error::new(
-                                std::io::ErrorKind::InvalidData,
-                                err.to_string(),
-                            )),
-                        }
-                    }
-                    Err(err) => Err(std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        err.to_string(),
-                    )),
-                }
-            }
+        match set_coverart_with(
+            song_filepath,
+            coverart_filepath,
+            &crate::flac::WriteOptions::default(),
+        ) {
+            Ok((data, _)) => Ok(data),
             Err(err) => Err(err),
         }
     }
 
+    /// Like `set_coverart`, with the options to write the song with
+    pub fn set_coverart_with(
+        song_filepath: &String,
+        coverart_filepath: &String,
+        options: &crate::flac::WriteOptions,
+    ) -> Result<(Vec<u8>, crate::flac::WriteReport), std::io::Error> {
+        let coverart_path = std::path::Path::new(coverart_filepath);
+
+        crate::flac::modify(song_filepath, options, |flac_file| {
+            let mut coverart_file = std::fs::File::open(coverart_path)?;
+            embed_coverart(flac_file, &mut coverart_file)
+        })
+    }
+
     pub fn get_coverart(song_filepath: &String) -> Result<Vec<u8>, std::io::Error> {
         match std::fs::File::open(song_filepath) {
             Ok(mut file) => {
@@ -62,16 +45,7 @@ pub mod coverart {
                     &mut file,
                     lofty::config::ParseOptions::new(),
                 ) {
-                    Ok(flac_file) => {
-                        let pictures = flac_file.pictures();
-                        let res = pictures.to_vec();
-                        if !res.is_empty() {
-                            let picture = &res[0];
-                            Ok(picture.clone().0.into_data())
-                        } else {
-                            Ok(Vec::new())
-                        }
-                    }
+                    Ok(flac_file) => Ok(coverart_from_file(&flac_file)),
                     Err(err) => Err(std::io::Error::new(
                         std::io::ErrorKind::InvalidData,
                         err.to_string(),
@@ -112,42 +86,212 @@ pub mod coverart {
         }
     }
 
+    /// Removes the first picture of the song and returns its data. The song is
+    /// saved without the picture; earlier versions left the file unchanged.
     pub fn remove_coverart(song_filepath: &String) -> Result<Vec<u8>, std::io::Error> {
-        match std::fs::File::open(song_filepath) {
+        match remove_coverart_with(song_filepath, &crate::flac::WriteOptions::default()) {
+            Ok((data, _)) => Ok(data),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Like `remove_coverart`, with the options to write the song with
+    pub fn remove_coverart_with(
+        song_filepath: &String,
+        options: &crate::flac::WriteOptions,
+    ) -> Result<(Vec<u8>, crate::flac::WriteReport), std::io::Error> {
+        crate::flac::modify(song_filepath, options, take_coverart)
+    }
+
+    /// Embeds the front cover of `source_filepath`, or its first picture if it has no
+    /// front cover, as the first picture of `song_filepath` and returns its data
+    pub fn copy_coverart(
+        source_filepath: &String,
+        song_filepath: &String,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        match copy_coverart_with(
+            source_filepath,
+            song_filepath,
+            &crate::flac::WriteOptions::default(),
+        ) {
+            Ok((data, _)) => Ok(data),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Like `copy_coverart`, with the options to write the song with
+    pub fn copy_coverart_with(
+        source_filepath: &String,
+        song_filepath: &String,
+        options: &crate::flac::WriteOptions,
+    ) -> Result<(Vec<u8>, crate::flac::WriteReport), std::io::Error> {
+        let (pic, info) = match std::fs::File::open(source_filepath) {
             Ok(mut file) => {
                 match lofty::flac::FlacFile::read_from(
                     &mut file,
                     lofty::config::ParseOptions::new(),
                 ) {
-                    Ok(mut flac_file) => {
+                    Ok(flac_file) => {
                         let pictures = flac_file.pictures();
-                        let res = pictures.to_vec();
-                        if !res.is_empty() {
-                            let picture = &res[0];
-                            flac_file.remove_picture(0);
-                            Ok(picture.clone().0.into_data())
-                        } else {
-                            Err(std::io::Error::new(
-                                std::io::ErrorKind::NotFound,
-                                "No pictures found",
-                            ))
+                        match pictures
+                            .iter()
+                            .find(|(pic, _)| {
+                                pic.pic_type() == lofty::picture::PictureType::CoverFront
+                            })
+                            .or(pictures.first())
+                        {
+                            Some(picture) => picture.clone(),
+                            None => {
+                                return Err(std::io::Error::new(
+                                    std::io::ErrorKind::NotFound,
+                                    "No pictures found",
+                                ));
+                            }
                         }
                     }
-                    Err(err) => Err(std::io::Error::new(
+                    Err(err) => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            err.to_string(),
+                        ));
+                    }
+                }
+            }
+            Err(err) => return Err(err),
+        };
+
+        crate::flac::modify(song_filepath, options, |flac_file| {
+            flac_file.set_picture(0, pic.clone(), info);
+            Ok(pic.into_data())
+        })
+    }
+
+    /// Embeds the image as the first picture and returns its data
+    pub(crate) fn embed_coverart<R: std::io::Read>(
+        flac_file: &mut lofty::flac::FlacFile,
+        coverart: &mut R,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        match lofty::picture::Picture::from_reader(coverart) {
+            Ok(pic) => match lofty::picture::PictureInformation::from_picture(&pic) {
+                Ok(info) => {
+                    flac_file.set_picture(0, pic.clone(), info);
+                    Ok(pic.into_data())
+                }
+                Err(err) => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    err.to_string(),
+                )),
+            },
+            Err(err) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// Data of the first picture, empty if there is none
+    pub(crate) fn coverart_from_file(flac_file: &lofty::flac::FlacFile) -> Vec<u8> {
+        match flac_file.pictures().first() {
+            Some((pic, _)) => pic.data().to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Removes the first picture and returns its data
+    pub(crate) fn take_coverart(
+        flac_file: &mut lofty::flac::FlacFile,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        if flac_file.pictures().is_empty() {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No pictures found",
+            ))
+        } else {
+            Ok(flac_file.remove_picture(0).0.into_data())
+        }
+    }
+
+    pub(crate) fn picture_info(
+        pic: &lofty::picture::Picture,
+        info: &lofty::picture::PictureInformation,
+    ) -> crate::types::PictureInfo {
+        crate::types::PictureInfo {
+            picture_type: pic.pic_type().as_u8(),
+            mime: match pic.mime_type() {
+                Some(mime) => mime.as_str().to_owned(),
+                None => String::new(),
+            },
+            description: pic.description().map(|desc| desc.to_owned()),
+            width: info.width,
+            height: info.height,
+            color_depth: info.color_depth,
+            size: pic.data().len(),
+            hash: crate::util::hash_hex(pic.data()),
+            path: None,
+        }
+    }
+
+    /// Builds the pictures described by `wanted`, loading them from their path or
+    /// reusing an embedded picture with the same hash
+    pub(crate) fn resolve_pictures(
+        embedded: &[(lofty::picture::Picture, lofty::picture::PictureInformation)],
+        wanted: &[crate::types::PictureInfo],
+    ) -> Result<Vec<(lofty::picture::Picture, lofty::picture::PictureInformation)>, std::io::Error>
+    {
+        let mut pictures = Vec::new();
+
+        for picture_info in wanted {
+            let mut pic = match &picture_info.path {
+                Some(path) => match std::fs::File::open(path) {
+                    Ok(mut file) => match lofty::picture::Picture::from_reader(&mut file) {
+                        Ok(pic) => pic,
+                        Err(err) => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                format!("{path}: {err}"),
+                            ));
+                        }
+                    },
+                    Err(err) => return Err(err),
+                },
+                None => match embedded
+                    .iter()
+                    .find(|(pic, _)| crate::util::hash_hex(pic.data()) == picture_info.hash)
+                {
+                    Some((pic, _)) => pic.clone(),
+                    None => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("No embedded picture with hash {}", picture_info.hash),
+                        ));
+                    }
+                },
+            };
+
+            pic.set_pic_type(lofty::picture::PictureType::from_u8(
+                picture_info.picture_type,
+            ));
+            pic.set_description(picture_info.description.clone());
+
+            match lofty::picture::PictureInformation::from_picture(&pic) {
+                Ok(info) => pictures.push((pic, info)),
+                Err(err) => {
+                    return Err(std::io::Error::new(
                         std::io::ErrorKind::InvalidData,
                         err.to_string(),
-                    )),
+                    ));
                 }
             }
-            Err(err) => Err(err),
         }
+
+        Ok(pictures)
     }
 }
 
 pub mod metadata {
     use lofty::file::AudioFile;
+    use lofty::ogg::OggPictureStorage;
     use lofty::tag::Accessor;
-    use lofty::tag::TagExt;
 
     use crate::types;
 
@@ -158,22 +302,7 @@ pub mod metadata {
                     &mut content,
                     lofty::config::ParseOptions::new(),
                 ) {
-                    Ok(flac_file) => match flac_file.vorbis_comments() {
-                        Some(vb) => {
-                            let type_str: String = types::access::get_type(t).unwrap();
-                            match vb.get(&type_str) {
-                                Some(val) => Ok(val.to_owned()),
-                                None => Err(std::io::Error::new(
-                                    std::io::ErrorKind::InvalidData,
-                                    "Could not get tag data",
-                                )),
-                            }
-                        }
-                        None => Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            "No tags found",
-                        )),
-                    },
+                    Ok(flac_file) => meta_from_file(t, &flac_file),
                     Err(err) => Err(std::io::Error::new(
                         std::io::ErrorKind::InvalidData,
                         err.to_string(),
@@ -187,64 +316,151 @@ pub mod metadata {
     pub fn set_meta(
         t: types::Type,
         filepath: &String,
-        value: &String,
+        value: &str,
+    ) -> Result<String, std::io::Error> {
+        match set_meta_with(t, filepath, value, &crate::flac::WriteOptions::default()) {
+            Ok((written, _)) => Ok(written),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Like `set_meta`, with the options to write the song with
+    pub fn set_meta_with(
+        t: types::Type,
+        filepath: &String,
+        value: &str,
+        options: &crate::flac::WriteOptions,
+    ) -> Result<(String, crate::flac::WriteReport), std::io::Error> {
+        crate::flac::modify(filepath, options, |flac_file| {
+            set_meta_in_file(t, flac_file, value)
+        })
+    }
+
+    pub(crate) fn meta_from_file(
+        t: types::Type,
+        flac_file: &lofty::flac::FlacFile,
+    ) -> Result<String, std::io::Error> {
+        match flac_file.vorbis_comments() {
+            Some(vb) => {
+                let mut type_strs: Vec<String> = vec![types::access::get_type(t).unwrap()];
+                type_strs.extend(types::access::get_fallback_types(t));
+
+                match type_strs.iter().find_map(|type_str| vb.get(type_str)) {
+                    Some(val) => Ok(val.to_owned()),
+                    None => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "Could not get tag data",
+                    )),
+                }
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "No tags found",
+            )),
+        }
+    }
+
+    /// Validates the value and stores it in the parsed song without saving
+    pub(crate) fn set_meta_in_file(
+        t: types::Type,
+        flac_file: &mut lofty::flac::FlacFile,
+        value: &str,
     ) -> Result<String, std::io::Error> {
+        let written = match types::MetadataType::parse(t, value) {
+            Ok(parsed) => parse_value(parsed),
+            Err(err) => return Err(err),
+        };
+
+        let total = types::parse_total(t, value)?;
+
+        match flac_file.vorbis_comments_mut() {
+            Some(vb) => {
+                insert_value(vb, t, written.clone());
+                if let Some((count_type, total)) = total {
+                    insert_value(vb, count_type, total.to_string());
+                }
+                Ok(written)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "No tags found",
+            )),
+        }
+    }
+
+    /// Stores an already validated value under the key(s) of the Type
+    fn insert_value(vb: &mut lofty::ogg::VorbisComments, t: types::Type, written: String) {
+        let number = written.parse::<u32>().ok();
+
+        match t {
+            types::Type::Album => {
+                vb.set_album(written);
+            }
+            types::Type::AlbumArtist => {
+                vb.insert(types::access::get_type(t).unwrap(), written);
+            }
+            types::Type::Artist => {
+                vb.set_artist(written);
+            }
+            types::Type::Date => {
+                vb.insert(types::access::get_type(t).unwrap(), written);
+            }
+            types::Type::Disc => {
+                if let Some(disc) = number {
+                    vb.set_disk(disc);
+                }
+            }
+            types::Type::Genre => {
+                vb.set_genre(written);
+            }
+            types::Type::Title => {
+                vb.set_title(written);
+            }
+            types::Type::Track => {
+                if let Some(track) = number {
+                    vb.set_track(track);
+                }
+            }
+            types::Type::TrackCount => {
+                if let Some(track_total) = number {
+                    vb.set_track_total(track_total);
+                }
+                vb.insert(types::access::get_type(t).unwrap(), written);
+            }
+            types::Type::DiscCount => {
+                if let Some(disc_total) = number {
+                    vb.set_disk_total(disc_total);
+                }
+                vb.insert(types::access::get_type(t).unwrap(), written);
+            }
+            types::Type::MusicBrainzTrackId
+            | types::Type::MusicBrainzAlbumId
+            | types::Type::MusicBrainzArtistId
+            | types::Type::MusicBrainzAlbumArtistId
+            | types::Type::MusicBrainzReleaseGroupId
+            | types::Type::AcoustId => {
+                vb.insert(types::access::get_type(t).unwrap(), written);
+            }
+        }
+    }
+
+    pub fn parse_value(value: types::MetadataType) -> String {
+        match value {
+            types::MetadataType::String(val) => val,
+            other => other.to_string(),
+        }
+    }
+
+    /// Reads every tag of the song. A file without a Vorbis comment block yields
+    /// an empty SongMetadata.
+    pub fn get_all_meta(filepath: &String) -> Result<types::SongMetadata, std::io::Error> {
         match std::fs::File::open(filepath) {
             Ok(mut content) => {
                 match lofty::flac::FlacFile::read_from(
                     &mut content,
                     lofty::config::ParseOptions::new(),
                 ) {
-                    Ok(mut flac_file) => match flac_file.vorbis_comments_mut() {
-                        Some(vb) => {
-                            let pre_value = value.clone();
-                            match t {
-                                types::Type::Album => {
-                                    vb.set_album(pre_value);
-                                }
-                                types::Type::AlbumArtist => {
-                                    vb.insert(types::access::get_type(t).unwrap(), pre_value);
-                                }
-                                types::Type::Artist => {
-                                    vb.set_artist(pre_value);
-                                }
-                                types::Type::Date => {
-                                    vb.insert(types::access::get_type(t).unwrap(), pre_value);
-                                }
-                                types::Type::Disc => {
-                                    vb.set_disk(pre_value.parse().unwrap());
-                                }
-                                types::Type::Genre => {
-                                    vb.set_genre(pre_value);
-                                }
-                                types::Type::Title => {
-                                    vb.set_title(pre_value);
-                                }
-                                types::Type::Track => {
-                                    vb.set_track(pre_value.parse().unwrap());
-                                }
-                                types::Type::TrackCount => {
-                                    vb.set_track_total(pre_value.parse().unwrap());
-                                }
-                                types::Type::DiscCount => {
-                                    vb.set_disk_total(pre_value.parse().unwrap());
-                                }
-                            };
-
-                            match vb.save_to_path(filepath, lofty::config::WriteOptions::default())
-                            {
-                                Ok(_) => Ok(value.to_owned()),
-                                Err(err) => Err(std::io::Error::new(
-                                    std::io::ErrorKind::InvalidData,
-                                    err.to_string(),
-                                )),
-                            }
-                        }
-                        None => Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            "No tags found",
-                        )),
-                    },
+                    Ok(flac_file) => Ok(song_metadata_from_file(&flac_file)),
                     Err(err) => Err(std::io::Error::new(
                         std::io::ErrorKind::InvalidData,
                         err.to_string(),
@@ -255,94 +471,609 @@ pub mod metadata {
         }
     }
 
-    pub fn parse_value(value: types::MetadataType) -> String {
-        match value {
-            types::MetadataType::String(val) => val,
-            types::MetadataType::Int(val) => val.to_string(),
+    /// Replaces every tag and picture of the song with the ones in SongMetadata.
+    /// Only the keys whose values change are rewritten, so the vendor string and
+    /// the order of untouched comments are kept. Pictures are matched against the
+    /// embedded ones by hash unless their `path` points at an image file to embed
+    /// instead.
+    pub fn set_all_meta(
+        filepath: &String,
+        song_metadata: &types::SongMetadata,
+    ) -> Result<(), std::io::Error> {
+        match set_all_meta_with(
+            filepath,
+            song_metadata,
+            &crate::flac::WriteOptions::default(),
+        ) {
+            Ok(((), _)) => Ok(()),
+            Err(err) => Err(err),
         }
     }
 
-    pub fn set_meta_value(
-        t: types::Type,
+    /// Like `set_all_meta`, with the options to write the song with
+    pub fn set_all_meta_with(
         filepath: &String,
-        value: types::MetadataType,
-    ) -> Result<String, std::io::Error> {
-        let parsed_val = parse_value(value);
+        song_metadata: &types::SongMetadata,
+        options: &crate::flac::WriteOptions,
+    ) -> Result<((), crate::flac::WriteReport), std::io::Error> {
+        crate::flac::modify(filepath, options, |flac_file| {
+            apply_song_metadata(flac_file, song_metadata);
+
+            let pictures =
+                super::coverart::resolve_pictures(flac_file.pictures(), &song_metadata.pictures)?;
+            let _ = flac_file.remove_pictures();
+            for (pic, info) in pictures {
+                let index = flac_file.pictures().len();
+                flac_file.set_picture(index, pic, info);
+            }
+            Ok(())
+        })
+    }
 
-        set_meta(t, filepath, &parsed_val)
+    /// Tags and picture descriptions of an already parsed song
+    pub(crate) fn song_metadata_from_file(
+        flac_file: &lofty::flac::FlacFile,
+    ) -> types::SongMetadata {
+        let mut song_metadata = match flac_file.vorbis_comments() {
+            Some(vb) => song_metadata_from_comments(vb),
+            None => types::SongMetadata::default(),
+        };
+        song_metadata.pictures = flac_file
+            .pictures()
+            .iter()
+            .map(|(pic, info)| super::coverart::picture_info(pic, info))
+            .collect();
+
+        song_metadata
     }
-}
 
-#[cfg(test)]
-mod tests {
-    mod get {
-        use super::super::metadata::get_meta;
-        use crate::test_util::util;
-        use crate::test_util::util::{file_exists, get_full_path};
-        use crate::types;
+    /// Splits the comments into typed fields and `custom`. A field holds the
+    /// first value of its keys that parses. Every other value goes to `custom`
+    /// under its own key, except copies of the field under a fallback key, such
+    /// as the TRACKTOTAL that `set_meta` writes next to TRACKCOUNT.
+    pub(crate) fn song_metadata_from_comments(
+        vb: &lofty::ogg::VorbisComments,
+    ) -> types::SongMetadata {
+        let mut song_metadata = types::SongMetadata::default();
+        let mut known_keys: Vec<String> = Vec::new();
+
+        for t in types::all_metadata_types() {
+            let type_strs = type_keys(t);
+            let values: Vec<(&String, &str)> = type_strs
+                .iter()
+                .flat_map(|type_str| vb.get_all(type_str).map(move |value| (type_str, value)))
+                .collect();
+
+            let typed = values
+                .iter()
+                .position(|(_, value)| song_metadata.set(t, value).is_ok());
+            for (index, (type_str, value)) in values.iter().enumerate() {
+                let copy = *type_str != &type_strs[0]
+                    && types::MetadataType::parse(t, value).ok() == song_metadata.get(t);
+                if Some(index) != typed && !copy {
+                    song_metadata
+                        .custom
+                        .entry(type_str.to_string())
+                        .or_default()
+                        .push(value.to_string());
+                }
+            }
 
-        #[test]
-        fn test_get_title() {
-            let filename = util::get_filename(1);
-            let dir = String::from(util::TESTFILEDIRECTORY);
+            known_keys.extend(type_strs);
+        }
 
-            match file_exists(&dir, &filename) {
-                Ok(_) => {
-                    let filepath = get_full_path(&dir, &filename).unwrap();
+        for (key, value) in vb.items() {
+            let key = key.to_uppercase();
+            if !known_keys.contains(&key) {
+                song_metadata
+                    .custom
+                    .entry(key)
+                    .or_default()
+                    .push(value.to_owned());
+            }
+        }
 
-                    match get_meta(types::Type::Title, &filepath) {
-                        Ok(title) => {
-                            let found = title == "Just roll it";
-                            assert!(found, "Meta information was not found {:?}", title);
-                        }
-                        Err(err) => {
-                            assert!(false, "Error: {:?}", err);
-                        }
+        song_metadata
+    }
+
+    /// Rewrites the comments of every Type or custom key whose values differ
+    /// from the ones already in the song. A changed Type is written together
+    /// with the `custom` values of its keys.
+    fn apply_song_metadata(
+        flac_file: &mut lofty::flac::FlacFile,
+        song_metadata: &types::SongMetadata,
+    ) {
+        let mut vb = flac_file.remove_vorbis_comments().unwrap_or_default();
+        let current = song_metadata_from_comments(&vb);
+        let mut known_keys: Vec<String> = Vec::new();
+        let custom_changed = |key: &String| {
+            current.custom.get(key).cloned().unwrap_or_default()
+                != song_metadata.custom.get(key).cloned().unwrap_or_default()
+        };
+
+        for t in types::all_metadata_types() {
+            let type_strs = type_keys(t);
+            let changed =
+                current.get(t) != song_metadata.get(t) || type_strs.iter().any(custom_changed);
+
+            if changed {
+                for type_str in &type_strs {
+                    let _ = vb.remove(type_str);
+                }
+                if let Some(value) = song_metadata.get(t) {
+                    insert_value(&mut vb, t, parse_value(value));
+                }
+                for type_str in &type_strs {
+                    for value in song_metadata.custom.get(type_str).into_iter().flatten() {
+                        vb.push(type_str.to_owned(), value.to_owned());
                     }
                 }
-                Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+            }
+
+            known_keys.extend(type_strs);
+        }
+
+        let mut keys: Vec<&String> = current
+            .custom
+            .keys()
+            .chain(song_metadata.custom.keys())
+            .filter(|key| !known_keys.contains(key))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            if custom_changed(key) {
+                let _ = vb.remove(key);
+                for value in song_metadata.custom.get(key).into_iter().flatten() {
+                    vb.push(key.to_owned(), value.to_owned());
                 }
-            };
+            }
         }
 
-        #[test]
-        fn test_get_artist() {
-            let filename = util::get_filename(1);
-            let dir = String::from(util::TESTFILEDIRECTORY);
+        flac_file.set_vorbis_comments(vb);
+    }
 
-            match file_exists(&dir, &filename) {
-                Ok(_) => {
-                    let filepath = get_full_path(&dir, &filename).unwrap();
+    /// The key of a Type followed by its fallback keys
+    fn type_keys(t: types::Type) -> Vec<String> {
+        let mut type_strs: Vec<String> = vec![types::access::get_type(t).unwrap()];
+        type_strs.extend(types::access::get_fallback_types(t));
+        type_strs
+    }
 
-                    match get_meta(types::Type::Artist, &filepath) {
-                        Ok(artist) => {
-                            let found = artist == "KD";
-                            assert!(found, "Meta information was not found {:?}", artist);
-                        }
-                        Err(err) => {
-                            assert!(false, "Error: {:?}", err);
-                        }
-                    }
-                }
-                Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
-                }
-            };
+    /// Gets the value of a Type converted to the MetadataType it holds
+    pub fn get_meta_value(
+        t: types::Type,
+        filepath: &String,
+    ) -> Result<types::MetadataType, std::io::Error> {
+        match get_meta(t, filepath) {
+            Ok(value) => types::MetadataType::parse(t, &value),
+            Err(err) => Err(err),
         }
+    }
 
-        #[test]
-        fn test_get_album() {
-            let filename = util::get_filename(1);
-            let dir = String::from(util::TESTFILEDIRECTORY);
+    /// Gets the value of a field with its typed representation, e.g.
+    /// `get::<types::field::Track>(&path)` returns a `u32`
+    pub fn get<F: types::Field>(filepath: &String) -> Result<F::Value, std::io::Error> {
+        match get_meta(F::TYPE, filepath) {
+            Ok(value) => F::parse(&value),
+            Err(err) => Err(err),
+        }
+    }
 
-            match file_exists(&dir, &filename) {
-                Ok(_) => {
-                    let filepath = get_full_path(&dir, &filename).unwrap();
+    /// Sets the value of a field from its typed representation
+    pub fn set<F: types::Field>(
+        filepath: &String,
+        value: &F::Value,
+    ) -> Result<F::Value, std::io::Error> {
+        match set_meta(F::TYPE, filepath, &F::format(value)) {
+            Ok(written) => F::parse(&written),
+            Err(err) => Err(err),
+        }
+    }
 
-                    match get_meta(types::Type::Album, &filepath) {
-                        Ok(album) => {
-                            let found = album == "Sample Tracks 3";
+    /// Gets the release date, falling back to YEAR, ORIGINALDATE and ORIGINALYEAR
+    pub fn get_date(filepath: &String) -> Result<types::PartialDate, std::io::Error> {
+        match get_meta(types::Type::Date, filepath) {
+            Ok(value) => types::PartialDate::parse(&value),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn set_meta_value(
+        t: types::Type,
+        filepath: &String,
+        value: types::MetadataType,
+    ) -> Result<String, std::io::Error> {
+        let parsed_val = parse_value(value);
+
+        set_meta(t, filepath, &parsed_val)
+    }
+
+    /// Removes the Type, including its fallback keys, and returns the removed values
+    pub fn remove_meta(t: types::Type, filepath: &String) -> Result<Vec<String>, std::io::Error> {
+        let mut type_strs: Vec<String> = vec![types::access::get_type(t).unwrap()];
+        type_strs.extend(types::access::get_fallback_types(t));
+
+        remove_comments(filepath, &type_strs)
+    }
+
+    /// Removes every Vorbis comment with one of the keys and returns the removed values
+    pub fn remove_comments(
+        filepath: &String,
+        keys: &[String],
+    ) -> Result<Vec<String>, std::io::Error> {
+        match remove_comments_with(filepath, keys, &crate::flac::WriteOptions::default()) {
+            Ok((removed, _)) => Ok(removed),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Like `remove_comments`, with the options to write the song with
+    pub fn remove_comments_with(
+        filepath: &String,
+        keys: &[String],
+        options: &crate::flac::WriteOptions,
+    ) -> Result<(Vec<String>, crate::flac::WriteReport), std::io::Error> {
+        crate::flac::modify(filepath, options, |flac_file| {
+            Ok(remove_comments_in_file(flac_file, keys))
+        })
+    }
+
+    /// The vendor string of the Vorbis comments, which names the encoder
+    pub fn get_vendor(filepath: &String) -> Result<String, std::io::Error> {
+        match std::fs::File::open(filepath) {
+            Ok(mut content) => {
+                match lofty::flac::FlacFile::read_from(
+                    &mut content,
+                    lofty::config::ParseOptions::new().read_properties(false),
+                ) {
+                    Ok(flac_file) => match flac_file.vorbis_comments() {
+                        Some(vb) => Ok(vb.vendor().to_owned()),
+                        None => Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "No tags found",
+                        )),
+                    },
+                    Err(err) => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        err.to_string(),
+                    )),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replaces the vendor string and returns the previous one
+    pub fn set_vendor(filepath: &String, vendor: &str) -> Result<String, std::io::Error> {
+        match set_vendor_with(filepath, vendor, &crate::flac::WriteOptions::default()) {
+            Ok((previous, _)) => Ok(previous),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Like `set_vendor`, with the options to write the song with
+    pub fn set_vendor_with(
+        filepath: &String,
+        vendor: &str,
+        options: &crate::flac::WriteOptions,
+    ) -> Result<(String, crate::flac::WriteReport), std::io::Error> {
+        // lofty keeps the vendor string of the file when it saves, so the block is
+        // rewritten directly
+        crate::flac::modify_blocks(filepath, options, |blocks| {
+            match blocks
+                .iter_mut()
+                .find(|block| block.block_type == crate::flac::VORBIS_COMMENT)
+            {
+                Some(block) => {
+                    let vorbis_comment = crate::flac::decode_vorbis_comment(&block.data)?;
+                    block.data =
+                        crate::flac::serialize_vorbis_comment(vendor, &vorbis_comment.comments);
+                    Ok(vorbis_comment.vendor)
+                }
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "No tags found",
+                )),
+            }
+        })
+    }
+
+    /// Every value of a Vorbis comment key exactly as stored, without parsing or
+    /// fallback keys
+    pub fn get_comments(filepath: &String, key: &str) -> Result<Vec<String>, std::io::Error> {
+        match std::fs::File::open(filepath) {
+            Ok(mut content) => {
+                match lofty::flac::FlacFile::read_from(
+                    &mut content,
+                    lofty::config::ParseOptions::new(),
+                ) {
+                    Ok(flac_file) => match flac_file.vorbis_comments() {
+                        Some(vb) => Ok(vb.get_all(key).map(String::from).collect()),
+                        None => Ok(Vec::new()),
+                    },
+                    Err(err) => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        err.to_string(),
+                    )),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replaces every value of a Vorbis comment key, creating the comment block
+    /// if the song has none. Values are stored as-is, newlines included.
+    pub fn set_comments(
+        filepath: &String,
+        key: &str,
+        values: &[String],
+    ) -> Result<(), std::io::Error> {
+        if !is_field_name(key) || key.contains('=') {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid field name {key:?}"),
+            ));
+        }
+
+        match crate::flac::modify(
+            filepath,
+            &crate::flac::WriteOptions::default(),
+            |flac_file| {
+                let mut vb = flac_file.remove_vorbis_comments().unwrap_or_default();
+                let _ = vb.remove(key);
+                for value in values {
+                    vb.push(key.to_uppercase(), value.to_owned());
+                }
+                flac_file.set_vorbis_comments(vb);
+                Ok(())
+            },
+        ) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(crate) fn remove_comments_in_file(
+        flac_file: &mut lofty::flac::FlacFile,
+        keys: &[String],
+    ) -> Vec<String> {
+        let mut removed: Vec<String> = Vec::new();
+        if let Some(vb) = flac_file.vorbis_comments_mut() {
+            for key in keys {
+                removed.extend(vb.remove(key));
+            }
+        }
+
+        removed
+    }
+
+    /// How imported Vorbis comments combine with the ones already in the song
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ImportMode {
+        /// Every existing comment is removed first
+        Replace,
+        /// Imported keys replace all existing values of the same key, other
+        /// comments are kept
+        Merge,
+    }
+
+    /// Exports every Vorbis comment in the `metaflac --export-tags-to` format, one
+    /// `KEY=value` per line. Values are written as they are, so a multi-line value
+    /// spans several lines.
+    pub fn export_comments(filepath: &String) -> Result<String, std::io::Error> {
+        match std::fs::File::open(filepath) {
+            Ok(mut content) => {
+                match lofty::flac::FlacFile::read_from(
+                    &mut content,
+                    lofty::config::ParseOptions::new(),
+                ) {
+                    Ok(flac_file) => match flac_file.vorbis_comments() {
+                        Some(vb) => Ok(vb
+                            .items()
+                            .map(|(key, value)| format!("{key}={value}\n"))
+                            .collect()),
+                        None => Ok(String::new()),
+                    },
+                    Err(err) => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        err.to_string(),
+                    )),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn export_comments_to(
+        filepath: &String,
+        output_path: &String,
+    ) -> Result<(), std::io::Error> {
+        match export_comments(filepath) {
+            Ok(text) => std::fs::write(output_path, text),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Imports comments in the `metaflac --import-tags-from` format and returns
+    /// how many were imported. Values are taken as they are. A line that does not
+    /// start with a valid field name followed by `=` continues the value of the
+    /// previous comment, which is how metaflac writes multi-line values.
+    pub fn import_comments(
+        filepath: &String,
+        text: &[u8],
+        mode: ImportMode,
+    ) -> Result<usize, std::io::Error> {
+        match import_comments_with(filepath, text, mode, &crate::flac::WriteOptions::default()) {
+            Ok((count, _)) => Ok(count),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Like `import_comments`, with the options to write the song with
+    pub fn import_comments_with(
+        filepath: &String,
+        text: &[u8],
+        mode: ImportMode,
+        options: &crate::flac::WriteOptions,
+    ) -> Result<(usize, crate::flac::WriteReport), std::io::Error> {
+        let comments = parse_comments(text)?;
+
+        crate::flac::modify(filepath, options, |flac_file| {
+            let mut vb = flac_file.remove_vorbis_comments().unwrap_or_default();
+
+            match mode {
+                ImportMode::Replace => {
+                    let _ = vb.take_items();
+                }
+                ImportMode::Merge => {
+                    for (key, _) in &comments {
+                        let _ = vb.remove(key);
+                    }
+                }
+            }
+            for (key, value) in &comments {
+                vb.push(key.to_owned(), value.to_owned());
+            }
+            flac_file.set_vorbis_comments(vb);
+            Ok(comments.len())
+        })
+    }
+
+    pub fn import_comments_from(
+        filepath: &String,
+        input_path: &String,
+        mode: ImportMode,
+    ) -> Result<usize, std::io::Error> {
+        match std::fs::read(input_path) {
+            Ok(text) => import_comments(filepath, &text, mode),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn parse_comments(text: &[u8]) -> Result<Vec<(String, String)>, std::io::Error> {
+        let text = match std::str::from_utf8(text) {
+            Ok(text) => text,
+            Err(err) => {
+                let line = text[..err.valid_up_to()]
+                    .iter()
+                    .filter(|byte| **byte == b'\n')
+                    .count()
+                    + 1;
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Invalid UTF-8 on line {line}"),
+                ));
+            }
+        };
+
+        let mut comments: Vec<(String, String)> = Vec::new();
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        if text.is_empty() {
+            return Ok(comments);
+        }
+
+        for (index, line) in text.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let field = line.split_once('=').filter(|(key, _)| is_field_name(key));
+
+            match (field, comments.last_mut()) {
+                (Some((key, value)), _) => {
+                    comments.push((key.to_uppercase(), String::from(value)));
+                }
+                (None, Some((_, value))) => {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+                (None, None) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Line {} is not a KEY=value comment", index + 1),
+                    ));
+                }
+            }
+        }
+
+        Ok(comments)
+    }
+
+    /// Vorbis field names are printable ASCII other than '='
+    fn is_field_name(key: &str) -> bool {
+        !key.is_empty() && key.bytes().all(|byte| (0x20..=0x7D).contains(&byte))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+mod tests {
+    mod get {
+        use super::super::metadata::get_meta;
+        use crate::test_util::util;
+        use crate::test_util::util::{file_exists, get_full_path};
+        use crate::types;
+
+        #[test]
+        fn test_get_title() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::Title, &filepath) {
+                        Ok(title) => {
+                            let found = title == "Just roll it";
+                            assert!(found, "Meta information was not found {:?}", title);
+                        }
+                        Err(err) => {
+                            assert!(false, "Error: {:?}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_get_artist() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::Artist, &filepath) {
+                        Ok(artist) => {
+                            let found = artist == "KD";
+                            assert!(found, "Meta information was not found {:?}", artist);
+                        }
+                        Err(err) => {
+                            assert!(false, "Error: {:?}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_get_album() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::Album, &filepath) {
+                        Ok(album) => {
+                            let found = album == "Sample Tracks 3";
                             assert!(found, "Meta information was not found {:?}", album);
                         }
                         Err(err) => {
@@ -401,668 +1132,1409 @@ mod tests {
                     }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+        #[test]
+        fn test_get_year() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::Date, &filepath) {
+                        Ok(date) => {
+                            let found = date == "2025-04-11";
+                            assert!(found, "Meta information was not found {:?}", date);
+                        }
+                        Err(err) => {
+                            assert!(false, "Error: {:?}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_get_track() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::Track, &filepath) {
+                        Ok(track) => {
+                            let found = track == "1";
+                            assert!(found, "Meta information was not found {:?}", track);
+                        }
+                        Err(err) => {
+                            assert!(false, "Error: {:?}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_get_disc() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::Disc, &filepath) {
+                        Ok(disc) => {
+                            let found = disc == "1";
+                            assert!(found, "Meta information was not found {:?}", disc);
+                        }
+                        Err(err) => {
+                            assert!(false, "Error: {:?}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_get_track_total() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::TrackCount, &filepath) {
+                        Ok(track_total) => {
+                            let found = track_total == "3";
+                            assert!(found, "Meta information was not found {:?}", track_total);
+                        }
+                        Err(err) => {
+                            assert!(false, "Error: {:?}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_get_disc_total() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get_meta(types::Type::DiscCount, &filepath) {
+                        Ok(disc_total) => {
+                            let found = disc_total == "1";
+                            assert!(found, "Meta information was not found {:?}", disc_total);
+                        }
+                        Err(err) => {
+                            assert!(false, "Error: {:?}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+    }
+
+    mod set {
+        use super::super::metadata::{get_meta, set_meta};
+        use crate::test_util::util;
+        use crate::test_util::util::{file_exists, get_full_path};
+        use crate::types;
+
+        #[test]
+        fn test_set_title() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = util::generate_filename().unwrap();
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::Title, &filepath) {
+                            Ok(title) => {
+                                let found = title == "Just roll it";
+                                assert!(found, "Meta information was not found {:?}", title);
+                                let new_title = String::from("The wind burned her");
+
+                                match set_meta(types::Type::Title, &new_filepath, &new_title) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_title, m,
+                                            "New title does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_set_artist() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = util::generate_filename().unwrap();
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::Artist, &filepath) {
+                            Ok(artist) => {
+                                let found = artist == "KD";
+                                assert!(found, "Meta information was not found {:?}", artist);
+                                let new_artist = String::from("Pilot");
+
+                                match set_meta(types::Type::Artist, &new_filepath, &new_artist) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_artist, m,
+                                            "New artist does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_set_album() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = String::from("track08.flac");
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::Album, &filepath) {
+                            Ok(title) => {
+                                let found = title == "Sample Tracks 3";
+                                assert!(found, "Meta information was not found {:?}", title);
+                                let new_album = String::from("Sample Tracks 3 Remastered");
+
+                                match set_meta(types::Type::Album, &new_filepath, &new_album) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_album, m,
+                                            "New album does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+        #[test]
+        fn test_set_album_artist() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = String::from("track08.flac");
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::AlbumArtist, &filepath) {
+                            Ok(album_artist) => {
+                                let found = album_artist == "KD";
+                                assert!(found, "Meta information was not found {:?}", album_artist);
+                                let new_album_artist = String::from("Bob");
+
+                                match set_meta(
+                                    types::Type::AlbumArtist,
+                                    &new_filepath,
+                                    &new_album_artist,
+                                ) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_album_artist, m,
+                                            "New album artist does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+        #[test]
+        fn test_set_date() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = String::from("track08.flac");
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::Date, &filepath) {
+                            Ok(date) => {
+                                let found = date == "2025-04-11";
+                                assert!(found, "Meta information was not found {:?}", date);
+                                let new_date = String::from("2025-02-01");
+
+                                match set_meta(types::Type::Date, &new_filepath, &new_date) {
+                                    Ok(m) => {
+                                        assert_eq!(new_date, m, "New date does not match {:?}", m);
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+        #[test]
+        fn test_set_track() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = util::generate_filename().unwrap();
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::Track, &filepath) {
+                            Ok(track) => {
+                                let found = track == "1";
+                                assert!(found, "Meta information was not found {:?}", track);
+                                let new_track = String::from("5");
+
+                                match set_meta(types::Type::Track, &new_filepath, &new_track) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_track, m,
+                                            "New track does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+        #[test]
+        fn test_set_disc() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = String::from("track08.flac");
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::Disc, &filepath) {
+                            Ok(disc) => {
+                                let found = disc == "1";
+                                assert!(found, "Meta information was not found {:?}", disc);
+                                let new_disc = String::from("2");
+
+                                match set_meta(types::Type::Disc, &new_filepath, &new_disc) {
+                                    Ok(m) => {
+                                        assert_eq!(new_disc, m, "New disc does not match {:?}", m);
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_set_track_total() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = util::generate_filename().unwrap();
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::TrackCount, &filepath) {
+                            Ok(track_total) => {
+                                let found = track_total == "3";
+                                assert!(found, "Meta information was not found {:?}", track_total);
+                                let new_track_total = String::from("5");
+
+                                match set_meta(
+                                    types::Type::TrackCount,
+                                    &new_filepath,
+                                    &new_track_total,
+                                ) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_track_total, m,
+                                            "New track does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_set_disc_total() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = String::from("track08.flac");
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::DiscCount, &filepath) {
+                            Ok(disc_total) => {
+                                let found = disc_total == "1";
+                                assert!(found, "Meta information was not found {:?}", disc_total);
+                                let new_disc_total = String::from("2");
+
+                                match set_meta(
+                                    types::Type::DiscCount,
+                                    &new_filepath,
+                                    &new_disc_total,
+                                ) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_disc_total, m,
+                                            "New disc_total does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_set_genre() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let test_filename = util::generate_filename().unwrap();
+            let new_filepath = test_dir + "/" + &test_filename;
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match util::copy_file(&filepath, &new_filepath) {
+                        Ok(_o) => match get_meta(types::Type::Genre, &filepath) {
+                            Ok(genre) => {
+                                let found = genre == "Metal";
+                                assert!(found, "Meta information was not found {:?}", genre);
+                                let new_genre = String::from("Blues");
+
+                                match set_meta(types::Type::Genre, &new_filepath, &new_genre) {
+                                    Ok(m) => {
+                                        assert_eq!(
+                                            new_genre, m,
+                                            "New genre does not match {:?}",
+                                            m
+                                        );
+                                    }
+                                    Err(err) => {
+                                        assert!(false, "Error: {:?}", err);
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                assert!(false, "Error: {:?}", err);
+                            }
+                        },
+                        Err(err) => {
+                            assert!(
+                                false,
+                                "Error: {:?} source {:?} destination {:?}",
+                                err, filepath, new_filepath
+                            );
+                        }
+                    };
+                }
+                Err(err) => {
+                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+    }
+
+    mod typed {
+        use super::super::coverart::contains_coverart;
+        use super::super::metadata::{get, get_meta, get_meta_value, set, set_meta};
+        use crate::test_util::util;
+        use crate::test_util::util::{file_exists, get_full_path};
+        use crate::types;
+        use crate::types::field;
+
+        #[test]
+        fn test_get_typed() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+
+                    match get::<field::Track>(&filepath) {
+                        Ok(track) => assert_eq!(1, track, "Track does not match {:?}", track),
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
+                    match get::<field::Title>(&filepath) {
+                        Ok(title) => {
+                            assert_eq!("Just roll it", title, "Title does not match {:?}", title)
+                        }
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
+                    match get_meta_value(types::Type::TrackCount, &filepath) {
+                        Ok(value) => assert_eq!(types::MetadataType::Int(3), value),
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
+                }
+                Err(err) => {
+                    panic!("Error: File does not exist {:?}", err.to_string());
+                }
+            };
+        }
+
+        #[test]
+        fn test_set_typed() {
+            let filename = util::get_filename(1);
+            let dir = String::from(util::TESTFILEDIRECTORY);
+
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
+
+            match file_exists(&dir, &filename) {
+                Ok(_) => {
+                    let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
+
+                    match set::<field::TrackCount>(&new_filepath, &12) {
+                        Ok(track_total) => {
+                            assert_eq!(12, track_total);
+                            assert_eq!(12, get::<field::TrackCount>(&new_filepath).unwrap());
+                        }
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
+
+                    let date = types::PartialDate::new(2001, Some(9), Some(30)).unwrap();
+                    match set::<field::Date>(&new_filepath, &date) {
+                        Ok(written) => assert_eq!(date, written),
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
+
+                    match contains_coverart(&new_filepath) {
+                        Ok((exists, _)) => assert!(exists, "Cover art should have been kept"),
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
+                }
+                Err(err) => {
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
+
         #[test]
-        fn test_get_year() {
+        fn test_set_track_with_total() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
+
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
 
-                    match get_meta(types::Type::Date, &filepath) {
-                        Ok(date) => {
-                            let found = date == "2025-04-11";
-                            assert!(found, "Meta information was not found {:?}", date);
+                    match set_meta(types::Type::Track, &new_filepath, "3/12") {
+                        Ok(written) => {
+                            assert_eq!("3", written);
+                            assert_eq!(3, get::<field::Track>(&new_filepath).unwrap());
+                            assert_eq!(12, get::<field::TrackCount>(&new_filepath).unwrap());
                         }
-                        Err(err) => {
-                            assert!(false, "Error: {:?}", err);
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
+
+                    match set_meta(types::Type::Disc, &new_filepath, "1/") {
+                        Ok(written) => {
+                            assert_eq!("1", written);
+                            assert_eq!(12, get::<field::TrackCount>(&new_filepath).unwrap());
                         }
+                        Err(err) => panic!("Error: {:?}", err),
                     }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
 
         #[test]
-        fn test_get_track() {
+        fn test_set_invalid_number() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
+
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
 
-                    match get_meta(types::Type::Track, &filepath) {
-                        Ok(track) => {
-                            let found = track == "1";
-                            assert!(found, "Meta information was not found {:?}", track);
-                        }
+                    match set_meta(types::Type::Track, &new_filepath, &String::from("one")) {
+                        Ok(m) => panic!("Invalid track should not be written {:?}", m),
                         Err(err) => {
-                            assert!(false, "Error: {:?}", err);
+                            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+                            assert_eq!("1", get_meta(types::Type::Track, &new_filepath).unwrap());
                         }
                     }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
+    }
+
+    mod all {
+        use super::super::metadata::{get_all_meta, set_all_meta};
+        use crate::test_util::util;
+        use crate::test_util::util::{file_exists, get_full_path};
 
         #[test]
-        fn test_get_disc() {
-            let filename = util::get_filename(1);
+        fn test_set_all_meta() {
+            let filename = util::get_filename(2);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
+
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
-
-                    match get_meta(types::Type::Disc, &filepath) {
-                        Ok(disc) => {
-                            let found = disc == "1";
-                            assert!(found, "Meta information was not found {:?}", disc);
+                    util::copy_file(&filepath, &new_filepath).unwrap();
+
+                    let mut song_metadata = get_all_meta(&new_filepath).unwrap();
+                    song_metadata.title = Some(String::from("There we went"));
+                    song_metadata.genre = None;
+                    song_metadata.custom.remove("UMID");
+
+                    match set_all_meta(&new_filepath, &song_metadata) {
+                        Ok(_) => {
+                            let written = get_all_meta(&new_filepath).unwrap();
+                            assert_eq!(song_metadata, written, "Metadata does not match");
+                            assert_eq!(1, written.pictures.len(), "Picture should be kept");
                         }
                         Err(err) => {
-                            assert!(false, "Error: {:?}", err);
+                            panic!("Error: {:?}", err);
                         }
                     }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
 
         #[test]
-        fn test_get_track_total() {
-            let filename = util::get_filename(1);
+        fn test_get_all_meta() {
+            let filename = util::get_filename(2);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
 
-                    match get_meta(types::Type::TrackCount, &filepath) {
-                        Ok(track_total) => {
-                            let found = track_total == "3";
-                            assert!(found, "Meta information was not found {:?}", track_total);
+                    match get_all_meta(&filepath) {
+                        Ok(song_metadata) => {
+                            assert_eq!(Some(String::from("Here we go!")), song_metadata.title);
+                            assert_eq!(Some(String::from("KD")), song_metadata.album_artist);
+                            assert_eq!(Some(2), song_metadata.track);
+                            assert_eq!(Some(3), song_metadata.track_count);
+                            assert_eq!(
+                                Some(String::from("2025-04-11")),
+                                song_metadata.date.map(|date| date.to_string())
+                            );
+                            assert_eq!(None, song_metadata.musicbrainz_track_id);
+                            assert_eq!(
+                                Some(&vec![String::from("Logic Pro")]),
+                                song_metadata.custom.get("ENCODED_BY")
+                            );
+                            assert!(
+                                !song_metadata.custom.contains_key("TITLE"),
+                                "Known fields should not be custom {:?}",
+                                song_metadata.custom
+                            );
                         }
                         Err(err) => {
-                            assert!(false, "Error: {:?}", err);
+                            panic!("Error: {:?}", err);
                         }
                     }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
+    }
+
+    mod remove {
+        use super::super::metadata::{get_meta, remove_comments, remove_meta};
+        use crate::test_util::util;
+        use crate::test_util::util::{file_exists, get_full_path};
+        use crate::types;
 
         #[test]
-        fn test_get_disc_total() {
+        fn test_remove_meta() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
+
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
 
-                    match get_meta(types::Type::DiscCount, &filepath) {
-                        Ok(disc_total) => {
-                            let found = disc_total == "1";
-                            assert!(found, "Meta information was not found {:?}", disc_total);
+                    match remove_meta(types::Type::Genre, &new_filepath) {
+                        Ok(removed) => {
+                            assert_eq!(vec![String::from("Metal")], removed);
+                            assert!(get_meta(types::Type::Genre, &new_filepath).is_err());
+                            assert_eq!("KD", get_meta(types::Type::Artist, &new_filepath).unwrap());
                         }
                         Err(err) => {
-                            assert!(false, "Error: {:?}", err);
+                            panic!("Error: {:?}", err);
                         }
                     }
+
+                    let keys = vec![String::from("UMID"), String::from("NOT_THERE")];
+                    match remove_comments(&new_filepath, &keys) {
+                        Ok(removed) => assert_eq!(1, removed.len()),
+                        Err(err) => panic!("Error: {:?}", err),
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
     }
 
-    mod set {
-        use super::super::metadata::{get_meta, set_meta};
+    mod comments {
+        use super::super::metadata::{
+            ImportMode, export_comments, export_comments_to, get_all_meta, get_comments,
+            import_comments, import_comments_from, set_comments,
+        };
         use crate::test_util::util;
         use crate::test_util::util::{file_exists, get_full_path};
-        use crate::types;
 
         #[test]
-        fn test_set_title() {
+        fn test_export_comments() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
-            let temp_file = tempfile::tempdir().expect("Could not create test directory");
-            let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = util::generate_filename().unwrap();
-            let new_filepath = test_dir + "/" + &test_filename;
-
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
 
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::Title, &filepath) {
-                            Ok(title) => {
-                                let found = title == "Just roll it";
-                                assert!(found, "Meta information was not found {:?}", title);
-                                let new_title = String::from("The wind burned her");
-
-                                match set_meta(types::Type::Title, &new_filepath, &new_title) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_title, m,
-                                            "New title does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
+                    match export_comments(&filepath) {
+                        Ok(text) => {
+                            let lines: Vec<&str> = text.lines().collect();
+                            assert_eq!(14, lines.len(), "Unexpected export {:?}", text);
+                            assert_eq!("ENCODED_BY=Logic Pro", lines[0]);
+                            assert!(lines.contains(&"TITLE=Just roll it"));
+                            assert!(text.ends_with('\n'));
+                        }
                         Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
+                            panic!("Error: {:?}", err);
                         }
-                    };
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
 
         #[test]
-        fn test_set_artist() {
+        fn test_import_comments() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             let temp_file = tempfile::tempdir().expect("Could not create test directory");
             let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = util::generate_filename().unwrap();
-            let new_filepath = test_dir + "/" + &test_filename;
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
+
+                    let text =
+                        "TITLE=Rolled\nCOMMENT=first line\nsecond line\n\nARTIST=A\nARTIST=B\n";
+                    match import_comments(&new_filepath, text.as_bytes(), ImportMode::Merge) {
+                        Ok(count) => {
+                            assert_eq!(4, count);
+                            let song_metadata = get_all_meta(&new_filepath).unwrap();
+                            assert_eq!(Some(String::from("Rolled")), song_metadata.title);
+                            assert_eq!(Some(String::from("A")), song_metadata.artist);
+                            assert_eq!(Some(String::from("Metal")), song_metadata.genre);
+                            assert_eq!(
+                                Some(&vec![String::from("first line\nsecond line\n")]),
+                                song_metadata.custom.get("COMMENT")
+                            );
+                            assert_eq!(1, song_metadata.pictures.len());
+                        }
+                        Err(err) => {
+                            panic!("Error: {:?}", err);
+                        }
+                    }
 
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::Artist, &filepath) {
-                            Ok(artist) => {
-                                let found = artist == "KD";
-                                assert!(found, "Meta information was not found {:?}", artist);
-                                let new_artist = String::from("Pilot");
+                    let exported = get_full_path(&test_dir, "tags.txt").unwrap();
+                    export_comments_to(&new_filepath, &exported).unwrap();
+                    let text = std::fs::read_to_string(&exported).unwrap();
+                    assert!(text.contains("COMMENT=first line\nsecond line\n\nARTIST=A\n"));
 
-                                match set_meta(types::Type::Artist, &new_filepath, &new_artist) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_artist, m,
-                                            "New artist does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
+                    std::fs::write(&exported, "GENRE=Blues\n").unwrap();
+                    match import_comments_from(&new_filepath, &exported, ImportMode::Replace) {
+                        Ok(count) => {
+                            assert_eq!(1, count);
+                            assert_eq!("GENRE=Blues\n", export_comments(&new_filepath).unwrap());
+                        }
                         Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
+                            panic!("Error: {:?}", err);
                         }
-                    };
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
 
         #[test]
-        fn test_set_album() {
+        fn test_export_import_round_trip() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             let temp_file = tempfile::tempdir().expect("Could not create test directory");
             let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = String::from("track08.flac");
-            let new_filepath = test_dir + "/" + &test_filename;
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
-
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::Album, &filepath) {
-                            Ok(title) => {
-                                let found = title == "Sample Tracks 3";
-                                assert!(found, "Meta information was not found {:?}", title);
-                                let new_album = String::from("Sample Tracks 3 Remastered");
-
-                                match set_meta(types::Type::Album, &new_filepath, &new_album) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_album, m,
-                                            "New album does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
-                        Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
-                        }
-                    };
+                    util::copy_file(&filepath, &new_filepath).unwrap();
+
+                    let lyrics = String::from("Just roll it\nsung twice");
+                    set_comments(&new_filepath, "LYRICS", std::slice::from_ref(&lyrics)).unwrap();
+                    set_comments(&new_filepath, "ARTIST", &[String::from("AC\\DC")]).unwrap();
+
+                    // Values are written raw, the way metaflac does
+                    let exported = export_comments(&new_filepath).unwrap();
+                    assert!(exported.contains("\nLYRICS=Just roll it\nsung twice\n"));
+                    assert!(exported.contains("\nARTIST=AC\\DC\n"));
+
+                    import_comments(&new_filepath, exported.as_bytes(), ImportMode::Replace)
+                        .unwrap();
+                    assert_eq!(exported, export_comments(&new_filepath).unwrap());
+                    assert_eq!(vec![lyrics], get_comments(&new_filepath, "LYRICS").unwrap());
+
+                    // Backslashes in metaflac files are kept
+                    import_comments(&new_filepath, b"COMMENT=C:\\new\n", ImportMode::Merge)
+                        .unwrap();
+                    assert_eq!(
+                        vec![String::from("C:\\new")],
+                        get_comments(&new_filepath, "COMMENT").unwrap()
+                    );
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
+
         #[test]
-        fn test_set_album_artist() {
+        fn test_import_invalid_comments() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
+            let filepath = get_full_path(&dir, &filename).unwrap();
 
-            let temp_file = tempfile::tempdir().expect("Could not create test directory");
-            let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = String::from("track08.flac");
-            let new_filepath = test_dir + "/" + &test_filename;
+            let invalid_utf8 = b"TITLE=ok\nARTIST=\xff\xfe\n";
+            match import_comments(&filepath, invalid_utf8, ImportMode::Merge) {
+                Ok(count) => panic!("Invalid UTF-8 should not be imported {count}"),
+                Err(err) => {
+                    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+                    assert!(err.to_string().contains("line 2"), "{err}");
+                }
+            }
 
-            match file_exists(&dir, &filename) {
-                Ok(_) => {
-                    let filepath = get_full_path(&dir, &filename).unwrap();
+            match import_comments(&filepath, b"no comment here\n", ImportMode::Merge) {
+                Ok(count) => panic!("Text without a comment should not be imported {count}"),
+                Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::InvalidData),
+            }
+        }
+    }
 
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::AlbumArtist, &filepath) {
-                            Ok(album_artist) => {
-                                let found = album_artist == "KD";
-                                assert!(found, "Meta information was not found {:?}", album_artist);
-                                let new_album_artist = String::from("Bob");
+    mod vendor {
+        use super::super::metadata::{get_meta, get_vendor, set_vendor};
+        use crate::test_util::util;
+        use crate::types;
 
-                                match set_meta(
-                                    types::Type::AlbumArtist,
-                                    &new_filepath,
-                                    &new_album_artist,
-                                ) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_album_artist, m,
-                                            "New album artist does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
-                        Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
-                        }
-                    };
+        #[test]
+        fn test_set_vendor() {
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let filepath =
+                util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+            let new_filepath = util::get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+            util::copy_file(&filepath, &new_filepath).unwrap();
+
+            assert_eq!("Lavf61.7.100", get_vendor(&new_filepath).unwrap());
+            match set_vendor(&new_filepath, "reference libFLAC 1.4.3 20230623") {
+                Ok(previous) => {
+                    assert_eq!("Lavf61.7.100", previous);
+                    assert_eq!(
+                        "reference libFLAC 1.4.3 20230623",
+                        get_vendor(&new_filepath).unwrap()
+                    );
+                    assert_eq!(
+                        "Just roll it",
+                        get_meta(types::Type::Title, &new_filepath).unwrap()
+                    );
+
+                    let encoder = crate::properties::get_encoder_info(&new_filepath)
+                        .unwrap()
+                        .unwrap();
+                    assert_eq!("libFLAC", encoder.name);
+                    assert_eq!(Some(String::from("1.4.3")), encoder.version);
+                    assert_eq!(Some(String::from("20230623")), encoder.date);
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: {:?}", err);
                 }
-            };
+            }
         }
+    }
+
+    mod dates {
+        use super::super::metadata::{get_date, get_meta, set_meta, set_meta_value};
+        use crate::test_util::util;
+        use crate::test_util::util::{file_exists, get_full_path};
+        use crate::types;
+
         #[test]
-        fn test_set_date() {
+        fn test_get_date() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
-            let temp_file = tempfile::tempdir().expect("Could not create test directory");
-            let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = String::from("track08.flac");
-            let new_filepath = test_dir + "/" + &test_filename;
-
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
 
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::Date, &filepath) {
-                            Ok(date) => {
-                                let found = date == "2025-04-11";
-                                assert!(found, "Meta information was not found {:?}", date);
-                                let new_date = String::from("2025-02-01");
-
-                                match set_meta(types::Type::Date, &new_filepath, &new_date) {
-                                    Ok(m) => {
-                                        assert_eq!(new_date, m, "New date does not match {:?}", m);
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
+                    match get_date(&filepath) {
+                        Ok(date) => {
+                            assert_eq!(2025, date.year(), "Year does not match {:?}", date);
+                            assert_eq!(Some(4), date.month(), "Month does not match {:?}", date);
+                            assert_eq!(Some(11), date.day(), "Day does not match {:?}", date);
+                        }
                         Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
+                            panic!("Error: {:?}", err);
                         }
-                    };
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
+
         #[test]
-        fn test_set_track() {
+        fn test_set_date_normalized() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             let temp_file = tempfile::tempdir().expect("Could not create test directory");
             let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = util::generate_filename().unwrap();
-            let new_filepath = test_dir + "/" + &test_filename;
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
 
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::Track, &filepath) {
-                            Ok(track) => {
-                                let found = track == "1";
-                                assert!(found, "Meta information was not found {:?}", track);
-                                let new_track = String::from("5");
+                    let new_date = String::from("2019-05-03T00:00:00");
 
-                                match set_meta(types::Type::Track, &new_filepath, &new_track) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_track, m,
-                                            "New track does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
-                        Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
+                    match set_meta(types::Type::Date, &new_filepath, &new_date) {
+                        Ok(m) => {
+                            assert_eq!("2019-05-03", m, "Date was not normalized {:?}", m);
+                            assert_eq!(
+                                "2019-05-03",
+                                get_meta(types::Type::Date, &new_filepath).unwrap()
                             );
                         }
-                    };
+                        Err(err) => {
+                            panic!("Error: {:?}", err);
+                        }
+                    }
+
+                    let year = types::PartialDate::new(2019, None, None).unwrap();
+                    match set_meta_value(
+                        types::Type::Date,
+                        &new_filepath,
+                        types::MetadataType::from_date(year),
+                    ) {
+                        Ok(m) => {
+                            assert_eq!("2019", m, "Date does not match {:?}", m);
+                        }
+                        Err(err) => {
+                            panic!("Error: {:?}", err);
+                        }
+                    }
+
+                    match set_meta(types::Type::Date, &new_filepath, &String::from("May 2019")) {
+                        Ok(m) => {
+                            panic!("Malformed date should not be written {:?}", m);
+                        }
+                        Err(err) => {
+                            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+                        }
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
+
         #[test]
-        fn test_set_disc() {
+        fn test_get_date_fallback() {
+            use lofty::file::AudioFile;
+            use lofty::tag::TagExt;
+
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             let temp_file = tempfile::tempdir().expect("Could not create test directory");
             let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = String::from("track08.flac");
-            let new_filepath = test_dir + "/" + &test_filename;
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
-
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::Disc, &filepath) {
-                            Ok(disc) => {
-                                let found = disc == "1";
-                                assert!(found, "Meta information was not found {:?}", disc);
-                                let new_disc = String::from("2");
-
-                                match set_meta(types::Type::Disc, &new_filepath, &new_disc) {
-                                    Ok(m) => {
-                                        assert_eq!(new_disc, m, "New disc does not match {:?}", m);
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
+                    util::copy_file(&filepath, &new_filepath).unwrap();
+
+                    let mut file = std::fs::File::open(&new_filepath).unwrap();
+                    let mut flac_file = lofty::flac::FlacFile::read_from(
+                        &mut file,
+                        lofty::config::ParseOptions::new(),
+                    )
+                    .unwrap();
+                    let vb = flac_file.vorbis_comments_mut().unwrap();
+                    let _ = vb.remove("DATE");
+                    vb.insert(String::from("ORIGINALYEAR"), String::from("1999"));
+                    vb.save_to_path(&new_filepath, lofty::config::WriteOptions::default())
+                        .unwrap();
+
+                    match get_date(&new_filepath) {
+                        Ok(date) => {
+                            assert_eq!("1999", date.to_string(), "Date does not match {:?}", date);
+                        }
                         Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
+                            panic!("Error: {:?}", err);
                         }
-                    };
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
+    }
+
+    mod identifiers {
+        use super::super::metadata::{get_meta, set_meta, set_meta_value};
+        use crate::test_util::util;
+        use crate::test_util::util::{file_exists, get_full_path};
+        use crate::types;
 
         #[test]
-        fn test_set_track_total() {
+        fn test_set_musicbrainz_track_id() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             let temp_file = tempfile::tempdir().expect("Could not create test directory");
             let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = util::generate_filename().unwrap();
-            let new_filepath = test_dir + "/" + &test_filename;
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
-
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::TrackCount, &filepath) {
-                            Ok(track_total) => {
-                                let found = track_total == "3";
-                                assert!(found, "Meta information was not found {:?}", track_total);
-                                let new_track_total = String::from("5");
-
-                                match set_meta(
-                                    types::Type::TrackCount,
-                                    &new_filepath,
-                                    &new_track_total,
-                                ) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_track_total, m,
-                                            "New track does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
+                    util::copy_file(&filepath, &new_filepath).unwrap();
+
+                    let track_id = String::from("B8A4C0F4-6A1B-4E3A-9A8B-1C2D3E4F5A6B");
+
+                    match set_meta(types::Type::MusicBrainzTrackId, &new_filepath, &track_id) {
+                        Ok(_) => match get_meta(types::Type::MusicBrainzTrackId, &new_filepath) {
+                            Ok(m) => {
+                                assert_eq!(
+                                    "b8a4c0f4-6a1b-4e3a-9a8b-1c2d3e4f5a6b", m,
+                                    "Track id does not match {:?}",
+                                    m
+                                );
                             }
                             Err(err) => {
-                                assert!(false, "Error: {:?}", err);
+                                panic!("Error: {:?}", err);
                             }
                         },
                         Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
+                            panic!("Error: {:?}", err);
                         }
-                    };
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
 
         #[test]
-        fn test_set_disc_total() {
+        fn test_set_acoustid_value() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             let temp_file = tempfile::tempdir().expect("Could not create test directory");
             let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = String::from("track08.flac");
-            let new_filepath = test_dir + "/" + &test_filename;
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
-
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::DiscCount, &filepath) {
-                            Ok(disc_total) => {
-                                let found = disc_total == "1";
-                                assert!(found, "Meta information was not found {:?}", disc_total);
-                                let new_disc_total = String::from("2");
-
-                                match set_meta(
-                                    types::Type::DiscCount,
-                                    &new_filepath,
-                                    &new_disc_total,
-                                ) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_disc_total, m,
-                                            "New disc_total does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
+                    util::copy_file(&filepath, &new_filepath).unwrap();
+
+                    let acoustid =
+                        types::identifier::parse("0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0").unwrap();
+
+                    match set_meta_value(
+                        types::Type::AcoustId,
+                        &new_filepath,
+                        types::MetadataType::from_uuid(acoustid),
+                    ) {
+                        Ok(_) => match get_meta(types::Type::AcoustId, &new_filepath) {
+                            Ok(m) => {
+                                assert_eq!(
+                                    "0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0", m,
+                                    "AcoustID does not match {:?}",
+                                    m
+                                );
                             }
                             Err(err) => {
-                                assert!(false, "Error: {:?}", err);
+                                panic!("Error: {:?}", err);
                             }
                         },
                         Err(err) => {
-                            assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
-                            );
+                            panic!("Error: {:?}", err);
                         }
-                    };
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
 
         #[test]
-        fn test_set_genre() {
+        fn test_set_malformed_identifier() {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
             let temp_file = tempfile::tempdir().expect("Could not create test directory");
             let test_dir = String::from(temp_file.path().to_str().unwrap());
-            let test_filename = util::generate_filename().unwrap();
-            let new_filepath = test_dir + "/" + &test_filename;
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
 
-                    match util::copy_file(&filepath, &new_filepath) {
-                        Ok(_o) => match get_meta(types::Type::Genre, &filepath) {
-                            Ok(genre) => {
-                                let found = genre == "Metal";
-                                assert!(found, "Meta information was not found {:?}", genre);
-                                let new_genre = String::from("Blues");
+                    let malformed = String::from("b8a4c0f46a1b4e3a9a8b1c2d3e4f5a6b-nope");
 
-                                match set_meta(types::Type::Genre, &new_filepath, &new_genre) {
-                                    Ok(m) => {
-                                        assert_eq!(
-                                            new_genre, m,
-                                            "New genre does not match {:?}",
-                                            m
-                                        );
-                                    }
-                                    Err(err) => {
-                                        assert!(false, "Error: {:?}", err);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                assert!(false, "Error: {:?}", err);
-                            }
-                        },
+                    match set_meta(types::Type::MusicBrainzAlbumId, &new_filepath, &malformed) {
+                        Ok(m) => {
+                            panic!("Malformed identifier should not be written {:?}", m);
+                        }
                         Err(err) => {
+                            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
                             assert!(
-                                false,
-                                "Error: {:?} source {:?} destination {:?}",
-                                err, filepath, new_filepath
+                                get_meta(types::Type::MusicBrainzAlbumId, &new_filepath).is_err(),
+                                "Nothing should have been written"
                             );
                         }
-                    };
+                    }
                 }
                 Err(err) => {
-                    assert!(false, "Error: File does not exist {:?}", err.to_string());
+                    panic!("Error: File does not exist {:?}", err.to_string());
                 }
             };
         }
@@ -1117,14 +2589,20 @@ mod tests {
             let filename = util::get_filename(1);
             let dir = String::from(util::TESTFILEDIRECTORY);
 
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let new_filepath =
+                get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
+
             let new_coverart = String::from("Sample Tracks 3 - Other one.png");
             let new_cover_art_path = get_full_path(&dir, &new_coverart).unwrap();
 
             match file_exists(&dir, &filename) {
                 Ok(_) => {
                     let filepath = get_full_path(&dir, &filename).unwrap();
+                    util::copy_file(&filepath, &new_filepath).unwrap();
 
-                    match coverart::set_coverart(&filepath, &new_cover_art_path) {
+                    match coverart::set_coverart(&new_filepath, &new_cover_art_path) {
                         Ok(bytes) => {
                             assert_eq!(false, bytes.is_empty(), "This should not be empty");
                         }
@@ -1182,6 +2660,10 @@ mod tests {
                         Ok(_o) => match coverart::remove_coverart(&new_filepath) {
                             Ok(bytes) => {
                                 assert_eq!(false, bytes.is_empty(), "This should not be empty");
+
+                                let (exists, _) =
+                                    coverart::contains_coverart(&new_filepath).unwrap();
+                                assert!(!exists, "Cover art should have been removed");
                             }
                             Err(err) => {
                                 assert!(false, "Error: {:?}", err);
@@ -1197,5 +2679,67 @@ mod tests {
                 }
             };
         }
+
+        #[test]
+        fn test_remove_picture_saved() {
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+            let filepath = get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+            let new_filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+            // The picture block is gone from the file itself
+            coverart::remove_coverart(&new_filepath).unwrap();
+            assert!(
+                crate::flac::blocks(&new_filepath)
+                    .unwrap()
+                    .iter()
+                    .all(|info| info.block_type != crate::flac::BlockType::Picture)
+            );
+            assert!(crate::strip::verify_audio(&filepath, &new_filepath).unwrap());
+        }
+
+        #[test]
+        fn test_copy_picture() {
+            let dir = String::from(util::TESTFILEDIRECTORY);
+            let temp_file = tempfile::tempdir().expect("Could not create test directory");
+            let test_dir = String::from(temp_file.path().to_str().unwrap());
+
+            let source_filepath = get_full_path(&test_dir, &util::get_filename(1)).unwrap();
+            let new_filepath = get_full_path(&test_dir, &util::get_filename(2)).unwrap();
+            util::copy_file(
+                &get_full_path(&dir, &util::get_filename(1)).unwrap(),
+                &source_filepath,
+            )
+            .unwrap();
+            util::copy_file(
+                &get_full_path(&dir, &util::get_filename(2)).unwrap(),
+                &new_filepath,
+            )
+            .unwrap();
+
+            let other_coverart = get_full_path(&dir, "Sample Tracks 3 - Other one.png").unwrap();
+            coverart::set_coverart(&source_filepath, &other_coverart).unwrap();
+            coverart::remove_coverart(&new_filepath).unwrap();
+
+            match coverart::copy_coverart(&source_filepath, &new_filepath) {
+                Ok(bytes) => {
+                    assert_eq!(util::get_data_from_file(&other_coverart).unwrap(), bytes);
+                    assert_eq!(bytes, coverart::get_coverart(&new_filepath).unwrap());
+                    assert_eq!(
+                        (true, 1),
+                        coverart::contains_coverart(&new_filepath).unwrap()
+                    );
+                }
+                Err(err) => {
+                    panic!("Error: {:?}", err);
+                }
+            }
+
+            coverart::remove_coverart(&source_filepath).unwrap();
+            match coverart::copy_coverart(&source_filepath, &new_filepath) {
+                Ok(_) => panic!("Nothing should have been copied"),
+                Err(err) => assert_eq!(std::io::ErrorKind::NotFound, err.kind()),
+            }
+        }
     }
 }
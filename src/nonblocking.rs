@@ -0,0 +1,420 @@
+//! Async equivalents of the `meta`, `properties` and `detection` functions. Files
+//! are read with tokio. Parsing and writing run on the blocking thread pool.
+
+use lofty::file::AudioFile;
+
+/// Reads the metadata blocks of the song, not its audio, and parses them on the
+/// blocking pool
+async fn with_flac_file<T, F>(filepath: &str, f: F) -> Result<T, std::io::Error>
+where
+    T: Send + 'static,
+    F: FnOnce(lofty::flac::FlacFile) -> Result<T, std::io::Error> + Send + 'static,
+{
+    let mut file = tokio::fs::File::open(filepath).await?;
+    let region = crate::flac::read_metadata_region_async(&mut file).await?;
+
+    match tokio::task::spawn_blocking(move || match read_flac_file(&region) {
+        Ok(flac_file) => f(flac_file),
+        Err(err) => Err(err),
+    })
+    .await
+    {
+        Ok(result) => result,
+        Err(err) => Err(std::io::Error::other(err.to_string())),
+    }
+}
+
+/// Runs `flac::modify` on the blocking pool, so the song is written in place when
+/// the metadata fits and through a temporary file otherwise
+async fn modify_flac_file<T, F>(
+    filepath: &str,
+    options: crate::flac::WriteOptions,
+    f: F,
+) -> Result<(T, crate::flac::WriteReport), std::io::Error>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut lofty::flac::FlacFile) -> Result<T, std::io::Error> + Send + 'static,
+{
+    let filepath = String::from(filepath);
+
+    match tokio::task::spawn_blocking(move || crate::flac::modify(&filepath, &options, f)).await {
+        Ok(result) => result,
+        Err(err) => Err(std::io::Error::other(err.to_string())),
+    }
+}
+
+fn read_flac_file(data: &[u8]) -> Result<lofty::flac::FlacFile, std::io::Error> {
+    match lofty::flac::FlacFile::read_from(
+        &mut std::io::Cursor::new(data),
+        lofty::config::ParseOptions::new().read_properties(false),
+    ) {
+        Ok(flac_file) => Ok(flac_file),
+        Err(err) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            err.to_string(),
+        )),
+    }
+}
+
+async fn read_header(filepath: &str) -> Result<Vec<u8>, std::io::Error> {
+    use tokio::io::AsyncReadExt;
+
+    let mut header = Vec::new();
+    tokio::fs::File::open(filepath)
+        .await?
+        .take(crate::scan::HEADER_SIZE)
+        .read_to_end(&mut header)
+        .await?;
+
+    Ok(header)
+}
+
+pub mod meta {
+    pub mod coverart {
+        use lofty::ogg::OggPictureStorage;
+
+        /// See `meta::coverart::set_coverart`
+        pub async fn set_coverart(
+            song_filepath: &str,
+            coverart_filepath: &str,
+        ) -> Result<Vec<u8>, std::io::Error> {
+            let coverart = tokio::fs::read(coverart_filepath).await?;
+
+            match super::super::modify_flac_file(
+                song_filepath,
+                crate::flac::WriteOptions::default(),
+                move |flac_file| {
+                    crate::meta::coverart::embed_coverart(
+                        flac_file,
+                        &mut std::io::Cursor::new(coverart),
+                    )
+                },
+            )
+            .await
+            {
+                Ok((data, _)) => Ok(data),
+                Err(err) => Err(err),
+            }
+        }
+
+        /// See `meta::coverart::get_coverart`
+        pub async fn get_coverart(song_filepath: &str) -> Result<Vec<u8>, std::io::Error> {
+            super::super::with_flac_file(song_filepath, |flac_file| {
+                Ok(crate::meta::coverart::coverart_from_file(&flac_file))
+            })
+            .await
+        }
+
+        /// See `meta::coverart::contains_coverart`
+        pub async fn contains_coverart(
+            song_filepath: &str,
+        ) -> Result<(bool, usize), std::io::Error> {
+            super::super::with_flac_file(song_filepath, |flac_file| {
+                let count = flac_file.pictures().len();
+                Ok((count > 0, count))
+            })
+            .await
+        }
+
+        /// See `meta::coverart::remove_coverart`
+        pub async fn remove_coverart(song_filepath: &str) -> Result<Vec<u8>, std::io::Error> {
+            match super::super::modify_flac_file(
+                song_filepath,
+                crate::flac::WriteOptions::default(),
+                crate::meta::coverart::take_coverart,
+            )
+            .await
+            {
+                Ok((data, _)) => Ok(data),
+                Err(err) => Err(err),
+            }
+        }
+    }
+
+    pub mod metadata {
+        use crate::types;
+
+        /// See `meta::metadata::get_meta`
+        pub async fn get_meta(t: types::Type, filepath: &str) -> Result<String, std::io::Error> {
+            super::super::with_flac_file(filepath, move |flac_file| {
+                crate::meta::metadata::meta_from_file(t, &flac_file)
+            })
+            .await
+        }
+
+        /// See `meta::metadata::set_meta`
+        pub async fn set_meta(
+            t: types::Type,
+            filepath: &str,
+            value: &str,
+        ) -> Result<String, std::io::Error> {
+            match set_meta_with(t, filepath, value, &crate::flac::WriteOptions::default()).await {
+                Ok((written, _)) => Ok(written),
+                Err(err) => Err(err),
+            }
+        }
+
+        /// See `meta::metadata::set_meta_with`
+        pub async fn set_meta_with(
+            t: types::Type,
+            filepath: &str,
+            value: &str,
+            options: &crate::flac::WriteOptions,
+        ) -> Result<(String, crate::flac::WriteReport), std::io::Error> {
+            let value = String::from(value);
+
+            super::super::modify_flac_file(filepath, *options, move |flac_file| {
+                crate::meta::metadata::set_meta_in_file(t, flac_file, &value)
+            })
+            .await
+        }
+
+        /// See `meta::metadata::remove_meta`
+        pub async fn remove_meta(
+            t: types::Type,
+            filepath: &str,
+        ) -> Result<Vec<String>, std::io::Error> {
+            let mut type_strs: Vec<String> = vec![types::access::get_type(t)?];
+            type_strs.extend(types::access::get_fallback_types(t));
+
+            match super::super::modify_flac_file(
+                filepath,
+                crate::flac::WriteOptions::default(),
+                move |flac_file| {
+                    Ok(crate::meta::metadata::remove_comments_in_file(
+                        flac_file, &type_strs,
+                    ))
+                },
+            )
+            .await
+            {
+                Ok((removed, _)) => Ok(removed),
+                Err(err) => Err(err),
+            }
+        }
+
+        /// See `meta::metadata::get_all_meta`
+        pub async fn get_all_meta(filepath: &str) -> Result<types::SongMetadata, std::io::Error> {
+            super::super::with_flac_file(filepath, |flac_file| {
+                Ok(crate::meta::metadata::song_metadata_from_file(&flac_file))
+            })
+            .await
+        }
+    }
+}
+
+pub mod properties {
+
+    /// See `properties::get_song_properties`
+    pub async fn get_song_properties(
+        song_path: &str,
+    ) -> Result<crate::properties::SongProperties, std::io::Error> {
+        // The bitrates depend on the length of the audio, so the song is read
+        // from the file, which lofty seeks through rather than loading
+        let song_path = String::from(song_path);
+        match tokio::task::spawn_blocking(move || {
+            crate::properties::get_song_properties(&song_path)
+        })
+        .await
+        {
+            Ok(result) => result,
+            Err(err) => Err(std::io::Error::other(err.to_string())),
+        }
+    }
+
+    /// See `properties::get_duration`
+    pub async fn get_duration(song_path: &str) -> Result<std::time::Duration, std::io::Error> {
+        match get_song_properties(song_path).await {
+            Ok(song_properties) => Ok(song_properties.duration),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// See `properties::get_encoder_info`
+    pub async fn get_encoder_info(
+        song_path: &str,
+    ) -> Result<Option<crate::properties::encoder::EncoderInfo>, std::io::Error> {
+        super::with_flac_file(song_path, |flac_file| match flac_file.vorbis_comments() {
+            Some(vb) => Ok(crate::properties::encoder::parse_vendor(vb.vendor())),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "No tags found",
+            )),
+        })
+        .await
+    }
+}
+
+pub mod detection {
+    pub mod song {
+        /// See `detection::song::file_type_from_filepath`
+        pub async fn file_type_from_filepath(
+            filepath: &str,
+        ) -> Result<crate::detection::FileType, std::io::Error> {
+            match super::super::read_header(filepath).await {
+                Ok(header) => crate::detection::song::file_type_from_data(&header),
+                Err(err) => Err(err),
+            }
+        }
+    }
+
+    pub mod coverart {
+        /// See `detection::coverart::file_type_from_filepath`
+        pub async fn file_type_from_filepath(
+            filepath: &str,
+        ) -> Result<crate::detection::FileType, std::io::Error> {
+            match super::super::read_header(filepath).await {
+                Ok(header) => crate::detection::coverart::file_type_from_data(&header),
+                Err(err) => Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+    use crate::types;
+
+    #[tokio::test]
+    async fn test_read_metadata_region() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let data = util::get_data_from_file(&filepath).unwrap();
+        let audio_length = crate::flac::parse(&data).unwrap().audio.len();
+
+        // Only the blocks in front of the audio are read
+        let mut file = tokio::fs::File::open(&filepath).await.unwrap();
+        let region = crate::flac::read_metadata_region_async(&mut file)
+            .await
+            .unwrap();
+        assert_eq!(&data[..data.len() - audio_length], region.as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_metadata() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        assert_eq!(
+            "Just roll it",
+            super::meta::metadata::get_meta(types::Type::Title, &filepath)
+                .await
+                .unwrap()
+        );
+
+        let written = super::meta::metadata::set_meta(types::Type::Date, &filepath, "2019-05")
+            .await
+            .unwrap();
+        assert_eq!("2019-05", written);
+        let (written, report) = super::meta::metadata::set_meta_with(
+            types::Type::Date,
+            &filepath,
+            "2019-05",
+            &crate::flac::WriteOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!("2019-05", written);
+        assert_eq!(crate::flac::WriteMode::InPlace, report.mode);
+        assert_eq!(
+            "2019-05",
+            crate::meta::metadata::get_meta(types::Type::Date, &filepath).unwrap()
+        );
+
+        match super::meta::metadata::set_meta(types::Type::Track, &filepath, "three").await {
+            Ok(written) => panic!("Invalid track written {written}"),
+            Err(err) => assert_eq!(std::io::ErrorKind::InvalidInput, err.kind()),
+        }
+
+        let removed = super::meta::metadata::remove_meta(types::Type::Genre, &filepath)
+            .await
+            .unwrap();
+        assert_eq!(vec![String::from("Metal")], removed);
+
+        let song_metadata = super::meta::metadata::get_all_meta(&filepath)
+            .await
+            .unwrap();
+        assert_eq!(None, song_metadata.genre);
+        assert_eq!(Some(String::from("KD")), song_metadata.artist);
+        assert_eq!(1, song_metadata.pictures.len());
+    }
+
+    #[tokio::test]
+    async fn test_coverart() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath = util::copy_track(&test_dir, &util::get_filename(1));
+        let coverart_filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, "Sample Tracks 3 - Other one.png")
+                .unwrap();
+
+        let removed = super::meta::coverart::remove_coverart(&filepath)
+            .await
+            .unwrap();
+        assert!(!removed.is_empty());
+        assert_eq!(
+            (false, 0),
+            super::meta::coverart::contains_coverart(&filepath)
+                .await
+                .unwrap()
+        );
+
+        let data = super::meta::coverart::set_coverart(&filepath, &coverart_filepath)
+            .await
+            .unwrap();
+        assert_eq!(util::get_data_from_file(&coverart_filepath).unwrap(), data);
+        assert_eq!(
+            data,
+            super::meta::coverart::get_coverart(&filepath)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            "Just roll it",
+            crate::meta::metadata::get_meta(types::Type::Title, &filepath).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_properties_and_detection() {
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let coverart_filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, "Sample Tracks 3.png").unwrap();
+
+        let song_properties = super::properties::get_song_properties(&filepath)
+            .await
+            .unwrap();
+        assert_eq!(48000, song_properties.sample_rate);
+        assert_eq!(
+            41,
+            super::properties::get_duration(&filepath)
+                .await
+                .unwrap()
+                .as_secs()
+        );
+        assert_eq!(
+            Some(String::from("FFmpeg")),
+            super::properties::get_encoder_info(&filepath)
+                .await
+                .unwrap()
+                .map(|encoder| encoder.name)
+        );
+
+        let file_type = super::detection::song::file_type_from_filepath(&filepath)
+            .await
+            .unwrap();
+        assert_eq!("flac", file_type.file_type);
+        let file_type = super::detection::coverart::file_type_from_filepath(&coverart_filepath)
+            .await
+            .unwrap();
+        assert_eq!("png", file_type.file_type);
+        assert!(
+            super::detection::song::file_type_from_filepath(&coverart_filepath)
+                .await
+                .is_err()
+        );
+    }
+}
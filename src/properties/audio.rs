@@ -1,4 +1,5 @@
 use lofty::file::AudioFile;
+use sha2::Digest;
 
 pub fn get_properties(songpath: &str) -> Result<lofty::flac::FlacProperties, std::io::Error> {
     match std::fs::File::open(songpath) {
@@ -12,3 +13,18 @@ pub fn get_properties(songpath: &str) -> Result<lofty::flac::FlacProperties, std
         Err(err) => Err(err),
     }
 }
+
+/// Hex encoded SHA-256 of the audio frames, which does not change when tags or
+/// pictures are edited
+pub fn get_audio_hash(songpath: &str) -> Result<String, std::io::Error> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(songpath)?);
+    // The reader is left at the first audio frame
+    let region = crate::flac::read_metadata_region(&mut reader)?;
+    crate::flac::parse(&region)?;
+
+    let mut hasher = sha2::Sha256::new();
+    match std::io::copy(&mut reader, &mut hasher) {
+        Ok(_) => Ok(crate::util::to_hex(&hasher.finalize())),
+        Err(err) => Err(err),
+    }
+}
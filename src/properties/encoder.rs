@@ -0,0 +1,94 @@
+//! Identify the encoder of a song from its Vorbis comment vendor string
+
+/// The encoder named by a vendor string such as `reference libFLAC 1.4.3 20230623`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncoderInfo {
+    /// `libFLAC`, `FFmpeg`, `CUETools` or `XLD`
+    pub name: String,
+    pub version: Option<String>,
+    /// Release date as `YYYYMMDD`. XLD versions are release dates, so both are set.
+    pub date: Option<String>,
+}
+
+/// Recognizes the vendor strings of libFLAC, FFmpeg (`Lavf`), CUETools and XLD.
+/// Other encoders give `None`.
+pub fn parse_vendor(vendor: &str) -> Option<EncoderInfo> {
+    let words: Vec<&str> = vendor.split_whitespace().collect();
+    let encoder = |name: &str, version: Option<&&str>, date: Option<&&str>| EncoderInfo {
+        name: String::from(name),
+        version: version.map(|version| version.to_string()),
+        date: date
+            .filter(|date| is_date(date))
+            .map(|date| date.to_string()),
+    };
+
+    match words.as_slice() {
+        ["reference", "libFLAC", rest @ ..] | ["libFLAC", rest @ ..] => {
+            Some(encoder("libFLAC", rest.first(), rest.get(1)))
+        }
+        [ident, ..] if ident.starts_with("Lavf") || ident.starts_with("Lavc") => {
+            let version = &ident[4..];
+            Some(encoder(
+                "FFmpeg",
+                Some(&version).filter(|version| !version.is_empty()),
+                None,
+            ))
+        }
+        [name, rest @ ..] if *name == "CUETools" || name.starts_with("CUETools.") => {
+            Some(encoder("CUETools", rest.first(), None))
+        }
+        ["XLD", "version", version, ..] | ["X", "Lossless", "Decoder", version, ..] => {
+            Some(encoder("XLD", Some(version), Some(version)))
+        }
+        _ => None,
+    }
+}
+
+fn is_date(value: &str) -> bool {
+    value.len() == 8 && value.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    fn encoder(name: &str, version: Option<&str>, date: Option<&str>) -> super::EncoderInfo {
+        super::EncoderInfo {
+            name: String::from(name),
+            version: version.map(String::from),
+            date: date.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_parse_vendor() {
+        let cases = [
+            (
+                "reference libFLAC 1.4.3 20230623",
+                Some(encoder("libFLAC", Some("1.4.3"), Some("20230623"))),
+            ),
+            (
+                "reference libFLAC 1.2.1 20070917",
+                Some(encoder("libFLAC", Some("1.2.1"), Some("20070917"))),
+            ),
+            (
+                "Lavf61.7.100",
+                Some(encoder("FFmpeg", Some("61.7.100"), None)),
+            ),
+            ("Lavf", Some(encoder("FFmpeg", None, None))),
+            (
+                "CUETools 2.1.6",
+                Some(encoder("CUETools", Some("2.1.6"), None)),
+            ),
+            (
+                "XLD version 20191004",
+                Some(encoder("XLD", Some("20191004"), Some("20191004"))),
+            ),
+            ("Xiph.Org libVorbis I 20200704 (Reducing Environment)", None),
+            ("", None),
+        ];
+
+        for (vendor, expected) in cases {
+            assert_eq!(expected, super::parse_vendor(vendor), "{vendor:?}");
+        }
+    }
+}
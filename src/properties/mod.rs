@@ -1,7 +1,14 @@
 pub mod audio;
+pub mod encoder;
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SongProperties {
+    /// Serialized as whole milliseconds under `duration_ms`
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "duration_ms", with = "duration_millis")
+    )]
     pub duration: std::time::Duration,
     pub sample_rate: u32,
     pub bitrate: u32,
@@ -12,18 +19,24 @@ pub struct SongProperties {
 
 pub fn get_song_properties(song_path: &str) -> Result<SongProperties, std::io::Error> {
     match audio::get_properties(song_path) {
-        Ok(flac_properties) => Ok(SongProperties {
-            duration: flac_properties.duration(),
-            sample_rate: flac_properties.sample_rate(),
-            bitrate: flac_properties.audio_bitrate(),
-            overall_bitrate: flac_properties.overall_bitrate(),
-            bit_depth: flac_properties.bit_depth(),
-            channels: flac_properties.channels(),
-        }),
+        Ok(flac_properties) => Ok(song_properties_from(&flac_properties)),
         Err(err) => Err(err),
     }
 }
 
+pub(crate) fn song_properties_from(
+    flac_properties: &lofty::flac::FlacProperties,
+) -> SongProperties {
+    SongProperties {
+        duration: flac_properties.duration(),
+        sample_rate: flac_properties.sample_rate(),
+        bitrate: flac_properties.audio_bitrate(),
+        overall_bitrate: flac_properties.overall_bitrate(),
+        bit_depth: flac_properties.bit_depth(),
+        channels: flac_properties.channels(),
+    }
+}
+
 pub fn get_duration(song_path: &str) -> Result<std::time::Duration, std::io::Error> {
     match get_song_properties(song_path) {
         Ok(song_properties) => Ok(song_properties.duration),
@@ -31,7 +44,34 @@ pub fn get_duration(song_path: &str) -> Result<std::time::Duration, std::io::Err
     }
 }
 
+/// The encoder named by the vendor string of the song, when it is one
+/// `encoder::parse_vendor` knows
+pub fn get_encoder_info(song_path: &str) -> Result<Option<encoder::EncoderInfo>, std::io::Error> {
+    match crate::meta::metadata::get_vendor(&String::from(song_path)) {
+        Ok(vendor) => Ok(encoder::parse_vendor(&vendor)),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(feature = "serde")]
+mod duration_millis {
+    pub fn serialize<S: serde::Serializer>(
+        duration: &std::time::Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<std::time::Duration, D::Error> {
+        let millis = <u64 as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(std::time::Duration::from_millis(millis))
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use crate::test_util;
 
@@ -90,7 +130,7 @@ mod tests {
 
                             let song_duration: u64 = 41;
                             let bitrate: u32 = 1;
-                            let overall_bitrate: u32 = 3;
+                            let overall_bitrate: u32 = 9;
                             let bit_depth: u8 = 24;
                             let channels: u8 = 2;
 
@@ -136,4 +176,29 @@ mod tests {
             }
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_song_properties() {
+        let filepath = test_util::util::get_full_path(
+            test_util::util::TESTFILEDIRECTORY,
+            &test_util::util::get_filename(1),
+        )
+        .unwrap();
+
+        match super::get_song_properties(&filepath) {
+            Ok(song_properties) => {
+                let json = serde_json::to_value(&song_properties).unwrap();
+                assert_eq!(41926, json["duration_ms"], "Unexpected JSON {json}");
+                assert_eq!(48000, json["sample_rate"], "Unexpected JSON {json}");
+
+                let parsed: super::SongProperties = serde_json::from_value(json).unwrap();
+                assert_eq!(song_properties.duration, parsed.duration);
+                assert_eq!(song_properties.bit_depth, parsed.bit_depth);
+            }
+            Err(err) => {
+                panic!("Error: {err:?}");
+            }
+        }
+    }
 }
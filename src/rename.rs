@@ -0,0 +1,688 @@
+//! Move songs to paths built from their tags, and read tags back from paths
+
+use crate::types;
+
+/// A value a template placeholder stands for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Placeholder {
+    Field(types::Type),
+    /// The year of DATE
+    Year,
+    /// The extension of the song, without the dot
+    Ext,
+    /// Any other Vorbis comment, by its uppercase key
+    Custom(String),
+}
+
+impl Placeholder {
+    /// Accepts Vorbis keys and field names in any case, with or without
+    /// underscores, plus `track`, `disc`, `year` and `ext`
+    pub fn from_name(name: &str) -> Placeholder {
+        let squashed = name.replace('_', "").to_lowercase();
+        match squashed.as_str() {
+            "track" => return Placeholder::Field(types::Type::Track),
+            "disc" => return Placeholder::Field(types::Type::Disc),
+            "year" => return Placeholder::Year,
+            "ext" => return Placeholder::Ext,
+            _ => {}
+        }
+
+        match types::all_metadata_types().into_iter().find(|t| {
+            types::access::get_type(*t)
+                .is_ok_and(|key| key.replace('_', "").eq_ignore_ascii_case(&squashed))
+        }) {
+            Some(t) => Placeholder::Field(t),
+            None => Placeholder::Custom(name.to_uppercase()),
+        }
+    }
+
+    /// The value for the song, None when the song does not have it
+    pub fn value(&self, song_metadata: &types::SongMetadata, ext: &str) -> Option<String> {
+        match self {
+            Placeholder::Field(t) => song_metadata.get(*t).map(|value| value.to_string()),
+            Placeholder::Year => song_metadata.date.map(|date| date.year().to_string()),
+            Placeholder::Ext => Some(String::from(ext)),
+            Placeholder::Custom(key) => song_metadata
+                .custom
+                .get(key)
+                .and_then(|values| values.first().cloned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Part {
+    Text(String),
+    /// `{name}` or `{name:0N}`, which pads the value with zeros to N characters
+    Placeholder {
+        placeholder: Placeholder,
+        width: usize,
+    },
+}
+
+/// A path template like `{albumartist}/{date} - {album}/{disc}-{track:02} {title}.{ext}`.
+/// `/` separates directories, and `{{` and `}}` stand for literal braces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    pub parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, std::io::Error> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => return Err(invalid_template(template, "unclosed {")),
+                        }
+                    }
+
+                    let (name, spec) = match inner.split_once(':') {
+                        Some((name, spec)) => (name.trim(), Some(spec.trim())),
+                        None => (inner.trim(), None),
+                    };
+                    if name.is_empty() {
+                        return Err(invalid_template(template, "empty placeholder"));
+                    }
+                    let width = match spec {
+                        Some(spec) => match spec.parse::<usize>() {
+                            Ok(width) => width,
+                            Err(_) => return Err(invalid_template(template, "invalid width")),
+                        },
+                        None => 0,
+                    };
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder {
+                        placeholder: Placeholder::from_name(name),
+                        width,
+                    });
+                }
+                '}' => return Err(invalid_template(template, "unmatched }")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Builds the relative path for a song. Values are sanitized so they cannot
+    /// add directories or characters that filesystems reject.
+    pub fn render(
+        &self,
+        song_metadata: &types::SongMetadata,
+        ext: &str,
+    ) -> Result<String, std::io::Error> {
+        let mut path = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => path.push_str(text),
+                Part::Placeholder { placeholder, width } => {
+                    match placeholder.value(song_metadata, ext) {
+                        Some(value) => {
+                            path.push_str(&sanitize(&format!("{value:0>width$}")));
+                        }
+                        None => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                format!("Missing value for {placeholder:?}"),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let components: Vec<&str> = path
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .collect();
+        Ok(components.join("/"))
+    }
+}
+
+fn invalid_template(template: &str, reason: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid template {template:?}: {reason}"),
+    )
+}
+
+/// Replaces characters that are not allowed in file names on common filesystems
+/// with `_`, and trailing dots and spaces which Windows drops
+pub fn sanitize(value: &str) -> String {
+    let replaced: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let trimmed = replaced.trim_end_matches(['.', ' ']).trim_start();
+    if trimmed.is_empty() {
+        String::from("_")
+    } else {
+        String::from(trimmed)
+    }
+}
+
+/// A planned move of a song
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub from: String,
+    pub to: String,
+}
+
+/// Computes where each song goes below `destination`. When the path is taken by
+/// an existing file or an earlier song, ` (2)`, ` (3)` and so on is added before
+/// the extension. Songs already at their destination are left out.
+pub fn plan(
+    paths: &[String],
+    template: &Template,
+    destination: &str,
+) -> Result<Vec<Move>, std::io::Error> {
+    let mut moves = Vec::new();
+    let mut taken: std::collections::HashSet<std::path::PathBuf> = std::collections::HashSet::new();
+
+    for path in paths {
+        let ext = match std::path::Path::new(path).extension() {
+            Some(ext) => ext.to_string_lossy().into_owned(),
+            None => String::new(),
+        };
+        let relative = match crate::meta::metadata::get_all_meta(path)
+            .and_then(|song_metadata| template.render(&song_metadata, &ext))
+        {
+            Ok(relative) => relative,
+            Err(err) => return Err(std::io::Error::new(err.kind(), format!("{path}: {err}"))),
+        };
+
+        let wanted = std::path::Path::new(destination).join(&relative);
+        let source = std::path::Path::new(path);
+        let mut target = wanted.clone();
+        let mut count = 1;
+        while taken.contains(&target) || (target.exists() && !same_file(&target, source)) {
+            count += 1;
+            target = numbered(&wanted, count);
+        }
+        taken.insert(target.clone());
+
+        if !same_file(&target, source) {
+            moves.push(Move {
+                from: path.clone(),
+                to: target.to_string_lossy().into_owned(),
+            });
+        }
+    }
+
+    Ok(moves)
+}
+
+fn same_file(a: &std::path::Path, b: &std::path::Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn numbered(path: &std::path::Path, count: usize) -> std::path::PathBuf {
+    let stem = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => String::new(),
+    };
+    let name = match path.extension() {
+        Some(ext) => format!("{stem} ({count}).{}", ext.to_string_lossy()),
+        None => format!("{stem} ({count})"),
+    };
+    path.with_file_name(name)
+}
+
+/// Moves the songs, creating directories as needed. Falls back to copying and
+/// removing when a song moves to another filesystem.
+pub fn perform(moves: &[Move]) -> Result<(), std::io::Error> {
+    for planned in moves {
+        let to = std::path::Path::new(&planned.to);
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if to.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists", planned.to),
+            ));
+        }
+
+        if let Err(err) = std::fs::rename(&planned.from, to) {
+            if err.kind() != std::io::ErrorKind::CrossesDevices {
+                return Err(std::io::Error::new(
+                    err.kind(),
+                    format!("{}: {err}", planned.from),
+                ));
+            }
+            std::fs::copy(&planned.from, to)?;
+            std::fs::remove_file(&planned.from)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Plans the moves and performs them unless `simulate` is set
+pub fn rename(
+    paths: &[String],
+    template: &str,
+    destination: &str,
+    simulate: bool,
+) -> Result<Vec<Move>, std::io::Error> {
+    let template = Template::parse(template)?;
+    let moves = plan(paths, &template, destination)?;
+    if !simulate {
+        perform(&moves)?;
+    }
+
+    Ok(moves)
+}
+
+/// Reads tag values from the path of a song using a template such as
+/// `{track} - {artist} - {title}` or `{album}/{track} {title}`. The template is
+/// matched against the last directories and the file name of the path, without
+/// the extension unless the template ends with `{ext}`. Values are trimmed and
+/// validated like `types::SongMetadata::set`. Other placeholders end up in
+/// `custom`, and `{year}` sets the date.
+pub fn tags_from_path(path: &str, pattern: &str) -> Result<types::SongMetadata, std::io::Error> {
+    let template = Template::parse(pattern)?;
+
+    let separators: usize = template
+        .parts
+        .iter()
+        .map(|part| match part {
+            Part::Text(text) => text.matches('/').count(),
+            Part::Placeholder { .. } => 0,
+        })
+        .sum();
+    let with_ext = template.parts.iter().any(|part| {
+        matches!(
+            part,
+            Part::Placeholder {
+                placeholder: Placeholder::Ext,
+                ..
+            }
+        )
+    });
+
+    let file = std::path::Path::new(path);
+    let last = if with_ext {
+        file.file_name()
+    } else {
+        file.file_stem()
+    };
+    let mut components: Vec<String> = match last {
+        Some(last) => vec![last.to_string_lossy().into_owned()],
+        None => Vec::new(),
+    };
+    for ancestor in file.ancestors().skip(1).take(separators) {
+        if let Some(name) = ancestor.file_name() {
+            components.insert(0, name.to_string_lossy().into_owned());
+        }
+    }
+    let text = components.join("/");
+
+    let mut captures = Vec::new();
+    if !match_parts(&template.parts, &text, &mut captures) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{text:?} does not match {pattern:?}"),
+        ));
+    }
+
+    let mut song_metadata = types::SongMetadata::default();
+    for (placeholder, value) in captures {
+        match placeholder {
+            Placeholder::Field(t) => song_metadata.set(t, &value)?,
+            Placeholder::Year => song_metadata.set(types::Type::Date, &value)?,
+            Placeholder::Ext => {}
+            Placeholder::Custom(key) => song_metadata.custom.entry(key).or_default().push(value),
+        }
+    }
+
+    Ok(song_metadata)
+}
+
+/// Matches the text against the parts, giving each placeholder the shortest
+/// value that is valid for it and lets the rest match
+fn match_parts(parts: &[Part], text: &str, captures: &mut Vec<(Placeholder, String)>) -> bool {
+    match parts.split_first() {
+        None => text.is_empty(),
+        Some((Part::Text(literal), rest)) => match text.strip_prefix(literal.as_str()) {
+            Some(remaining) => match_parts(rest, remaining, captures),
+            None => false,
+        },
+        Some((Part::Placeholder { placeholder, .. }, rest)) => {
+            let ends = text
+                .char_indices()
+                .skip(1)
+                .map(|(end, _)| end)
+                .chain(std::iter::once(text.len()));
+
+            for end in ends {
+                let value = &text[..end];
+                if value.contains('/') {
+                    break;
+                }
+                let value = value.trim();
+                if !accepts(placeholder, value) {
+                    continue;
+                }
+
+                captures.push((placeholder.clone(), String::from(value)));
+                if match_parts(rest, &text[end..], captures) {
+                    return true;
+                }
+                captures.pop();
+            }
+
+            false
+        }
+    }
+}
+
+fn accepts(placeholder: &Placeholder, value: &str) -> bool {
+    if value.is_empty() {
+        return false;
+    }
+
+    match placeholder {
+        Placeholder::Field(t) => types::MetadataType::parse(*t, value).is_ok(),
+        Placeholder::Year => value.len() == 4 && value.chars().all(|c| c.is_ascii_digit()),
+        Placeholder::Ext | Placeholder::Custom(_) => true,
+    }
+}
+
+/// Reads tags from the path like `tags_from_path` and writes them, custom fields
+/// included, in a single write. Fields the song already has are kept unless
+/// `overwrite` is set. Returns the Vorbis keys written with their values.
+pub fn apply_tags_from_path(
+    path: &String,
+    pattern: &str,
+    overwrite: bool,
+) -> Result<Vec<(String, String)>, std::io::Error> {
+    let parsed = tags_from_path(path, pattern)?;
+
+    match crate::flac::modify(path, &crate::flac::WriteOptions::default(), |flac_file| {
+        if flac_file.vorbis_comments().is_none() {
+            flac_file.set_vorbis_comments(lofty::ogg::VorbisComments::default());
+        }
+
+        let mut written = Vec::new();
+        for t in types::all_metadata_types() {
+            let Some(value) = parsed.get(t) else {
+                continue;
+            };
+            // Values that do not parse are kept too
+            let mut keys = vec![types::access::get_type(t)?];
+            keys.extend(types::access::get_fallback_types(t));
+            let present = flac_file
+                .vorbis_comments()
+                .is_some_and(|vb| keys.iter().any(|key| vb.get(key).is_some()));
+            if overwrite || !present {
+                let value =
+                    crate::meta::metadata::set_meta_in_file(t, flac_file, &value.to_string())?;
+                written.push((types::access::get_type(t)?, value));
+            }
+        }
+
+        if let Some(vb) = flac_file.vorbis_comments_mut() {
+            for (key, values) in &parsed.custom {
+                if overwrite || vb.get(key).is_none() {
+                    let _ = vb.remove(key);
+                    for value in values {
+                        vb.push(key.to_owned(), value.to_owned());
+                        written.push((key.to_owned(), value.to_owned()));
+                    }
+                }
+            }
+        }
+
+        Ok(written)
+    }) {
+        Ok((written, _)) => Ok(written),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+    use crate::types;
+
+    const TEMPLATE: &str = "{albumartist}/{date} - {album}/{disc}-{track:02} {title}.{ext}";
+
+    #[test]
+    fn test_template() {
+        let template = super::Template::parse(TEMPLATE).unwrap();
+        assert_eq!(
+            super::Part::Placeholder {
+                placeholder: super::Placeholder::Field(types::Type::Track),
+                width: 2
+            },
+            template.parts[8]
+        );
+
+        let mut song_metadata = types::SongMetadata::default();
+        song_metadata
+            .set(types::Type::AlbumArtist, "AC/DC")
+            .unwrap();
+        song_metadata.set(types::Type::Date, "1980-07-25").unwrap();
+        song_metadata
+            .set(types::Type::Album, "Back in Black")
+            .unwrap();
+        song_metadata.set(types::Type::Disc, "1").unwrap();
+        song_metadata.set(types::Type::Track, "6").unwrap();
+        song_metadata.set(types::Type::Title, "What: Now?").unwrap();
+        assert_eq!(
+            "AC_DC/1980-07-25 - Back in Black/1-06 What_ Now_.flac",
+            template.render(&song_metadata, "flac").unwrap()
+        );
+
+        let template = super::Template::parse("{year}/{{{mood}}} {Album_Artist}").unwrap();
+        song_metadata
+            .custom
+            .insert(String::from("MOOD"), vec![String::from("Loud...")]);
+        assert_eq!(
+            "1980/{Loud} AC_DC",
+            template.render(&song_metadata, "flac").unwrap()
+        );
+
+        song_metadata.title = None;
+        let template = super::Template::parse("{title}").unwrap();
+        assert!(template.render(&song_metadata, "flac").is_err());
+
+        for invalid in ["{title", "title}", "{}", "{track:two}"] {
+            assert!(super::Template::parse(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!("_", super::sanitize(".."));
+    }
+
+    #[test]
+    fn test_rename() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let library = util::get_full_path(&test_dir, "library").unwrap();
+
+        let paths: Vec<String> = (1..=3)
+            .map(|track| {
+                let filepath =
+                    util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(track))
+                        .unwrap();
+                let new_filepath =
+                    util::get_full_path(&test_dir, &util::generate_filename().unwrap()).unwrap();
+                util::copy_file(&filepath, &new_filepath).unwrap();
+                new_filepath
+            })
+            .collect();
+        crate::meta::metadata::set_meta(types::Type::Title, &paths[2], "Here we go!").unwrap();
+        crate::meta::metadata::set_meta(types::Type::Track, &paths[2], "2").unwrap();
+
+        let album_dir = format!("{library}/KD/2025-04-11 - Sample Tracks 3");
+        let expected = vec![
+            super::Move {
+                from: paths[0].clone(),
+                to: format!("{album_dir}/1-01 Just roll it.flac"),
+            },
+            super::Move {
+                from: paths[1].clone(),
+                to: format!("{album_dir}/1-02 Here we go!.flac"),
+            },
+            super::Move {
+                from: paths[2].clone(),
+                to: format!("{album_dir}/1-02 Here we go! (2).flac"),
+            },
+        ];
+
+        let moves = super::rename(&paths, TEMPLATE, &library, true).unwrap();
+        assert_eq!(expected, moves);
+        assert!(paths.iter().all(|path| std::path::Path::new(path).exists()));
+
+        let moves = super::rename(&paths, TEMPLATE, &library, false).unwrap();
+        assert_eq!(expected, moves);
+        for planned in &moves {
+            assert!(!std::path::Path::new(&planned.from).exists());
+            assert!(std::path::Path::new(&planned.to).exists());
+        }
+
+        // Renaming again leaves the songs where they are
+        let moved: Vec<String> = moves.into_iter().map(|planned| planned.to).collect();
+        assert!(
+            super::rename(&moved, TEMPLATE, &library, false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_tags_from_path() {
+        let song_metadata = super::tags_from_path(
+            "/music/Sample Tracks 3/03 - Someone - A - Song.flac",
+            "{album}/{track} - {artist} - {title}",
+        )
+        .unwrap();
+        assert_eq!(Some(String::from("Sample Tracks 3")), song_metadata.album);
+        assert_eq!(Some(3), song_metadata.track);
+        assert_eq!(Some(String::from("Someone")), song_metadata.artist);
+        assert_eq!(Some(String::from("A - Song")), song_metadata.title);
+
+        let song_metadata =
+            super::tags_from_path("1999/01 Intro.flac", "{year}/{track:02} {title}.{ext}").unwrap();
+        assert_eq!(Some(1999), song_metadata.date.map(|date| date.year()));
+        assert_eq!(Some(1), song_metadata.track);
+        assert_eq!(Some(String::from("Intro")), song_metadata.title);
+
+        // The track has to be a number, so "Intro" cannot be the track
+        assert!(super::tags_from_path("Intro 01.flac", "{track} {title}").is_err());
+        assert!(super::tags_from_path("track.flac", "{track} - {title}").is_err());
+    }
+
+    #[test]
+    fn test_apply_tags_from_path() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let album_dir = util::get_full_path(&test_dir, "Other Album").unwrap();
+        std::fs::create_dir_all(&album_dir).unwrap();
+
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath =
+            util::get_full_path(&album_dir, "07 - Someone - New Title.flac").unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        crate::meta::metadata::remove_meta(types::Type::Track, &new_filepath).unwrap();
+        crate::meta::metadata::remove_meta(types::Type::Title, &new_filepath).unwrap();
+
+        let pattern = "{album}/{track} - {artist} - {title}";
+        let written = super::apply_tags_from_path(&new_filepath, pattern, false).unwrap();
+        assert_eq!(
+            vec![
+                (String::from("TITLE"), String::from("New Title")),
+                (String::from("TRACKNUMBER"), String::from("7"))
+            ],
+            written
+        );
+        let song_metadata = crate::meta::metadata::get_all_meta(&new_filepath).unwrap();
+        assert_eq!(Some(String::from("KD")), song_metadata.artist);
+        assert_eq!(Some(String::from("Sample Tracks 3")), song_metadata.album);
+        assert_eq!(Some(7), song_metadata.track);
+
+        let written = super::apply_tags_from_path(&new_filepath, pattern, true).unwrap();
+        assert_eq!(4, written.len());
+        let song_metadata = crate::meta::metadata::get_all_meta(&new_filepath).unwrap();
+        assert_eq!(Some(String::from("Someone")), song_metadata.artist);
+        assert_eq!(Some(String::from("Other Album")), song_metadata.album);
+
+        let pattern = "{album}/{track} - {mood} - {title}";
+        let written = super::apply_tags_from_path(&new_filepath, pattern, false).unwrap();
+        assert_eq!(
+            vec![(String::from("MOOD"), String::from("Someone"))],
+            written
+        );
+        assert_eq!(
+            vec![String::from("Someone")],
+            crate::meta::metadata::get_comments(&new_filepath, "MOOD").unwrap()
+        );
+        assert_eq!(
+            Some(1),
+            crate::meta::coverart::contains_coverart(&new_filepath)
+                .ok()
+                .map(|(_, count)| count)
+        );
+    }
+
+    #[test]
+    fn test_apply_tags_keeps_unparseable_values() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let album_dir = util::get_full_path(&test_dir, "Other Album").unwrap();
+        std::fs::create_dir_all(&album_dir).unwrap();
+
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::get_full_path(&album_dir, "2024 07 - New Title.flac").unwrap();
+        util::copy_file(&filepath, &new_filepath).unwrap();
+        crate::meta::metadata::set_comments(&new_filepath, "TRACKNUMBER", &[String::from("A1")])
+            .unwrap();
+        crate::meta::metadata::set_comments(&new_filepath, "DATE", &[String::from("unknown")])
+            .unwrap();
+
+        let pattern = "{album}/{date} {track} - {title}";
+        let written = super::apply_tags_from_path(&new_filepath, pattern, false).unwrap();
+        assert!(written.is_empty(), "{written:?}");
+        assert_eq!(
+            vec![String::from("A1")],
+            crate::meta::metadata::get_comments(&new_filepath, "TRACKNUMBER").unwrap()
+        );
+        assert_eq!(
+            vec![String::from("unknown")],
+            crate::meta::metadata::get_comments(&new_filepath, "DATE").unwrap()
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Walk a library directory and read every supported song and image in it
+
+use std::io::Read;
+
+use lofty::file::AudioFile;
+
+use crate::{detection, types};
+
+/// Bytes read from the start of a file to detect its type
+pub(crate) const HEADER_SIZE: u64 = 64;
+
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    /// Descend into linked directories and read linked files. Directories already
+    /// visited through another link are skipped.
+    pub follow_symlinks: bool,
+    /// Include files and directories whose name starts with a dot
+    pub include_hidden: bool,
+    /// Only read files with one of these extensions, compared case-insensitively
+    /// and without the dot. Empty reads every file.
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ScannedSong {
+    pub file_type: detection::FileType,
+    pub metadata: types::SongMetadata,
+    pub properties: crate::properties::SongProperties,
+}
+
+impl ScannedSong {
+    pub fn has_coverart(&self) -> bool {
+        !self.metadata.pictures.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub enum ScannedFile {
+    Song(Box<ScannedSong>),
+    Image(detection::FileType),
+}
+
+/// The outcome for one file. Files that could not be read or parsed carry their
+/// error so the rest of the scan can go on.
+#[derive(Debug)]
+pub struct ScanResult {
+    pub path: String,
+    pub file: Result<ScannedFile, std::io::Error>,
+}
+
+enum Pending {
+    File(std::path::PathBuf),
+    Failed(std::path::PathBuf, std::io::Error),
+}
+
+/// Iterator over the supported files below a directory, in name order with the
+/// files of a directory before the contents of its subdirectories
+pub struct Scan {
+    options: ScanOptions,
+    directories: Vec<std::path::PathBuf>,
+    pending: std::collections::VecDeque<Pending>,
+    visited: std::collections::HashSet<std::path::PathBuf>,
+}
+
+/// Starts a scan of the directory. Nothing is read until the iterator is advanced.
+pub fn scan(directory: &str, options: &ScanOptions) -> Scan {
+    let root = std::path::PathBuf::from(directory);
+    let mut visited = std::collections::HashSet::new();
+    if let Ok(canonical) = std::fs::canonicalize(&root) {
+        visited.insert(canonical);
+    }
+
+    Scan {
+        options: options.clone(),
+        directories: vec![root],
+        pending: std::collections::VecDeque::new(),
+        visited,
+    }
+}
+
+/// Detects and reads a single file. Returns None for files that are neither a
+/// supported song nor a supported image.
+pub fn read_file(filepath: &str) -> Result<Option<ScannedFile>, std::io::Error> {
+    let mut header = Vec::new();
+    std::fs::File::open(filepath)?
+        .take(HEADER_SIZE)
+        .read_to_end(&mut header)?;
+
+    if let Ok(file_type) = detection::song::file_type_from_data(&header) {
+        match read_song(filepath, file_type) {
+            Ok(song) => Ok(Some(ScannedFile::Song(Box::new(song)))),
+            Err(err) => Err(err),
+        }
+    } else if let Ok(file_type) = detection::coverart::file_type_from_data(&header) {
+        Ok(Some(ScannedFile::Image(file_type)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn read_song(
+    filepath: &str,
+    file_type: detection::FileType,
+) -> Result<ScannedSong, std::io::Error> {
+    match std::fs::File::open(filepath) {
+        Ok(mut content) => {
+            match lofty::flac::FlacFile::read_from(&mut content, lofty::config::ParseOptions::new())
+            {
+                Ok(flac_file) => Ok(ScannedSong {
+                    file_type,
+                    metadata: crate::meta::metadata::song_metadata_from_file(&flac_file),
+                    properties: crate::properties::song_properties_from(flac_file.properties()),
+                }),
+                Err(err) => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    err.to_string(),
+                )),
+            }
+        }
+        Err(err) => Err(err),
+    }
+}
+
+impl Scan {
+    fn is_hidden(&self, name: &std::ffi::OsStr) -> bool {
+        !self.options.include_hidden && name.to_string_lossy().starts_with('.')
+    }
+
+    fn has_wanted_extension(&self, path: &std::path::Path) -> bool {
+        if self.options.extensions.is_empty() {
+            return true;
+        }
+
+        match path.extension() {
+            Some(extension) => {
+                let extension = extension.to_string_lossy();
+                self.options.extensions.iter().any(|wanted| {
+                    wanted
+                        .trim_start_matches('.')
+                        .eq_ignore_ascii_case(&extension)
+                })
+            }
+            None => false,
+        }
+    }
+
+    /// Queues the files of a directory and pushes its subdirectories
+    fn read_directory(&mut self, directory: std::path::PathBuf) {
+        let entries = match std::fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.pending.push_back(Pending::Failed(directory, err));
+                return;
+            }
+        };
+
+        let mut entries: Vec<std::fs::DirEntry> = entries.filter_map(|entry| entry.ok()).collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut subdirectories = Vec::new();
+        for entry in entries {
+            if self.is_hidden(&entry.file_name()) {
+                continue;
+            }
+
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(err) => {
+                    self.pending.push_back(Pending::Failed(path, err));
+                    continue;
+                }
+            };
+
+            let is_dir = if file_type.is_symlink() {
+                if !self.options.follow_symlinks {
+                    continue;
+                }
+                match std::fs::metadata(&path) {
+                    Ok(metadata) => metadata.is_dir(),
+                    Err(err) => {
+                        self.pending.push_back(Pending::Failed(path, err));
+                        continue;
+                    }
+                }
+            } else {
+                file_type.is_dir()
+            };
+
+            if is_dir {
+                match std::fs::canonicalize(&path) {
+                    Ok(canonical) => {
+                        if self.visited.insert(canonical) {
+                            subdirectories.push(path);
+                        }
+                    }
+                    Err(err) => self.pending.push_back(Pending::Failed(path, err)),
+                }
+            } else if self.has_wanted_extension(&path) {
+                self.pending.push_back(Pending::File(path));
+            }
+        }
+
+        self.directories.extend(subdirectories.into_iter().rev());
+    }
+}
+
+impl Iterator for Scan {
+    type Item = ScanResult;
+
+    fn next(&mut self) -> Option<ScanResult> {
+        loop {
+            match self.pending.pop_front() {
+                Some(Pending::File(path)) => {
+                    let filepath = path.to_string_lossy().into_owned();
+                    match read_file(&filepath) {
+                        Ok(Some(file)) => {
+                            return Some(ScanResult {
+                                path: filepath,
+                                file: Ok(file),
+                            });
+                        }
+                        Ok(None) => {}
+                        Err(err) => {
+                            return Some(ScanResult {
+                                path: filepath,
+                                file: Err(err),
+                            });
+                        }
+                    }
+                }
+                Some(Pending::Failed(path, err)) => {
+                    return Some(ScanResult {
+                        path: path.to_string_lossy().into_owned(),
+                        file: Err(err),
+                    });
+                }
+                None => {
+                    let directory = self.directories.pop()?;
+                    self.read_directory(directory);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    /// root/track01.flac, root/notes.txt, root/broken.flac, root/art/cover.png,
+    /// root/disc2/track02.flac and root/.hidden/track03.flac
+    fn build_library(root: &std::path::Path) {
+        let copy = |track: i32, directory: &std::path::Path| {
+            std::fs::create_dir_all(directory).unwrap();
+            let filepath =
+                util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(track)).unwrap();
+            let new_filepath = directory.join(util::get_filename(track));
+            std::fs::copy(filepath, new_filepath).unwrap();
+        };
+        copy(1, root);
+        copy(2, &root.join("disc2"));
+        copy(3, &root.join(".hidden"));
+
+        std::fs::create_dir_all(root.join("art")).unwrap();
+        std::fs::copy(
+            util::get_full_path(util::TESTFILEDIRECTORY, "Sample Tracks 3.png").unwrap(),
+            root.join("art").join("cover.png"),
+        )
+        .unwrap();
+
+        std::fs::write(root.join("notes.txt"), "not a song").unwrap();
+        std::fs::write(root.join("broken.flac"), b"fLaC\x00\x00").unwrap();
+    }
+
+    fn relative_paths(root: &std::path::Path, results: &[super::ScanResult]) -> Vec<String> {
+        results
+            .iter()
+            .map(|result| {
+                std::path::Path::new(&result.path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scan() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let root = temp_file.path();
+        build_library(root);
+
+        let results: Vec<super::ScanResult> =
+            super::scan(root.to_str().unwrap(), &super::ScanOptions::default()).collect();
+        assert_eq!(
+            vec![
+                "broken.flac",
+                "track01.flac",
+                "art/cover.png",
+                "disc2/track02.flac"
+            ],
+            relative_paths(root, &results)
+        );
+
+        assert!(results[0].file.is_err());
+        match &results[1].file {
+            Ok(super::ScannedFile::Song(song)) => {
+                assert_eq!("flac", song.file_type.file_type);
+                assert_eq!(Some(String::from("Just roll it")), song.metadata.title);
+                assert_eq!(48000, song.properties.sample_rate);
+                assert!(song.has_coverart());
+            }
+            other => panic!("Expected a song {other:?}"),
+        }
+        match &results[2].file {
+            Ok(super::ScannedFile::Image(file_type)) => assert_eq!("png", file_type.file_type),
+            other => panic!("Expected an image {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_scan_options() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let root = temp_file.path();
+        build_library(root);
+
+        let options = super::ScanOptions {
+            include_hidden: true,
+            extensions: vec![String::from("FLAC")],
+            ..Default::default()
+        };
+        let results: Vec<super::ScanResult> =
+            super::scan(root.to_str().unwrap(), &options).collect();
+        assert_eq!(
+            vec![
+                "broken.flac",
+                "track01.flac",
+                ".hidden/track03.flac",
+                "disc2/track02.flac"
+            ],
+            relative_paths(root, &results)
+        );
+
+        let results: Vec<super::ScanResult> = super::scan(
+            root.join("missing").to_str().unwrap(),
+            &super::ScanOptions::default(),
+        )
+        .collect();
+        assert_eq!(1, results.len());
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            results[0].file.as_ref().unwrap_err().kind()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_symlinks() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let root = temp_file.path();
+        build_library(root);
+        std::os::unix::fs::symlink(root.join("disc2"), root.join("linked")).unwrap();
+        std::os::unix::fs::symlink(root, root.join("disc2").join("loop")).unwrap();
+
+        let options = super::ScanOptions {
+            extensions: vec![String::from("flac")],
+            ..Default::default()
+        };
+        let results: Vec<super::ScanResult> =
+            super::scan(root.to_str().unwrap(), &options).collect();
+        assert_eq!(3, results.len());
+
+        // The link to disc2 is read once, and the loop back to the root is skipped
+        let options = super::ScanOptions {
+            follow_symlinks: true,
+            ..options
+        };
+        let results: Vec<super::ScanResult> =
+            super::scan(root.to_str().unwrap(), &options).collect();
+        assert_eq!(
+            vec!["broken.flac", "track01.flac", "disc2/track02.flac"],
+            relative_paths(root, &results)
+        );
+    }
+}
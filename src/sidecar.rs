@@ -0,0 +1,267 @@
+//! Export the tags of many songs to a JSON document and apply an edited copy back
+
+use crate::types;
+
+pub const SIDECAR_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Sidecar {
+    pub version: u32,
+    pub files: Vec<SidecarEntry>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SidecarEntry {
+    pub path: String,
+    /// See `properties::audio::get_audio_hash`
+    pub audio_hash: String,
+    pub metadata: types::SongMetadata,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FileReport {
+    pub path: String,
+    /// See `diff::diff_metadata`
+    pub changes: Vec<crate::diff::Change>,
+}
+
+/// Dumps the tags, custom fields and picture descriptions of every song as JSON
+pub fn export_metadata(paths: &[String]) -> Result<String, std::io::Error> {
+    let mut sidecar = Sidecar {
+        version: SIDECAR_VERSION,
+        files: Vec::new(),
+    };
+
+    for path in paths {
+        match export_entry(path) {
+            Ok(entry) => sidecar.files.push(entry),
+            Err(err) => {
+                return Err(std::io::Error::new(err.kind(), format!("{path}: {err}")));
+            }
+        }
+    }
+
+    match serde_json::to_string_pretty(&sidecar) {
+        Ok(json) => Ok(json),
+        Err(err) => Err(std::io::Error::other(err.to_string())),
+    }
+}
+
+/// Writes the tags of an exported document back to the songs. Nothing is written
+/// if the audio of any song changed since the export. Songs without changes are
+/// left untouched and reported with an empty list of changes.
+pub fn apply_metadata(json: &str) -> Result<Vec<FileReport>, std::io::Error> {
+    let sidecar: Sidecar = match serde_json::from_str(json) {
+        Ok(sidecar) => sidecar,
+        Err(err) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                err.to_string(),
+            ));
+        }
+    };
+
+    if sidecar.version != SIDECAR_VERSION {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unsupported sidecar version {}", sidecar.version),
+        ));
+    }
+
+    let mut reports = Vec::new();
+    for entry in &sidecar.files {
+        match crate::properties::audio::get_audio_hash(&entry.path) {
+            Ok(audio_hash) if audio_hash == entry.audio_hash => {}
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Audio of {} changed since it was exported", entry.path),
+                ));
+            }
+            Err(err) => {
+                return Err(std::io::Error::new(
+                    err.kind(),
+                    format!("{}: {err}", entry.path),
+                ));
+            }
+        }
+
+        match crate::meta::metadata::get_all_meta(&entry.path) {
+            Ok(current) => reports.push(FileReport {
+                path: entry.path.clone(),
+                changes: crate::diff::diff_metadata(&current, &entry.metadata).changes,
+            }),
+            Err(err) => return Err(err),
+        }
+    }
+
+    for (entry, report) in sidecar.files.iter().zip(&reports) {
+        if !report.changes.is_empty()
+            && let Err(err) = crate::meta::metadata::set_all_meta(&entry.path, &entry.metadata)
+        {
+            return Err(std::io::Error::new(
+                err.kind(),
+                format!("{}: {err}", entry.path),
+            ));
+        }
+    }
+
+    Ok(reports)
+}
+
+fn export_entry(path: &String) -> Result<SidecarEntry, std::io::Error> {
+    match crate::properties::audio::get_audio_hash(path) {
+        Ok(audio_hash) => match crate::meta::metadata::get_all_meta(path) {
+            Ok(metadata) => Ok(SidecarEntry {
+                path: path.clone(),
+                audio_hash,
+                metadata,
+            }),
+            Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    fn copy_tracks(test_dir: &str) -> Vec<String> {
+        (1..=2)
+            .map(|track| util::copy_track(test_dir, &util::get_filename(track)))
+            .collect()
+    }
+
+    #[test]
+    fn test_export_apply_round_trip() {
+        use crate::diff::Change;
+
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_tracks(&test_dir);
+
+        let json = super::export_metadata(&paths).unwrap();
+        let mut document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!("Just roll it", document["files"][0]["metadata"]["title"]);
+        assert_eq!(
+            3,
+            document["files"][0]["metadata"]["pictures"][0]["picture_type"]
+        );
+
+        document["files"][0]["metadata"]["title"] = serde_json::json!("Rolled");
+        document["files"][0]["metadata"]["custom"]["COMMENT"] =
+            serde_json::json!(["first", "second"]);
+        document["files"][0]["metadata"]["pictures"] = serde_json::json!([]);
+
+        match super::apply_metadata(&document.to_string()) {
+            Ok(reports) => {
+                assert_eq!(2, reports.len());
+                assert!(reports[1].changes.is_empty(), "{:?}", reports[1]);
+
+                match reports[0].changes.as_slice() {
+                    [
+                        Change::Changed { field, new, .. },
+                        Change::Added { field: added, .. },
+                        Change::PictureRemoved(_),
+                    ] => {
+                        assert_eq!("TITLE", field);
+                        assert_eq!(&vec![String::from("Rolled")], new);
+                        assert_eq!("COMMENT", added);
+                    }
+                    other => panic!("Unexpected changes {other:?}"),
+                }
+            }
+            Err(err) => {
+                panic!("Error: {err:?}");
+            }
+        }
+
+        let song_metadata = crate::meta::metadata::get_all_meta(&paths[0]).unwrap();
+        assert_eq!(Some(String::from("Rolled")), song_metadata.title);
+        assert_eq!(Some(String::from("KD")), song_metadata.artist);
+        assert_eq!(
+            Some(&vec![String::from("first"), String::from("second")]),
+            song_metadata.custom.get("COMMENT")
+        );
+        assert!(song_metadata.pictures.is_empty());
+
+        // Applying the edited document again has nothing left to change
+        let reports = super::apply_metadata(&document.to_string()).unwrap();
+        assert!(reports.iter().all(|report| report.changes.is_empty()));
+    }
+
+    #[test]
+    fn test_apply_keeps_extra_values() {
+        use crate::meta::metadata::{ImportMode, get_comments, import_comments};
+
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_tracks(&test_dir);
+        import_comments(
+            &paths[0],
+            b"ARTIST=KD\nARTIST=Guest\nYEAR=1999\n",
+            ImportMode::Merge,
+        )
+        .unwrap();
+
+        let json = super::export_metadata(&paths[..1]).unwrap();
+        let mut document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let metadata = &document["files"][0]["metadata"];
+        assert_eq!("KD", metadata["artist"]);
+        assert_eq!(serde_json::json!(["Guest"]), metadata["custom"]["ARTIST"]);
+        assert_eq!(serde_json::json!(["1999"]), metadata["custom"]["YEAR"]);
+
+        document["files"][0]["metadata"]["title"] = serde_json::json!("Rolled");
+        let reports = super::apply_metadata(&document.to_string()).unwrap();
+        assert_eq!(1, reports[0].changes.len(), "{:?}", reports[0]);
+
+        assert_eq!(
+            vec![String::from("KD"), String::from("Guest")],
+            get_comments(&paths[0], "ARTIST").unwrap()
+        );
+        assert_eq!(
+            vec![String::from("1999")],
+            get_comments(&paths[0], "YEAR").unwrap()
+        );
+        assert_eq!(
+            vec![String::from("2025-04-11")],
+            get_comments(&paths[0], "DATE").unwrap()
+        );
+
+        document["files"][0]["metadata"]["custom"]["ARTIST"] = serde_json::json!([]);
+        super::apply_metadata(&document.to_string()).unwrap();
+        assert_eq!(
+            vec![String::from("KD")],
+            get_comments(&paths[0], "ARTIST").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_apply_refuses_changed_audio() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_tracks(&test_dir);
+
+        let json = super::export_metadata(&paths).unwrap();
+        let edited = json.replace("Here we go!", "There we went");
+
+        let mut data = util::get_data_from_file(&paths[0]).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        util::save_bytes_to_file(&data, &paths[0]).unwrap();
+
+        match super::apply_metadata(&edited) {
+            Ok(reports) => {
+                panic!("Apply should have been refused {reports:?}");
+            }
+            Err(err) => {
+                assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+                assert_eq!(
+                    "Here we go!",
+                    crate::meta::metadata::get_meta(crate::types::Type::Title, &paths[1]).unwrap()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,500 @@
+//! Split a single-file album into one FLAC file per track
+
+use crate::cue;
+use crate::flac;
+use crate::frame;
+
+/// Comments of the image that describe a single track, or the image itself,
+/// rather than the album
+const TRACK_KEYS: [&str; 10] = [
+    "TITLE",
+    "TRACKNUMBER",
+    "TRACKNUM",
+    "TRACKCOUNT",
+    "TRACKTOTAL",
+    "TOTALTRACKS",
+    "ISRC",
+    "CUESHEET",
+    "MUSICBRAINZ_TRACKID",
+    "ACOUSTID_ID",
+];
+
+/// Frames that are re-encoded may not be shorter than this, except the last one
+const MIN_BLOCK_SIZE: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitOptions {
+    /// Path of each track relative to the destination directory, see
+    /// [`crate::rename::Template`]
+    pub template: String,
+    /// Padding written after the metadata of each track
+    pub padding: u32,
+    /// Copy the pictures of the image to every track
+    pub pictures: bool,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        SplitOptions {
+            template: String::from("{track:02} - {title}.{ext}"),
+            padding: flac::DEFAULT_PADDING,
+            pictures: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitTrack {
+    pub filepath: String,
+    pub track: cue::VirtualTrack,
+}
+
+/// Writes every audio track of the cue sheet to its own FLAC file under `dest_dir`.
+/// The cue sheet is read from `cue_filepath`, or else from the image. Frames that
+/// lie within a track are copied as they are, only the frames a track boundary
+/// falls in are decoded and written again, so the tracks hold exactly the samples
+/// of the image. Existing files are never overwritten.
+pub fn split(
+    image_filepath: &String,
+    cue_filepath: Option<&String>,
+    dest_dir: &String,
+    options: &SplitOptions,
+) -> Result<Vec<SplitTrack>, std::io::Error> {
+    let template = crate::rename::Template::parse(&options.template)?;
+    let cue_file = cue::load_cue(image_filepath, cue_filepath)?;
+
+    let data = std::fs::read(image_filepath)?;
+    let raw = flac::parse(&data)?;
+    let stream_info = match raw.blocks.first() {
+        Some(block) if block.block_type == flac::STREAMINFO => {
+            flac::decode_stream_info(&block.data)?
+        }
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The first metadata block is not STREAMINFO",
+            ));
+        }
+    };
+    let frames = frame::scan(&raw.audio)?;
+    let total_samples = match frames.last() {
+        Some(last) => last.first_sample + u64::from(last.header.block_size),
+        None => 0,
+    };
+    let tracks = cue::tracks_from(&cue_file, stream_info.sample_rate, total_samples)?;
+
+    let (vendor, album_comments) = album_comments(&raw, &cue_file)?;
+    let pictures: Vec<&flac::RawBlock> = match options.pictures {
+        true => raw
+            .blocks
+            .iter()
+            .filter(|block| block.block_type == flac::PICTURE)
+            .collect(),
+        false => Vec::new(),
+    };
+
+    let track_count = tracks.len();
+    let mut split_tracks = Vec::new();
+    for track in tracks {
+        let (audio, track_info) = extract(
+            &raw.audio,
+            &frames,
+            &stream_info,
+            track.start_sample,
+            track.end_sample,
+        )?;
+
+        let cue_track = cue_file
+            .tracks
+            .iter()
+            .find(|cue_track| cue_track.number == track.number);
+        let comments = track_comments(&album_comments, &track, cue_track, track_count);
+
+        let mut vorbis_comments = lofty::ogg::VorbisComments::default();
+        for (key, value) in &comments {
+            vorbis_comments.push(key.clone(), value.clone());
+        }
+        let song_metadata = crate::meta::metadata::song_metadata_from_comments(&vorbis_comments);
+        let filepath =
+            std::path::Path::new(dest_dir).join(template.render(&song_metadata, "flac")?);
+
+        let mut blocks = vec![
+            flac::RawBlock {
+                block_type: flac::STREAMINFO,
+                data: flac::encode_stream_info(&track_info)?,
+            },
+            flac::RawBlock {
+                block_type: flac::VORBIS_COMMENT,
+                data: flac::serialize_vorbis_comment(&vendor, &comments),
+            },
+        ];
+        blocks.extend(pictures.iter().map(|block| (*block).clone()));
+        if options.padding > 0 {
+            blocks.push(flac::RawBlock {
+                block_type: flac::PADDING,
+                data: vec![0; options.padding as usize],
+            });
+        }
+
+        if let Some(parent) = filepath.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_track(&filepath, &flac::serialize(&blocks, &audio)?)?;
+
+        split_tracks.push(SplitTrack {
+            filepath: filepath.display().to_string(),
+            track,
+        });
+    }
+
+    Ok(split_tracks)
+}
+
+/// Writes the track to a temporary file next to it and moves it into place, so an
+/// interrupted split leaves no partial track behind
+fn write_track(filepath: &std::path::Path, data: &[u8]) -> Result<(), std::io::Error> {
+    if filepath.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", filepath.display()),
+        ));
+    }
+
+    let temp_filepath = format!("{}.{}.tmp", filepath.display(), std::process::id());
+    let result = (|| {
+        let mut temp_file = std::fs::File::create(&temp_filepath)?;
+        std::io::Write::write_all(&mut temp_file, data)?;
+        temp_file.sync_all()?;
+        std::fs::rename(&temp_filepath, filepath)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_filepath);
+    }
+    result
+}
+
+/// The vendor string and the album level comments of the image, completed with
+/// the album fields of the cue sheet
+fn album_comments(
+    raw: &flac::RawFlac,
+    cue_file: &cue::CueFile,
+) -> Result<(String, Vec<(String, String)>), std::io::Error> {
+    let (vendor, mut comments) = match raw
+        .blocks
+        .iter()
+        .find(|block| block.block_type == flac::VORBIS_COMMENT)
+    {
+        Some(block) => {
+            let vorbis_comment = flac::decode_vorbis_comment(&block.data)?;
+            (vorbis_comment.vendor, vorbis_comment.comments)
+        }
+        None => (String::new(), Vec::new()),
+    };
+    comments.retain(|(key, _)| !TRACK_KEYS.contains(&key.to_uppercase().as_str()));
+
+    let mut missing = Vec::new();
+    if let Some(title) = &cue_file.title {
+        missing.push(("ALBUM", title));
+    }
+    if let Some(performer) = &cue_file.performer {
+        missing.push(("ALBUMARTIST", performer));
+        missing.push(("ARTIST", performer));
+    }
+    for (key, value) in &cue_file.remarks {
+        if key == "GENRE" || key == "DATE" {
+            missing.push((key.as_str(), value));
+        }
+    }
+    for (key, value) in missing {
+        if !comments.iter().any(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            comments.push((String::from(key), value.clone()));
+        }
+    }
+
+    Ok((vendor, comments))
+}
+
+fn track_comments(
+    album_comments: &[(String, String)],
+    track: &cue::VirtualTrack,
+    cue_track: Option<&cue::CueTrack>,
+    track_count: usize,
+) -> Vec<(String, String)> {
+    let mut comments: Vec<(String, String)> = album_comments
+        .iter()
+        .filter(|(key, _)| track.artist.is_none() || !key.eq_ignore_ascii_case("ARTIST"))
+        .cloned()
+        .collect();
+
+    if let Some(title) = &track.title {
+        comments.push((String::from("TITLE"), title.clone()));
+    }
+    if let Some(artist) = &track.artist {
+        comments.push((String::from("ARTIST"), artist.clone()));
+    }
+    comments.push((String::from("TRACKNUMBER"), track.number.to_string()));
+    comments.push((String::from("TRACKCOUNT"), track_count.to_string()));
+    if let Some(isrc) = cue_track.and_then(|cue_track| cue_track.isrc.as_ref()) {
+        comments.push((String::from("ISRC"), isrc.clone()));
+    }
+
+    comments
+}
+
+/// Part of a track before it is numbered
+enum Piece<'a> {
+    Copy(&'a frame::Frame),
+    Samples(Vec<Vec<i64>>),
+}
+
+/// The frames holding samples `start..end` of the audio, numbered from the start of
+/// the track, and the STREAMINFO describing them. The MD5 signature is left unset.
+fn extract(
+    audio: &[u8],
+    frames: &[frame::Frame],
+    stream_info: &flac::StreamInfo,
+    start: u64,
+    end: u64,
+) -> Result<(Vec<u8>, flac::StreamInfo), std::io::Error> {
+    let frame_data = |frame: &frame::Frame| &audio[frame.offset..frame.offset + frame.length];
+
+    let mut pieces = Vec::new();
+    for frame in frames {
+        let frame_start = frame.first_sample;
+        let frame_end = frame_start + u64::from(frame.header.block_size);
+        if frame_end <= start || frame_start >= end {
+            continue;
+        }
+        if frame_start >= start && frame_end <= end {
+            pieces.push(Piece::Copy(frame));
+            continue;
+        }
+
+        let from = (start.max(frame_start) - frame_start) as usize;
+        let to = (end.min(frame_end) - frame_start) as usize;
+        let channels = frame::decode(frame_data(frame), stream_info)?
+            .into_iter()
+            .map(|channel| channel[from..to].to_vec())
+            .collect();
+        pieces.push(Piece::Samples(channels));
+    }
+
+    // A short head would make a frame below the minimum block size, so it takes
+    // the samples of the piece after it
+    while let [Piece::Samples(head), next, ..] = pieces.as_slice()
+        && head[0].len() < MIN_BLOCK_SIZE
+    {
+        let next = match next {
+            Piece::Copy(frame) => frame::decode(frame_data(frame), stream_info)?,
+            Piece::Samples(channels) => channels.clone(),
+        };
+        let mut merged = head.clone();
+        for (channel, samples) in merged.iter_mut().zip(next) {
+            channel.extend(samples);
+        }
+        pieces.splice(0..2, [Piece::Samples(merged)]);
+    }
+
+    let max_block_size = usize::from(stream_info.max_block_size).max(MIN_BLOCK_SIZE);
+    let mut data = Vec::new();
+    let mut block_sizes = Vec::new();
+    let mut frame_sizes = Vec::new();
+    let mut position = 0;
+    for piece in pieces {
+        match piece {
+            Piece::Copy(frame) => {
+                let renumbered = frame::renumber(frame_data(frame), &frame.header, position);
+                block_sizes.push(frame.header.block_size);
+                frame_sizes.push(renumbered.len() as u32);
+                position += u64::from(frame.header.block_size);
+                data.extend(renumbered);
+            }
+            Piece::Samples(channels) => {
+                let length = channels[0].len();
+                let count = length.div_ceil(max_block_size);
+                let mut from = 0;
+                for i in 0..count {
+                    let to = from + length / count + usize::from(i < length % count);
+                    let chunk: Vec<Vec<i64>> = channels
+                        .iter()
+                        .map(|channel| channel[from..to].to_vec())
+                        .collect();
+                    let encoded = frame::encode_verbatim(&chunk, position, stream_info)?;
+                    block_sizes.push((to - from) as u32);
+                    frame_sizes.push(encoded.len() as u32);
+                    position += (to - from) as u64;
+                    data.extend(encoded);
+                    from = to;
+                }
+            }
+        }
+    }
+
+    // The last frame may be shorter than the minimum block size, which is why a
+    // track of a single short frame still declares the minimum
+    let min_block_size = match block_sizes.split_last() {
+        Some((_, [])) | None => MIN_BLOCK_SIZE as u32,
+        Some((_, rest)) => rest.iter().copied().min().unwrap_or(0),
+    };
+    let max_block_size = block_sizes
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(min_block_size);
+    let track_info = flac::StreamInfo {
+        min_block_size: min_block_size as u16,
+        max_block_size: max_block_size as u16,
+        min_frame_size: frame_sizes.iter().copied().min().unwrap_or(0),
+        max_frame_size: frame_sizes.iter().copied().max().unwrap_or(0),
+        total_samples: end - start,
+        md5: [0; 16],
+        ..stream_info.clone()
+    };
+
+    Ok((data, track_info))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+
+    const ALBUM_CUE: &str = "REM GENRE Metal
+PERFORMER \"KD\"
+TITLE \"Sample Tracks 3\"
+FILE \"track01.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"Just roll it\"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE \"Here we go!\"
+    PERFORMER \"KD & Friends\"
+    ISRC USABC2500002
+    INDEX 01 00:10:00
+  TRACK 03 AUDIO
+    TITLE \"Closer\"
+    INDEX 00 00:24:00
+    INDEX 01 00:25:37
+";
+
+    fn samples(filepath: &String) -> Vec<Vec<i64>> {
+        let data = util::get_data_from_file(filepath).unwrap();
+        let raw = crate::flac::parse(&data).unwrap();
+        let stream_info = crate::flac::read_stream_info(filepath).unwrap();
+
+        let mut channels = vec![Vec::new(); usize::from(stream_info.channels)];
+        for frame in crate::frame::scan(&raw.audio).unwrap() {
+            let frame_data = &raw.audio[frame.offset..frame.offset + frame.length];
+            for (channel, decoded) in channels
+                .iter_mut()
+                .zip(crate::frame::decode(frame_data, &stream_info).unwrap())
+            {
+                channel.extend(decoded);
+            }
+        }
+        channels
+    }
+
+    #[test]
+    fn test_split() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let image = util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let cue_filepath = util::get_full_path(&test_dir, "album.cue").unwrap();
+        std::fs::write(&cue_filepath, ALBUM_CUE).unwrap();
+        let dest_dir = util::get_full_path(&test_dir, "tracks").unwrap();
+
+        let options = super::SplitOptions::default();
+        let split_tracks = super::split(&image, Some(&cue_filepath), &dest_dir, &options).unwrap();
+        assert_eq!(3, split_tracks.len());
+        assert_eq!(
+            util::get_full_path(&dest_dir, "02 - Here we go!.flac").unwrap(),
+            split_tracks[1].filepath
+        );
+
+        // Together the tracks hold exactly the samples of the image
+        let mut joined = vec![Vec::new(); 2];
+        for split_track in &split_tracks {
+            let stream_info = crate::flac::read_stream_info(&split_track.filepath).unwrap();
+            assert_eq!(
+                split_track.track.end_sample - split_track.track.start_sample,
+                stream_info.total_samples
+            );
+            assert!(stream_info.min_block_size >= 16);
+            let track_samples = samples(&split_track.filepath);
+            assert_eq!(stream_info.total_samples, track_samples[0].len() as u64);
+            for (channel, decoded) in joined.iter_mut().zip(track_samples) {
+                channel.extend(decoded);
+            }
+        }
+        assert_eq!(samples(&image), joined);
+
+        let song_metadata = crate::meta::metadata::get_all_meta(&split_tracks[1].filepath).unwrap();
+        assert_eq!(Some(String::from("Here we go!")), song_metadata.title);
+        assert_eq!(Some(String::from("KD & Friends")), song_metadata.artist);
+        assert_eq!(Some(String::from("Sample Tracks 3")), song_metadata.album);
+        assert_eq!(Some(2), song_metadata.track);
+        assert_eq!(
+            vec![String::from("USABC2500002")],
+            crate::meta::metadata::get_comments(&split_tracks[1].filepath, "ISRC").unwrap()
+        );
+        assert_eq!(
+            crate::meta::coverart::get_coverart(&image).unwrap(),
+            crate::meta::coverart::get_coverart(&split_tracks[1].filepath).unwrap()
+        );
+        assert_eq!(
+            std::time::Duration::from_secs(10),
+            crate::properties::get_duration(&split_tracks[0].filepath).unwrap()
+        );
+
+        // Splitting again does not overwrite the tracks
+        assert!(super::split(&image, Some(&cue_filepath), &dest_dir, &options).is_err());
+    }
+
+    #[test]
+    fn test_extract_short_track() {
+        let image = util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let data = util::get_data_from_file(&image).unwrap();
+        let raw = crate::flac::parse(&data).unwrap();
+        let stream_info = crate::flac::read_stream_info(&image).unwrap();
+        let frames = crate::frame::scan(&raw.audio).unwrap();
+        let image_samples = samples(&image);
+
+        // Tracks within a frame and across the boundary of two frames, some of them
+        // shorter than the minimum block size
+        let boundary = frames[3].first_sample;
+        let cases = [
+            (boundary + 100, boundary + 110),
+            (boundary - 5, boundary + 5),
+            (boundary - 5, boundary + 20),
+            (boundary - 20, boundary + 5),
+        ];
+        for (start, end) in cases {
+            let (audio, track_info) =
+                super::extract(&raw.audio, &frames, &stream_info, start, end).unwrap();
+            let encoded = crate::flac::encode_stream_info(&track_info).unwrap();
+            assert_eq!(
+                track_info,
+                crate::flac::decode_stream_info(&encoded).unwrap()
+            );
+            assert_eq!(end - start, track_info.total_samples);
+
+            let mut channels = vec![Vec::new(); usize::from(track_info.channels)];
+            for frame in crate::frame::scan(&audio).unwrap() {
+                let frame_data = &audio[frame.offset..frame.offset + frame.length];
+                for (channel, decoded) in channels
+                    .iter_mut()
+                    .zip(crate::frame::decode(frame_data, &track_info).unwrap())
+                {
+                    channel.extend(decoded);
+                }
+            }
+            let expected: Vec<Vec<i64>> = image_samples
+                .iter()
+                .map(|channel| channel[start as usize..end as usize].to_vec())
+                .collect();
+            assert_eq!(expected, channels, "{start}..{end}");
+        }
+    }
+}
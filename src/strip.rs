@@ -0,0 +1,231 @@
+//! Remove metadata from songs before they are shared, leaving the audio untouched
+
+use crate::flac;
+
+/// What survives stripping. STREAMINFO, SEEKTABLE and CUESHEET blocks describe the
+/// audio and are always kept. Tags, pictures, APPLICATION blocks, padding and any
+/// ID3v2 tag in front of the stream are removed unless the policy keeps them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StripPolicy {
+    KeepNothing,
+    /// Keep these fields, under their primary and fallback keys. The vendor string
+    /// is cleared.
+    KeepFields(Vec<crate::types::Type>),
+    /// Keep the first front cover picture and no tags
+    KeepFrontCover,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StripReport {
+    pub removed_blocks: usize,
+    /// Bytes the file shrunk by
+    pub removed_bytes: usize,
+    /// Comments kept as `KEY=value`
+    pub kept_comments: Vec<String>,
+    pub kept_pictures: usize,
+}
+
+/// Rewrites the metadata section of the song according to the policy. The song is
+/// written to a temporary file whose audio frames are compared with the original,
+/// and only replaces the original if they are the same.
+pub fn strip(filepath: &String, policy: &StripPolicy) -> Result<StripReport, std::io::Error> {
+    let original = std::fs::read(filepath)?;
+    let raw = flac::parse(&original)?;
+
+    let mut report = StripReport::default();
+    let mut blocks = Vec::new();
+    for block in raw.blocks {
+        match block.block_type {
+            flac::STREAMINFO | flac::SEEKTABLE | flac::CUESHEET => blocks.push(block),
+            flac::VORBIS_COMMENT => match policy {
+                StripPolicy::KeepFields(types) => {
+                    let comments = flac::decode_vorbis_comment(&block.data)?.comments;
+                    let keys = kept_keys(types)?;
+                    let kept: Vec<(String, String)> = comments
+                        .into_iter()
+                        .filter(|(key, _)| keys.iter().any(|k| k.eq_ignore_ascii_case(key)))
+                        .collect();
+                    report.kept_comments = kept
+                        .iter()
+                        .map(|(key, value)| format!("{key}={value}"))
+                        .collect();
+                    blocks.push(flac::RawBlock {
+                        block_type: flac::VORBIS_COMMENT,
+                        data: flac::serialize_vorbis_comment("", &kept),
+                    });
+                }
+                _ => report.removed_blocks += 1,
+            },
+            flac::PICTURE
+                if *policy == StripPolicy::KeepFrontCover
+                    && report.kept_pictures == 0
+                    && flac::picture_type(&block.data) == Some(3) =>
+            {
+                report.kept_pictures += 1;
+                blocks.push(block);
+            }
+            _ => report.removed_blocks += 1,
+        }
+    }
+
+    let stripped = flac::serialize(&blocks, &raw.audio)?;
+    report.removed_bytes = original.len().saturating_sub(stripped.len());
+
+    // The original is only replaced once the written copy is known to hold the
+    // same audio frames
+    let temp_filepath = format!("{filepath}.{}.tmp", std::process::id());
+    let result = (|| {
+        std::fs::write(&temp_filepath, &stripped)?;
+        if !verify_audio_data(&original, &std::fs::read(&temp_filepath)?)? {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Audio frames changed while stripping, the original was kept",
+            ));
+        }
+        std::fs::set_permissions(&temp_filepath, std::fs::metadata(filepath)?.permissions())?;
+        std::fs::File::open(&temp_filepath)?.sync_all()?;
+        std::fs::rename(&temp_filepath, filepath)
+    })();
+
+    match result {
+        Ok(()) => Ok(report),
+        Err(err) => {
+            let _ = std::fs::remove_file(&temp_filepath);
+            Err(err)
+        }
+    }
+}
+
+fn kept_keys(types: &[crate::types::Type]) -> Result<Vec<String>, std::io::Error> {
+    let mut keys = Vec::new();
+    for t in types {
+        keys.push(crate::types::access::get_type(*t)?);
+        keys.extend(crate::types::access::get_fallback_types(*t));
+    }
+    Ok(keys)
+}
+
+/// Whether the two songs have byte for byte the same audio frames
+pub fn verify_audio(a: &String, b: &String) -> Result<bool, std::io::Error> {
+    verify_audio_data(&std::fs::read(a)?, &std::fs::read(b)?)
+}
+
+fn verify_audio_data(a: &[u8], b: &[u8]) -> Result<bool, std::io::Error> {
+    Ok(flac::parse(a)?.audio == flac::parse(b)?.audio)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+    use crate::types;
+
+    #[test]
+    fn test_strip_everything() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        let report = super::strip(&new_filepath, &super::StripPolicy::KeepNothing).unwrap();
+        assert_eq!(2, report.removed_blocks);
+        assert!(report.kept_comments.is_empty());
+        assert_eq!(0, report.kept_pictures);
+        assert_eq!(
+            util::get_data_from_file(&filepath).unwrap().len() - report.removed_bytes,
+            util::get_data_from_file(&new_filepath).unwrap().len()
+        );
+
+        assert!(super::verify_audio(&filepath, &new_filepath).unwrap());
+        let song_metadata = crate::meta::metadata::get_all_meta(&new_filepath).unwrap();
+        assert_eq!(None, song_metadata.title);
+        assert!(song_metadata.pictures.is_empty());
+        assert_eq!(
+            crate::properties::get_duration(&filepath).unwrap(),
+            crate::properties::get_duration(&new_filepath).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_strip_keep_fields() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        let policy = super::StripPolicy::KeepFields(vec![types::Type::Title, types::Type::Track]);
+        let report = super::strip(&new_filepath, &policy).unwrap();
+        assert_eq!(
+            vec![
+                String::from("TITLE=Just roll it"),
+                String::from("TRACKNUMBER=1")
+            ],
+            report.kept_comments
+        );
+        assert_eq!(1, report.removed_blocks);
+
+        assert!(super::verify_audio(&filepath, &new_filepath).unwrap());
+        let song_metadata = crate::meta::metadata::get_all_meta(&new_filepath).unwrap();
+        assert_eq!(Some(String::from("Just roll it")), song_metadata.title);
+        assert_eq!(Some(1), song_metadata.track);
+        assert_eq!(None, song_metadata.artist);
+        assert!(song_metadata.pictures.is_empty());
+    }
+
+    #[test]
+    fn test_strip_keep_fields_any_case() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let new_filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        let mut raw =
+            crate::flac::parse(&util::get_data_from_file(&new_filepath).unwrap()).unwrap();
+        for block in raw.blocks.iter_mut() {
+            if block.block_type == crate::flac::VORBIS_COMMENT {
+                block.data = crate::flac::serialize_vorbis_comment(
+                    "",
+                    &[
+                        (String::from("title"), String::from("Just roll it")),
+                        (String::from("Artist"), String::from("KD")),
+                    ],
+                );
+            }
+        }
+        let data = crate::flac::serialize(&raw.blocks, &raw.audio).unwrap();
+        util::save_bytes_to_file(&data, &new_filepath).unwrap();
+
+        let policy = super::StripPolicy::KeepFields(vec![types::Type::Title]);
+        let report = super::strip(&new_filepath, &policy).unwrap();
+        assert_eq!(
+            vec![String::from("TITLE=Just roll it")],
+            report.kept_comments
+        );
+        assert_eq!(
+            Some(String::from("Just roll it")),
+            crate::meta::metadata::get_all_meta(&new_filepath)
+                .unwrap()
+                .title
+        );
+    }
+
+    #[test]
+    fn test_strip_keep_front_cover() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let filepath =
+            util::get_full_path(util::TESTFILEDIRECTORY, &util::get_filename(1)).unwrap();
+        let new_filepath = util::copy_track(&test_dir, &util::get_filename(1));
+
+        let report = super::strip(&new_filepath, &super::StripPolicy::KeepFrontCover).unwrap();
+        assert_eq!(1, report.kept_pictures);
+
+        assert!(super::verify_audio(&filepath, &new_filepath).unwrap());
+        let song_metadata = crate::meta::metadata::get_all_meta(&new_filepath).unwrap();
+        assert_eq!(None, song_metadata.title);
+        assert_eq!(
+            crate::meta::coverart::get_coverart(&filepath).unwrap(),
+            crate::meta::coverart::get_coverart(&new_filepath).unwrap()
+        );
+    }
+}
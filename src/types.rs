@@ -1,4 +1,9 @@
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Type {
     Title,
     Artist,
@@ -10,12 +15,31 @@ pub enum Type {
     Disc,
     TrackCount,
     DiscCount,
+    #[cfg_attr(feature = "serde", serde(rename = "musicbrainz_track_id"))]
+    MusicBrainzTrackId,
+    #[cfg_attr(feature = "serde", serde(rename = "musicbrainz_album_id"))]
+    MusicBrainzAlbumId,
+    #[cfg_attr(feature = "serde", serde(rename = "musicbrainz_artist_id"))]
+    MusicBrainzArtistId,
+    #[cfg_attr(feature = "serde", serde(rename = "musicbrainz_album_artist_id"))]
+    MusicBrainzAlbumArtistId,
+    #[cfg_attr(feature = "serde", serde(rename = "musicbrainz_release_group_id"))]
+    MusicBrainzReleaseGroupId,
+    #[cfg_attr(feature = "serde", serde(rename = "acoustid_id"))]
+    AcoustId,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
 pub enum MetadataType {
     String(String),
     Int(i32),
+    Uuid(uuid::Uuid),
+    Date(PartialDate),
 }
 
 impl MetadataType {
@@ -30,6 +54,500 @@ impl MetadataType {
     pub fn from_int(i: i32) -> Self {
         MetadataType::Int(i)
     }
+
+    pub fn from_uuid(id: uuid::Uuid) -> Self {
+        MetadataType::Uuid(id)
+    }
+
+    pub fn from_date(date: PartialDate) -> Self {
+        MetadataType::Date(date)
+    }
+
+    /// Converts a raw tag value into the MetadataType held by the Type
+    pub fn parse(t: Type, value: &str) -> Result<Self, std::io::Error> {
+        match t {
+            Type::Title | Type::Artist | Type::Album | Type::AlbumArtist | Type::Genre => {
+                Ok(MetadataType::String(value.to_owned()))
+            }
+            Type::Date => match PartialDate::parse(value) {
+                Ok(date) => Ok(MetadataType::Date(date)),
+                Err(err) => Err(err),
+            },
+            Type::Track | Type::Disc | Type::TrackCount | Type::DiscCount => {
+                match parse_number(t, value) {
+                    Ok(number) => Ok(MetadataType::Int(number)),
+                    Err(err) => Err(err),
+                }
+            }
+            Type::MusicBrainzTrackId
+            | Type::MusicBrainzAlbumId
+            | Type::MusicBrainzArtistId
+            | Type::MusicBrainzAlbumArtistId
+            | Type::MusicBrainzReleaseGroupId
+            | Type::AcoustId => match identifier::parse(value) {
+                Ok(id) => Ok(MetadataType::Uuid(id)),
+                Err(err) => Err(err),
+            },
+        }
+    }
+}
+
+/// Formats the value the way it is stored in a tag
+impl std::fmt::Display for MetadataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataType::String(val) => write!(f, "{val}"),
+            MetadataType::Int(val) => write!(f, "{val}"),
+            MetadataType::Uuid(val) => write!(f, "{}", val.hyphenated()),
+            MetadataType::Date(val) => write!(f, "{val}"),
+        }
+    }
+}
+
+/// The total of a track or disc number written as "3/12", with the Type it is
+/// stored as. Numbers without a total give `None`.
+pub fn parse_total(t: Type, value: &str) -> Result<Option<(Type, i32)>, std::io::Error> {
+    let count_type = match t {
+        Type::Track => Type::TrackCount,
+        Type::Disc => Type::DiscCount,
+        _ => return Ok(None),
+    };
+
+    match value.split_once('/') {
+        Some((_number, total)) if !total.trim().is_empty() => {
+            Ok(Some((count_type, parse_number(count_type, total)?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Parses a track or disc number. Track and disc numbers written as "3/12" are
+/// accepted and yield the number before the slash; see `parse_total` for the rest.
+fn parse_number(t: Type, value: &str) -> Result<i32, std::io::Error> {
+    let value = value.trim();
+    let number = match t {
+        Type::Track | Type::Disc => match value.split_once('/') {
+            Some((number, _total)) => {
+                parse_total(t, value)?;
+                number.trim()
+            }
+            None => value,
+        },
+        _ => value,
+    };
+
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Expected a non-negative number for {t:?}, got {value:?}"),
+        ));
+    }
+
+    match number.parse::<i32>() {
+        Ok(number) => Ok(number),
+        Err(err) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Number out of range for {t:?}: {err}"),
+        )),
+    }
+}
+
+/// Ties a Type to the Rust type its value is read and written as
+pub trait Field {
+    const TYPE: Type;
+    type Value;
+
+    fn parse(value: &str) -> Result<Self::Value, std::io::Error>;
+    fn format(value: &Self::Value) -> String;
+}
+
+/// Marker types for the typed getter and setter in `meta::metadata`
+pub mod field {
+    use super::{Field, MetadataType, PartialDate, Type};
+
+    macro_rules! text_field {
+        ($($name:ident),*) => {$(
+            pub struct $name;
+
+            impl Field for $name {
+                const TYPE: Type = Type::$name;
+                type Value = String;
+
+                fn parse(value: &str) -> Result<Self::Value, std::io::Error> {
+                    Ok(value.to_owned())
+                }
+
+                fn format(value: &Self::Value) -> String {
+                    value.clone()
+                }
+            }
+        )*};
+    }
+
+    macro_rules! number_field {
+        ($($name:ident),*) => {$(
+            pub struct $name;
+
+            impl Field for $name {
+                const TYPE: Type = Type::$name;
+                type Value = u32;
+
+                fn parse(value: &str) -> Result<Self::Value, std::io::Error> {
+                    match MetadataType::parse(Self::TYPE, value) {
+                        Ok(MetadataType::Int(number)) => Ok(number as u32),
+                        Ok(other) => Err(mismatch(Self::TYPE, &other)),
+                        Err(err) => Err(err),
+                    }
+                }
+
+                fn format(value: &Self::Value) -> String {
+                    value.to_string()
+                }
+            }
+        )*};
+    }
+
+    macro_rules! identifier_field {
+        ($($name:ident),*) => {$(
+            pub struct $name;
+
+            impl Field for $name {
+                const TYPE: Type = Type::$name;
+                type Value = uuid::Uuid;
+
+                fn parse(value: &str) -> Result<Self::Value, std::io::Error> {
+                    super::identifier::parse(value)
+                }
+
+                fn format(value: &Self::Value) -> String {
+                    value.hyphenated().to_string()
+                }
+            }
+        )*};
+    }
+
+    text_field!(Title, Artist, Album, AlbumArtist, Genre);
+    number_field!(Track, Disc, TrackCount, DiscCount);
+    identifier_field!(
+        MusicBrainzTrackId,
+        MusicBrainzAlbumId,
+        MusicBrainzArtistId,
+        MusicBrainzAlbumArtistId,
+        MusicBrainzReleaseGroupId,
+        AcoustId
+    );
+
+    pub struct Date;
+
+    impl Field for Date {
+        const TYPE: Type = Type::Date;
+        type Value = PartialDate;
+
+        fn parse(value: &str) -> Result<Self::Value, std::io::Error> {
+            PartialDate::parse(value)
+        }
+
+        fn format(value: &Self::Value) -> String {
+            value.to_string()
+        }
+    }
+
+    fn mismatch(t: Type, value: &MetadataType) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{t:?} does not hold {value:?}"),
+        )
+    }
+}
+
+/// A date that may only be known to the year or month, as found in DATE tags
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PartialDate {
+    year: u16,
+    month: Option<u8>,
+    day: Option<u8>,
+}
+
+impl PartialDate {
+    pub fn new(year: u16, month: Option<u8>, day: Option<u8>) -> Result<Self, std::io::Error> {
+        if year > 9999 {
+            return Err(invalid_date(&format!("year {year} is out of range")));
+        }
+
+        match (month, day) {
+            (None, Some(_)) => Err(invalid_date("a day requires a month")),
+            (Some(m), _) if !(1..=12).contains(&m) => {
+                Err(invalid_date(&format!("month {m} is out of range")))
+            }
+            (Some(m), Some(d)) if d == 0 || d > days_in_month(year, m) => {
+                Err(invalid_date(&format!("day {d} is out of range")))
+            }
+            _ => Ok(PartialDate { year, month, day }),
+        }
+    }
+
+    /// Parses "YYYY", "YYYY-MM", "YYYY-MM-DD" and "YYYY-MM-DDTHH:MM:SS". Any time
+    /// component is validated loosely and discarded.
+    pub fn parse(value: &str) -> Result<Self, std::io::Error> {
+        let value = value.trim();
+        let date = match value.find(['T', ' ']) {
+            Some(index) => {
+                let time = &value[index + 1..];
+                if time.is_empty() || !time.starts_with(|c: char| c.is_ascii_digit()) {
+                    return Err(invalid_date(&format!("malformed time in {value:?}")));
+                }
+                &value[..index]
+            }
+            None => value,
+        };
+
+        let parts: Vec<&str> = date.split('-').collect();
+        let widths = [4, 2, 2];
+        if parts.len() > widths.len() {
+            return Err(invalid_date(&format!("malformed date {value:?}")));
+        }
+
+        let mut numbers: Vec<u16> = Vec::new();
+        for (part, width) in parts.iter().zip(widths) {
+            if part.len() != width || !part.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid_date(&format!("malformed date {value:?}")));
+            }
+            match part.parse::<u16>() {
+                Ok(number) => numbers.push(number),
+                Err(err) => return Err(invalid_date(&err.to_string())),
+            }
+        }
+
+        PartialDate::new(
+            numbers[0],
+            numbers.get(1).map(|m| *m as u8),
+            numbers.get(2).map(|d| *d as u8),
+        )
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> Option<u8> {
+        self.month
+    }
+
+    pub fn day(&self) -> Option<u8> {
+        self.day
+    }
+}
+
+impl std::str::FromStr for PartialDate {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PartialDate::parse(s)
+    }
+}
+
+/// Formats the date as ISO 8601 with only the known components
+impl std::fmt::Display for PartialDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}", self.year)?;
+        if let Some(month) = self.month {
+            write!(f, "-{month:02}")?;
+        }
+        if let Some(day) = self.day {
+            write!(f, "-{day:02}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PartialDate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PartialDate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        PartialDate::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn invalid_date(reason: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid date: {reason}"),
+    )
+}
+
+/// Every tag of a song. Values of the known Types are typed, any other Vorbis
+/// comment is kept in `custom` under its upper-cased key.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct SongMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<PartialDate>,
+    pub track: Option<u32>,
+    pub disc: Option<u32>,
+    pub track_count: Option<u32>,
+    pub disc_count: Option<u32>,
+    pub musicbrainz_track_id: Option<uuid::Uuid>,
+    pub musicbrainz_album_id: Option<uuid::Uuid>,
+    pub musicbrainz_artist_id: Option<uuid::Uuid>,
+    pub musicbrainz_album_artist_id: Option<uuid::Uuid>,
+    pub musicbrainz_release_group_id: Option<uuid::Uuid>,
+    pub acoustid_id: Option<uuid::Uuid>,
+    pub custom: std::collections::BTreeMap<String, Vec<String>>,
+    pub pictures: Vec<PictureInfo>,
+}
+
+/// Describes an embedded picture without carrying its data
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct PictureInfo {
+    /// FLAC/ID3v2 picture type, 3 being the front cover
+    pub picture_type: u8,
+    pub mime: String,
+    pub description: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub color_depth: u32,
+    pub size: usize,
+    /// Hex encoded SHA-256 of the picture data
+    pub hash: String,
+    /// Image file to read the picture from when writing. Without it, a picture
+    /// with the same hash has to already be embedded in the song.
+    pub path: Option<String>,
+}
+
+impl SongMetadata {
+    pub fn get(&self, t: Type) -> Option<MetadataType> {
+        match t {
+            Type::Title => self.title.clone().map(MetadataType::String),
+            Type::Artist => self.artist.clone().map(MetadataType::String),
+            Type::Album => self.album.clone().map(MetadataType::String),
+            Type::AlbumArtist => self.album_artist.clone().map(MetadataType::String),
+            Type::Genre => self.genre.clone().map(MetadataType::String),
+            Type::Date => self.date.map(MetadataType::Date),
+            Type::Track => self.track.map(|n| MetadataType::Int(n as i32)),
+            Type::Disc => self.disc.map(|n| MetadataType::Int(n as i32)),
+            Type::TrackCount => self.track_count.map(|n| MetadataType::Int(n as i32)),
+            Type::DiscCount => self.disc_count.map(|n| MetadataType::Int(n as i32)),
+            Type::MusicBrainzTrackId => self.musicbrainz_track_id.map(MetadataType::Uuid),
+            Type::MusicBrainzAlbumId => self.musicbrainz_album_id.map(MetadataType::Uuid),
+            Type::MusicBrainzArtistId => self.musicbrainz_artist_id.map(MetadataType::Uuid),
+            Type::MusicBrainzAlbumArtistId => {
+                self.musicbrainz_album_artist_id.map(MetadataType::Uuid)
+            }
+            Type::MusicBrainzReleaseGroupId => {
+                self.musicbrainz_release_group_id.map(MetadataType::Uuid)
+            }
+            Type::AcoustId => self.acoustid_id.map(MetadataType::Uuid),
+        }
+    }
+
+    /// Sets the field of a Type from a raw tag value, validating it the same way
+    /// `meta::metadata::set_meta` does
+    pub fn set(&mut self, t: Type, value: &str) -> Result<(), std::io::Error> {
+        match MetadataType::parse(t, value) {
+            Ok(parsed) => {
+                match (t, parsed) {
+                    (Type::Title, MetadataType::String(val)) => self.title = Some(val),
+                    (Type::Artist, MetadataType::String(val)) => self.artist = Some(val),
+                    (Type::Album, MetadataType::String(val)) => self.album = Some(val),
+                    (Type::AlbumArtist, MetadataType::String(val)) => self.album_artist = Some(val),
+                    (Type::Genre, MetadataType::String(val)) => self.genre = Some(val),
+                    (Type::Date, MetadataType::Date(val)) => self.date = Some(val),
+                    (Type::Track, MetadataType::Int(val)) => {
+                        self.track = Some(val as u32);
+                        if let Ok(Some((_, total))) = parse_total(t, value) {
+                            self.track_count = Some(total as u32);
+                        }
+                    }
+                    (Type::Disc, MetadataType::Int(val)) => {
+                        self.disc = Some(val as u32);
+                        if let Ok(Some((_, total))) = parse_total(t, value) {
+                            self.disc_count = Some(total as u32);
+                        }
+                    }
+                    (Type::TrackCount, MetadataType::Int(val)) => {
+                        self.track_count = Some(val as u32)
+                    }
+                    (Type::DiscCount, MetadataType::Int(val)) => self.disc_count = Some(val as u32),
+                    (Type::MusicBrainzTrackId, MetadataType::Uuid(val)) => {
+                        self.musicbrainz_track_id = Some(val)
+                    }
+                    (Type::MusicBrainzAlbumId, MetadataType::Uuid(val)) => {
+                        self.musicbrainz_album_id = Some(val)
+                    }
+                    (Type::MusicBrainzArtistId, MetadataType::Uuid(val)) => {
+                        self.musicbrainz_artist_id = Some(val)
+                    }
+                    (Type::MusicBrainzAlbumArtistId, MetadataType::Uuid(val)) => {
+                        self.musicbrainz_album_artist_id = Some(val)
+                    }
+                    (Type::MusicBrainzReleaseGroupId, MetadataType::Uuid(val)) => {
+                        self.musicbrainz_release_group_id = Some(val)
+                    }
+                    (Type::AcoustId, MetadataType::Uuid(val)) => self.acoustid_id = Some(val),
+                    (t, parsed) => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("{t:?} does not hold {parsed:?}"),
+                        ));
+                    }
+                };
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn clear(&mut self, t: Type) {
+        match t {
+            Type::Title => self.title = None,
+            Type::Artist => self.artist = None,
+            Type::Album => self.album = None,
+            Type::AlbumArtist => self.album_artist = None,
+            Type::Genre => self.genre = None,
+            Type::Date => self.date = None,
+            Type::Track => self.track = None,
+            Type::Disc => self.disc = None,
+            Type::TrackCount => self.track_count = None,
+            Type::DiscCount => self.disc_count = None,
+            Type::MusicBrainzTrackId => self.musicbrainz_track_id = None,
+            Type::MusicBrainzAlbumId => self.musicbrainz_album_id = None,
+            Type::MusicBrainzArtistId => self.musicbrainz_artist_id = None,
+            Type::MusicBrainzAlbumArtistId => self.musicbrainz_album_artist_id = None,
+            Type::MusicBrainzReleaseGroupId => self.musicbrainz_release_group_id = None,
+            Type::AcoustId => self.acoustid_id = None,
+        }
+    }
 }
 
 pub fn all_metadata_types() -> Vec<Type> {
@@ -44,6 +562,12 @@ pub fn all_metadata_types() -> Vec<Type> {
         Type::Track,
         Type::TrackCount,
         Type::DiscCount,
+        Type::MusicBrainzTrackId,
+        Type::MusicBrainzAlbumId,
+        Type::MusicBrainzArtistId,
+        Type::MusicBrainzAlbumArtistId,
+        Type::MusicBrainzReleaseGroupId,
+        Type::AcoustId,
     ]
 }
 
@@ -60,6 +584,220 @@ pub mod access {
             super::Type::Disc => Ok("DISCNUMBER".to_owned()),
             super::Type::TrackCount => Ok("TRACKCOUNT".to_owned()),
             super::Type::DiscCount => Ok("DISCCOUNT".to_owned()),
+            super::Type::MusicBrainzTrackId => Ok("MUSICBRAINZ_TRACKID".to_owned()),
+            super::Type::MusicBrainzAlbumId => Ok("MUSICBRAINZ_ALBUMID".to_owned()),
+            super::Type::MusicBrainzArtistId => Ok("MUSICBRAINZ_ARTISTID".to_owned()),
+            super::Type::MusicBrainzAlbumArtistId => Ok("MUSICBRAINZ_ALBUMARTISTID".to_owned()),
+            super::Type::MusicBrainzReleaseGroupId => Ok("MUSICBRAINZ_RELEASEGROUPID".to_owned()),
+            super::Type::AcoustId => Ok("ACOUSTID_ID".to_owned()),
+        }
+    }
+
+    /// Gets the Type stored under a Vorbis comment key, ignoring case
+    pub fn get_type_from_str(key: &str) -> Result<super::Type, std::io::Error> {
+        match super::all_metadata_types()
+            .into_iter()
+            .find(|t| get_type(*t).is_ok_and(|type_str| type_str.eq_ignore_ascii_case(key)))
+        {
+            Some(t) => Ok(t),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unknown metadata type: {key:?}"),
+            )),
         }
     }
+
+    /// Keys read, in order, when the primary key of the Type is missing
+    pub fn get_fallback_types(t: super::Type) -> Vec<String> {
+        match t {
+            super::Type::Date => vec![
+                "YEAR".to_owned(),
+                "ORIGINALDATE".to_owned(),
+                "ORIGINALYEAR".to_owned(),
+            ],
+            super::Type::Track => vec!["TRACKNUM".to_owned()],
+            super::Type::TrackCount => vec!["TRACKTOTAL".to_owned(), "TOTALTRACKS".to_owned()],
+            super::Type::DiscCount => vec!["DISCTOTAL".to_owned(), "TOTALDISCS".to_owned()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub mod identifier {
+    /// Whether the Type holds a MusicBrainz or AcoustID identifier
+    pub fn is_identifier(t: &super::Type) -> bool {
+        matches!(
+            t,
+            super::Type::MusicBrainzTrackId
+                | super::Type::MusicBrainzAlbumId
+                | super::Type::MusicBrainzArtistId
+                | super::Type::MusicBrainzAlbumArtistId
+                | super::Type::MusicBrainzReleaseGroupId
+                | super::Type::AcoustId
+        )
+    }
+
+    /// Parses an identifier, rejecting anything that is not a hyphenated UUID
+    pub fn parse(value: &str) -> Result<uuid::Uuid, std::io::Error> {
+        let value = value.trim();
+        if value.len() != uuid::fmt::Hyphenated::LENGTH {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Malformed identifier: {value:?}"),
+            ));
+        }
+
+        match uuid::Uuid::parse_str(value) {
+            Ok(id) => Ok(id),
+            Err(err) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Malformed identifier {value:?}: {err}"),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PartialDate;
+
+    #[test]
+    fn test_parse_partial_dates() {
+        let cases = [
+            ("2019", (2019, None, None), "2019"),
+            ("2019-05", (2019, Some(5), None), "2019-05"),
+            ("2019-05-03", (2019, Some(5), Some(3)), "2019-05-03"),
+            (
+                "2019-05-03T00:00:00",
+                (2019, Some(5), Some(3)),
+                "2019-05-03",
+            ),
+            (" 2020-02-29 ", (2020, Some(2), Some(29)), "2020-02-29"),
+        ];
+
+        for (value, (year, month, day), iso) in cases {
+            match PartialDate::parse(value) {
+                Ok(date) => {
+                    assert_eq!(year, date.year(), "Year does not match for {value:?}");
+                    assert_eq!(month, date.month(), "Month does not match for {value:?}");
+                    assert_eq!(day, date.day(), "Day does not match for {value:?}");
+                    assert_eq!(iso, date.to_string(), "ISO form does not match");
+                }
+                Err(err) => {
+                    panic!("Error parsing {value:?}: {err:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_metadata_types() {
+        use super::{MetadataType, Type};
+
+        assert_eq!(
+            MetadataType::Int(3),
+            MetadataType::parse(Type::Track, "3/12").unwrap()
+        );
+        assert_eq!(
+            MetadataType::Int(12),
+            MetadataType::parse(Type::TrackCount, " 12 ").unwrap()
+        );
+        assert_eq!(
+            MetadataType::String(String::from("Metal")),
+            MetadataType::parse(Type::Genre, "Metal").unwrap()
+        );
+        assert_eq!(
+            MetadataType::Date(PartialDate::new(2019, Some(5), None).unwrap()),
+            MetadataType::parse(Type::Date, "2019-05").unwrap()
+        );
+
+        assert_eq!(
+            Some((Type::TrackCount, 12)),
+            super::parse_total(Type::Track, "3/12").unwrap()
+        );
+        assert_eq!(None, super::parse_total(Type::Disc, "1").unwrap());
+
+        let mut song_metadata = super::SongMetadata::default();
+        song_metadata.set(Type::Disc, "2/3").unwrap();
+        assert_eq!(Some(2), song_metadata.disc);
+        assert_eq!(Some(3), song_metadata.disc_count);
+
+        for (t, value) in [
+            (Type::Track, "one"),
+            (Type::Disc, "-1"),
+            (Type::TrackCount, "3/12"),
+            (Type::Track, "3/twelve"),
+            (Type::DiscCount, "99999999999"),
+            (Type::MusicBrainzTrackId, "not-a-uuid"),
+        ] {
+            match MetadataType::parse(t, value) {
+                Ok(parsed) => panic!("{value:?} should not parse as {t:?}: {parsed:?}"),
+                Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_dates() {
+        let values = [
+            "",
+            "19",
+            "2019-5",
+            "2019-13",
+            "2019-02-29",
+            "2019-05-03T",
+            "May 2019",
+            "2019-05-03-01",
+        ];
+
+        for value in values {
+            assert!(
+                PartialDate::parse(value).is_err(),
+                "{value:?} should not have parsed"
+            );
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::{MetadataType, PartialDate, SongMetadata, Type};
+
+    #[test]
+    fn test_serialize_types() {
+        assert_eq!(
+            "\"album_artist\"",
+            serde_json::to_string(&Type::AlbumArtist).unwrap()
+        );
+        assert_eq!(
+            "\"musicbrainz_release_group_id\"",
+            serde_json::to_string(&Type::MusicBrainzReleaseGroupId).unwrap()
+        );
+
+        let date = MetadataType::Date(PartialDate::parse("2019-05").unwrap());
+        let json = serde_json::to_string(&date).unwrap();
+        assert_eq!("{\"type\":\"date\",\"value\":\"2019-05\"}", json);
+        assert_eq!(date, serde_json::from_str::<MetadataType>(&json).unwrap());
+    }
+
+    #[test]
+    fn test_song_metadata_round_trip() {
+        let mut song_metadata = SongMetadata::default();
+        song_metadata.set(Type::Title, "Just roll it").unwrap();
+        song_metadata.set(Type::Track, "1").unwrap();
+        song_metadata.set(Type::Date, "2025-04-11").unwrap();
+        song_metadata
+            .custom
+            .insert(String::from("ENCODER"), vec![String::from("Lavf61.7.100")]);
+
+        let json = serde_json::to_value(&song_metadata).unwrap();
+        assert_eq!("Just roll it", json["title"]);
+        assert_eq!(1, json["track"]);
+        assert_eq!("2025-04-11", json["date"]);
+        assert!(json["album"].is_null());
+
+        let parsed: SongMetadata = serde_json::from_value(json).unwrap();
+        assert_eq!(song_metadata, parsed);
+        assert!(serde_json::from_str::<SongMetadata>("{\"date\":\"May\"}").is_err());
+    }
 }
@@ -0,0 +1,10 @@
+use sha2::Digest;
+
+/// Hex encoded SHA-256 of the data
+pub fn hash_hex(data: &[u8]) -> String {
+    to_hex(&sha2::Sha256::digest(data))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
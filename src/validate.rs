@@ -0,0 +1,926 @@
+//! Check that the tracks of an album agree with each other
+
+use crate::types;
+
+/// A value shared by some of the tracks of an album
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueGroup {
+    pub value: String,
+    pub paths: Vec<String>,
+}
+
+/// No disc is expected to have more tracks than this, whatever its TRACKCOUNT
+const MAX_TRACK_NUMBER: u32 = 999;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Issue {
+    /// The tracks have different values for the field, most common value first
+    Mismatch {
+        field: types::Type,
+        groups: Vec<ValueGroup>,
+    },
+    /// Some tracks lack a field the others have. Tracks without a track number
+    /// are always reported.
+    Missing {
+        field: types::Type,
+        paths: Vec<String>,
+    },
+    DuplicateTrack {
+        disc: Option<u32>,
+        track: u32,
+        paths: Vec<String>,
+    },
+    /// A track number below the highest one on the disc that no track has
+    MissingTrack {
+        disc: Option<u32>,
+        track: u32,
+    },
+    /// A track number above both the number of tracks on the disc and its
+    /// TRACKCOUNT. It is not counted as the highest track number.
+    TrackOutOfRange {
+        disc: Option<u32>,
+        track: u32,
+        limit: u32,
+        paths: Vec<String>,
+    },
+    /// TRACKCOUNT differs from the highest track number on the disc
+    TrackCountMismatch {
+        disc: Option<u32>,
+        actual: u32,
+        groups: Vec<ValueGroup>,
+    },
+    /// DISCCOUNT differs from the highest disc number, or from 1 when no track
+    /// has a disc number
+    DiscCountMismatch {
+        actual: u32,
+        groups: Vec<ValueGroup>,
+    },
+    MixedSampleRates {
+        groups: Vec<ValueGroup>,
+    },
+    MixedBitDepths {
+        groups: Vec<ValueGroup>,
+    },
+    MissingCoverArt {
+        paths: Vec<String>,
+    },
+    /// The front covers differ, grouped by picture hash
+    DifferentCoverArt {
+        groups: Vec<ValueGroup>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct AlbumTrack {
+    pub path: String,
+    pub metadata: types::SongMetadata,
+    pub properties: crate::properties::SongProperties,
+}
+
+#[derive(Debug)]
+pub struct AlbumReport {
+    pub directory: String,
+    /// Every readable track, ordered by path
+    pub tracks: Vec<AlbumTrack>,
+    pub issues: Vec<Issue>,
+    /// Songs that could not be read, with the reason
+    pub unreadable: Vec<(String, std::io::Error)>,
+}
+
+impl AlbumReport {
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty() && self.unreadable.is_empty()
+    }
+}
+
+/// Reads every song in the directory and its subdirectories and reports where
+/// the tracks disagree
+pub fn album(directory: &str) -> Result<AlbumReport, std::io::Error> {
+    let options = crate::scan::ScanOptions {
+        extensions: vec![String::from(crate::detection::song::constants::FLAC_TYPE)],
+        ..Default::default()
+    };
+
+    let mut tracks = Vec::new();
+    let mut unreadable = Vec::new();
+    for result in crate::scan::scan(directory, &options) {
+        match result.file {
+            Ok(crate::scan::ScannedFile::Song(song)) => tracks.push(AlbumTrack {
+                path: result.path,
+                metadata: song.metadata,
+                properties: song.properties,
+            }),
+            Ok(crate::scan::ScannedFile::Image(_)) => {}
+            // The directory itself could not be read
+            Err(err) if result.path == directory => return Err(err),
+            Err(err) => unreadable.push((result.path, err)),
+        }
+    }
+    tracks.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(AlbumReport {
+        directory: String::from(directory),
+        issues: check(&tracks),
+        tracks,
+        unreadable,
+    })
+}
+
+/// Finds the issues between already read tracks
+pub fn check(tracks: &[AlbumTrack]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    for t in [
+        types::Type::Album,
+        types::Type::AlbumArtist,
+        types::Type::Date,
+        types::Type::Genre,
+    ] {
+        check_field(tracks, t, &mut issues);
+    }
+    check_partially_missing(tracks, types::Type::Disc, &mut issues);
+    check_track_numbers(tracks, &mut issues);
+    check_disc_count(tracks, &mut issues);
+
+    let groups = group(
+        tracks
+            .iter()
+            .map(|track| (track.properties.sample_rate.to_string(), &track.path)),
+    );
+    if groups.len() > 1 {
+        issues.push(Issue::MixedSampleRates { groups });
+    }
+    let groups = group(
+        tracks
+            .iter()
+            .map(|track| (track.properties.bit_depth.to_string(), &track.path)),
+    );
+    if groups.len() > 1 {
+        issues.push(Issue::MixedBitDepths { groups });
+    }
+
+    check_coverart(tracks, &mut issues);
+
+    issues
+}
+
+/// The front cover, or the first picture when there is no front cover
+pub fn front_cover(song_metadata: &types::SongMetadata) -> Option<&types::PictureInfo> {
+    song_metadata
+        .pictures
+        .iter()
+        .find(|pic| pic.picture_type == 3)
+        .or(song_metadata.pictures.first())
+}
+
+/// Groups the paths by value, most common value first
+fn group<'a, I: Iterator<Item = (String, &'a String)>>(values: I) -> Vec<ValueGroup> {
+    let mut grouped: std::collections::BTreeMap<String, Vec<String>> =
+        std::collections::BTreeMap::new();
+    for (value, path) in values {
+        grouped.entry(value).or_default().push(path.clone());
+    }
+
+    let mut groups: Vec<ValueGroup> = grouped
+        .into_iter()
+        .map(|(value, paths)| ValueGroup { value, paths })
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.paths.len()));
+    groups
+}
+
+fn check_field(tracks: &[AlbumTrack], t: types::Type, issues: &mut Vec<Issue>) {
+    check_partially_missing(tracks, t, issues);
+
+    let groups = group(tracks.iter().filter_map(|track| {
+        track
+            .metadata
+            .get(t)
+            .map(|value| (value.to_string(), &track.path))
+    }));
+    if groups.len() > 1 {
+        issues.push(Issue::Mismatch { field: t, groups });
+    }
+}
+
+fn check_partially_missing(tracks: &[AlbumTrack], t: types::Type, issues: &mut Vec<Issue>) {
+    let paths: Vec<String> = tracks
+        .iter()
+        .filter(|track| track.metadata.get(t).is_none())
+        .map(|track| track.path.clone())
+        .collect();
+    if !paths.is_empty() && paths.len() < tracks.len() {
+        issues.push(Issue::Missing { field: t, paths });
+    }
+}
+
+/// The tracks of each disc
+fn discs(tracks: &[AlbumTrack]) -> std::collections::BTreeMap<Option<u32>, Vec<&AlbumTrack>> {
+    let mut discs: std::collections::BTreeMap<Option<u32>, Vec<&AlbumTrack>> =
+        std::collections::BTreeMap::new();
+    for track in tracks {
+        discs.entry(track.metadata.disc).or_default().push(track);
+    }
+
+    discs
+}
+
+/// The highest plausible track number of a disc: its number of tracks or the
+/// largest TRACKCOUNT, whichever is more, but never above `MAX_TRACK_NUMBER`
+fn track_limit(disc_tracks: &[&AlbumTrack]) -> u32 {
+    let track_count = disc_tracks
+        .iter()
+        .filter_map(|track| track.metadata.track_count)
+        .max()
+        .unwrap_or(0);
+
+    (disc_tracks.len() as u32)
+        .max(track_count)
+        .min(MAX_TRACK_NUMBER)
+}
+
+/// The TRACKCOUNT of each disc: its highest track number within the track limit
+fn track_counts(tracks: &[AlbumTrack]) -> std::collections::BTreeMap<Option<u32>, u32> {
+    let mut track_counts: std::collections::BTreeMap<Option<u32>, u32> =
+        std::collections::BTreeMap::new();
+    for (disc, disc_tracks) in discs(tracks) {
+        let limit = track_limit(&disc_tracks);
+        if let Some(highest) = disc_tracks
+            .iter()
+            .filter_map(|track| track.metadata.track)
+            .filter(|number| *number <= limit)
+            .max()
+        {
+            track_counts.insert(disc, highest);
+        }
+    }
+
+    track_counts
+}
+
+/// The DISCCOUNT of the album: its highest disc number, 1 without disc numbers
+fn disc_count(tracks: &[AlbumTrack]) -> u32 {
+    tracks
+        .iter()
+        .filter_map(|track| track.metadata.disc)
+        .max()
+        .unwrap_or(1)
+}
+
+fn check_track_numbers(tracks: &[AlbumTrack], issues: &mut Vec<Issue>) {
+    let track_counts = track_counts(tracks);
+
+    let paths: Vec<String> = tracks
+        .iter()
+        .filter(|track| track.metadata.track.is_none())
+        .map(|track| track.path.clone())
+        .collect();
+    if !paths.is_empty() {
+        issues.push(Issue::Missing {
+            field: types::Type::Track,
+            paths,
+        });
+    }
+
+    for (disc, disc_tracks) in discs(tracks) {
+        let mut numbers: std::collections::BTreeMap<u32, Vec<String>> =
+            std::collections::BTreeMap::new();
+        for track in &disc_tracks {
+            if let Some(number) = track.metadata.track {
+                numbers.entry(number).or_default().push(track.path.clone());
+            }
+        }
+
+        for (track, paths) in &numbers {
+            if paths.len() > 1 {
+                issues.push(Issue::DuplicateTrack {
+                    disc,
+                    track: *track,
+                    paths: paths.clone(),
+                });
+            }
+        }
+
+        // Numbers past the limit are reported on their own rather than as a gap
+        // of possibly billions of missing tracks
+        let limit = track_limit(&disc_tracks);
+        for (track, paths) in numbers.range(limit + 1..) {
+            issues.push(Issue::TrackOutOfRange {
+                disc,
+                track: *track,
+                limit,
+                paths: paths.clone(),
+            });
+        }
+        if let Some(&highest) = track_counts.get(&disc) {
+            for track in 1..highest {
+                if !numbers.contains_key(&track) {
+                    issues.push(Issue::MissingTrack { disc, track });
+                }
+            }
+        }
+
+        let Some(&actual) = track_counts.get(&disc) else {
+            continue;
+        };
+        let groups = group(disc_tracks.iter().filter_map(|track| {
+            track
+                .metadata
+                .track_count
+                .filter(|track_count| *track_count != actual)
+                .map(|track_count| (track_count.to_string(), &track.path))
+        }));
+        if !groups.is_empty() {
+            issues.push(Issue::TrackCountMismatch {
+                disc,
+                actual,
+                groups,
+            });
+        }
+    }
+}
+
+fn check_disc_count(tracks: &[AlbumTrack], issues: &mut Vec<Issue>) {
+    let actual = disc_count(tracks);
+
+    let groups = group(tracks.iter().filter_map(|track| {
+        track
+            .metadata
+            .disc_count
+            .filter(|disc_count| *disc_count != actual)
+            .map(|disc_count| (disc_count.to_string(), &track.path))
+    }));
+    if !groups.is_empty() {
+        issues.push(Issue::DiscCountMismatch { actual, groups });
+    }
+}
+
+fn check_coverart(tracks: &[AlbumTrack], issues: &mut Vec<Issue>) {
+    let paths: Vec<String> = tracks
+        .iter()
+        .filter(|track| track.metadata.pictures.is_empty())
+        .map(|track| track.path.clone())
+        .collect();
+    if !paths.is_empty() {
+        issues.push(Issue::MissingCoverArt { paths });
+    }
+
+    let groups = group(tracks.iter().filter_map(|track| {
+        front_cover(&track.metadata).map(|pic| (pic.hash.clone(), &track.path))
+    }));
+    if groups.len() > 1 {
+        issues.push(Issue::DifferentCoverArt { groups });
+    }
+}
+
+/// A correction planned by `plan_fixes`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fix {
+    /// Written with `meta::metadata::set_meta`. `old` is the value as stored.
+    SetMeta {
+        path: String,
+        field: types::Type,
+        old: Option<String>,
+        new: String,
+    },
+    /// Written with `meta::coverart::copy_coverart`
+    CopyCoverArt { path: String, source: String },
+}
+
+/// Plans the corrections that are safe to make without asking:
+///
+/// * TRACKCOUNT set to the highest track number of each disc that is not out of
+///   range, and DISCCOUNT to the highest disc number, the same counts `check`
+///   expects. DISCCOUNT is only added to tracks without one when some track has a disc number.
+/// * ALBUMARTIST set to the value most tracks share, when one value is the most
+///   common
+/// * DATE rewritten in ISO 8601 when stored in another form
+/// * The most common front cover copied to tracks without pictures
+pub fn plan_fixes(report: &AlbumReport) -> Result<Vec<Fix>, std::io::Error> {
+    let mut fixes = Vec::new();
+    let tracks = &report.tracks;
+
+    let track_counts = track_counts(tracks);
+    let disc_count = disc_count(tracks);
+    let has_discs = tracks.iter().any(|track| track.metadata.disc.is_some());
+
+    for track in tracks {
+        if let Some(track_count) = track_counts.get(&track.metadata.disc) {
+            push_fix(&mut fixes, track, types::Type::TrackCount, *track_count);
+        }
+        if has_discs || track.metadata.disc_count.is_some() {
+            push_fix(&mut fixes, track, types::Type::DiscCount, disc_count);
+        }
+    }
+
+    let groups = group(tracks.iter().filter_map(|track| {
+        track
+            .metadata
+            .album_artist
+            .clone()
+            .map(|album_artist| (album_artist, &track.path))
+    }));
+    let majority = match groups.as_slice() {
+        [first] => Some(first),
+        [first, second, ..] if first.paths.len() > second.paths.len() => Some(first),
+        _ => None,
+    };
+    if let Some(majority) = majority {
+        for track in tracks {
+            if track.metadata.album_artist.as_ref() != Some(&majority.value) {
+                fixes.push(Fix::SetMeta {
+                    path: track.path.clone(),
+                    field: types::Type::AlbumArtist,
+                    old: track.metadata.album_artist.clone(),
+                    new: majority.value.clone(),
+                });
+            }
+        }
+    }
+
+    let date_key = types::access::get_type(types::Type::Date)?;
+    for track in tracks {
+        if let Some(date) = &track.metadata.date {
+            let stored = crate::meta::metadata::get_comments(&track.path, &date_key)?;
+            if stored != vec![date.to_string()] {
+                fixes.push(Fix::SetMeta {
+                    path: track.path.clone(),
+                    field: types::Type::Date,
+                    old: stored.into_iter().next(),
+                    new: date.to_string(),
+                });
+            }
+        }
+    }
+
+    let groups = group(tracks.iter().filter_map(|track| {
+        front_cover(&track.metadata).map(|pic| (pic.hash.clone(), &track.path))
+    }));
+    if let Some(dominant) = groups.first() {
+        for track in tracks {
+            if track.metadata.pictures.is_empty() {
+                fixes.push(Fix::CopyCoverArt {
+                    path: track.path.clone(),
+                    source: dominant.paths[0].clone(),
+                });
+            }
+        }
+    }
+
+    Ok(fixes)
+}
+
+fn push_fix(fixes: &mut Vec<Fix>, track: &AlbumTrack, t: types::Type, wanted: u32) {
+    let current = match t {
+        types::Type::TrackCount => track.metadata.track_count,
+        _ => track.metadata.disc_count,
+    };
+
+    if current != Some(wanted) {
+        fixes.push(Fix::SetMeta {
+            path: track.path.clone(),
+            field: t,
+            old: current.map(|value| value.to_string()),
+            new: wanted.to_string(),
+        });
+    }
+}
+
+/// Applies planned fixes in order, stopping at the first one that fails
+pub fn apply_fixes(fixes: &[Fix]) -> Result<(), std::io::Error> {
+    for fix in fixes {
+        let (path, result) = match fix {
+            Fix::SetMeta {
+                path, field, new, ..
+            } => (
+                path,
+                crate::meta::metadata::set_meta(*field, path, new).map(|_| ()),
+            ),
+            Fix::CopyCoverArt { path, source } => (
+                path,
+                crate::meta::coverart::copy_coverart(source, path).map(|_| ()),
+            ),
+        };
+
+        if let Err(err) = result {
+            return Err(std::io::Error::new(err.kind(), format!("{path}: {err}")));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the album and plans its fixes, applying them unless `dry_run` is set
+pub fn fix_album(directory: &str, dry_run: bool) -> Result<Vec<Fix>, std::io::Error> {
+    let report = album(directory)?;
+    let fixes = plan_fixes(&report)?;
+    if !dry_run {
+        apply_fixes(&fixes)?;
+    }
+
+    Ok(fixes)
+}
+
+fn describe_groups(groups: &[ValueGroup]) -> String {
+    groups
+        .iter()
+        .map(|group| format!("{:?} ({})", group.value, group.paths.join(", ")))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn describe_disc(disc: &Option<u32>) -> String {
+    match disc {
+        Some(disc) => format!(" on disc {disc}"),
+        None => String::new(),
+    }
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let key = |t: &types::Type| types::access::get_type(*t).unwrap_or_default();
+
+        match self {
+            Issue::Mismatch { field, groups } => {
+                write!(
+                    f,
+                    "Tracks disagree on {}: {}",
+                    key(field),
+                    describe_groups(groups)
+                )
+            }
+            Issue::Missing { field, paths } => {
+                write!(f, "{} is missing from {}", key(field), paths.join(", "))
+            }
+            Issue::DuplicateTrack { disc, track, paths } => write!(
+                f,
+                "Track {track}{} is used by {}",
+                describe_disc(disc),
+                paths.join(", ")
+            ),
+            Issue::MissingTrack { disc, track } => {
+                write!(f, "Track {track}{} is missing", describe_disc(disc))
+            }
+            Issue::TrackOutOfRange {
+                disc,
+                track,
+                limit,
+                paths,
+            } => write!(
+                f,
+                "Track {track}{} is above the {limit} tracks expected, used by {}",
+                describe_disc(disc),
+                paths.join(", ")
+            ),
+            Issue::TrackCountMismatch {
+                disc,
+                actual,
+                groups,
+            } => write!(
+                f,
+                "Tracks go up to {actual}{} but TRACKCOUNT is {}",
+                describe_disc(disc),
+                describe_groups(groups)
+            ),
+            Issue::DiscCountMismatch { actual, groups } => write!(
+                f,
+                "Discs go up to {actual} but DISCCOUNT is {}",
+                describe_groups(groups)
+            ),
+            Issue::MixedSampleRates { groups } => {
+                write!(f, "Mixed sample rates: {}", describe_groups(groups))
+            }
+            Issue::MixedBitDepths { groups } => {
+                write!(f, "Mixed bit depths: {}", describe_groups(groups))
+            }
+            Issue::MissingCoverArt { paths } => {
+                write!(f, "Cover art is missing from {}", paths.join(", "))
+            }
+            Issue::DifferentCoverArt { groups } => {
+                write!(f, "Cover art differs: {}", describe_groups(groups))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Fix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fix::SetMeta {
+                path,
+                field,
+                old,
+                new,
+            } => {
+                let key = types::access::get_type(*field).unwrap_or_default();
+                match old {
+                    Some(old) => write!(f, "{path}: set {key} from {old:?} to {new:?}"),
+                    None => write!(f, "{path}: set {key} to {new:?}"),
+                }
+            }
+            Fix::CopyCoverArt { path, source } => {
+                write!(f, "{path}: copy cover art from {source}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::util;
+    use crate::types;
+
+    fn copy_album(test_dir: &str) -> Vec<String> {
+        (1..=3)
+            .map(|track| util::copy_track(test_dir, &util::get_filename(track)))
+            .collect()
+    }
+
+    #[test]
+    fn test_consistent_album() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        copy_album(&test_dir);
+
+        let report = super::album(&test_dir).unwrap();
+        assert_eq!(3, report.tracks.len());
+        assert!(report.is_consistent(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_inconsistent_album() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_album(&test_dir);
+        let other_coverart =
+            util::get_full_path(util::TESTFILEDIRECTORY, "Sample Tracks 3 - Other one.png")
+                .unwrap();
+
+        crate::meta::metadata::set_meta(types::Type::AlbumArtist, &paths[1], "Someone").unwrap();
+        crate::meta::metadata::set_meta(types::Type::Track, &paths[2], "2").unwrap();
+        crate::meta::metadata::set_meta(types::Type::TrackCount, &paths[0], "4").unwrap();
+        crate::meta::coverart::remove_coverart(&paths[1]).unwrap();
+        crate::meta::coverart::set_coverart(&paths[0], &other_coverart).unwrap();
+        let broken = util::get_full_path(&test_dir, "broken.flac").unwrap();
+        util::save_bytes_to_file(b"fLaC", &broken).unwrap();
+
+        let report = super::album(&test_dir).unwrap();
+        assert_eq!(3, report.tracks.len());
+        assert_eq!(1, report.unreadable.len());
+        assert_eq!(broken, report.unreadable[0].0);
+
+        let expected = vec![
+            super::Issue::Mismatch {
+                field: types::Type::AlbumArtist,
+                groups: vec![
+                    super::ValueGroup {
+                        value: String::from("KD"),
+                        paths: vec![paths[0].clone(), paths[2].clone()],
+                    },
+                    super::ValueGroup {
+                        value: String::from("Someone"),
+                        paths: vec![paths[1].clone()],
+                    },
+                ],
+            },
+            super::Issue::DuplicateTrack {
+                disc: Some(1),
+                track: 2,
+                paths: vec![paths[1].clone(), paths[2].clone()],
+            },
+            super::Issue::TrackCountMismatch {
+                disc: Some(1),
+                actual: 2,
+                groups: vec![
+                    super::ValueGroup {
+                        value: String::from("3"),
+                        paths: vec![paths[1].clone(), paths[2].clone()],
+                    },
+                    super::ValueGroup {
+                        value: String::from("4"),
+                        paths: vec![paths[0].clone()],
+                    },
+                ],
+            },
+            super::Issue::MissingCoverArt {
+                paths: vec![paths[1].clone()],
+            },
+        ];
+        for issue in &expected {
+            assert!(
+                report.issues.contains(issue),
+                "{issue:?} not in {:?}",
+                report.issues
+            );
+        }
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, super::Issue::DifferentCoverArt { .. })),
+            "{:?}",
+            report.issues
+        );
+        assert_eq!(
+            expected.len() + 1,
+            report.issues.len(),
+            "{:?}",
+            report.issues
+        );
+
+        assert_eq!(
+            format!("Track 2 on disc 1 is used by {}, {}", paths[1], paths[2]),
+            expected[1].to_string()
+        );
+    }
+
+    #[test]
+    fn test_missing_track() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_album(&test_dir);
+        util::remove_file(&paths[1]).unwrap();
+
+        let report = super::album(&test_dir).unwrap();
+        assert!(report.issues.contains(&super::Issue::MissingTrack {
+            disc: Some(1),
+            track: 2
+        }));
+        // TRACKCOUNT still matches the highest track number
+        assert!(
+            !report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, super::Issue::TrackCountMismatch { .. })),
+            "{:?}",
+            report.issues
+        );
+        assert!(super::album(&format!("{test_dir}/missing")).is_err());
+    }
+
+    #[test]
+    fn test_huge_track_number() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_album(&test_dir);
+        crate::meta::metadata::set_meta(types::Type::Track, &paths[2], "2147483646").unwrap();
+
+        // The outlier is not counted, so the disc goes up to track 2
+        let report = super::album(&test_dir).unwrap();
+        assert_eq!(
+            vec![
+                super::Issue::TrackOutOfRange {
+                    disc: Some(1),
+                    track: 2147483646,
+                    limit: 3,
+                    paths: vec![paths[2].clone()],
+                },
+                super::Issue::TrackCountMismatch {
+                    disc: Some(1),
+                    actual: 2,
+                    groups: vec![super::ValueGroup {
+                        value: String::from("3"),
+                        paths: paths.clone(),
+                    }],
+                },
+            ],
+            report.issues
+        );
+        assert_eq!(
+            format!(
+                "Track 2147483646 on disc 1 is above the 3 tracks expected, used by {}",
+                paths[2]
+            ),
+            report.issues[0].to_string()
+        );
+
+        // TRACKCOUNT is planned from the tracks in range
+        let fixes = super::plan_fixes(&report).unwrap();
+        assert!(
+            fixes.iter().all(|fix| match fix {
+                super::Fix::SetMeta {
+                    field: types::Type::TrackCount,
+                    new,
+                    ..
+                } => new == "2",
+                _ => true,
+            }),
+            "{fixes:?}"
+        );
+    }
+
+    #[test]
+    fn test_fix_converges() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_album(&test_dir);
+
+        // Track 2 is missing, which leaves TRACKCOUNT at 3 correct
+        util::remove_file(&paths[1]).unwrap();
+        crate::meta::metadata::set_meta(types::Type::TrackCount, &paths[2], "5").unwrap();
+        crate::meta::metadata::set_meta(types::Type::DiscCount, &paths[0], "2").unwrap();
+
+        let report = super::album(&test_dir).unwrap();
+        assert_eq!(
+            vec![
+                super::Issue::MissingTrack {
+                    disc: Some(1),
+                    track: 2,
+                },
+                super::Issue::TrackCountMismatch {
+                    disc: Some(1),
+                    actual: 3,
+                    groups: vec![super::ValueGroup {
+                        value: String::from("5"),
+                        paths: vec![paths[2].clone()],
+                    }],
+                },
+                super::Issue::DiscCountMismatch {
+                    actual: 1,
+                    groups: vec![super::ValueGroup {
+                        value: String::from("2"),
+                        paths: vec![paths[0].clone()],
+                    }],
+                },
+            ],
+            report.issues
+        );
+
+        super::fix_album(&test_dir, false).unwrap();
+        let report = super::album(&test_dir).unwrap();
+        assert_eq!(
+            vec![super::Issue::MissingTrack {
+                disc: Some(1),
+                track: 2,
+            }],
+            report.issues
+        );
+        assert!(super::fix_album(&test_dir, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fix_album() {
+        let temp_file = tempfile::tempdir().expect("Could not create test directory");
+        let test_dir = String::from(temp_file.path().to_str().unwrap());
+        let paths = copy_album(&test_dir);
+
+        crate::meta::metadata::set_meta(types::Type::AlbumArtist, &paths[1], "Someone").unwrap();
+        crate::meta::metadata::set_meta(types::Type::TrackCount, &paths[2], "7").unwrap();
+        crate::meta::metadata::set_meta(types::Type::DiscCount, &paths[0], "2").unwrap();
+        crate::meta::metadata::import_comments(
+            &paths[0],
+            b"DATE=2025-04-11T10:00:00\n",
+            crate::meta::metadata::ImportMode::Merge,
+        )
+        .unwrap();
+        crate::meta::coverart::remove_coverart(&paths[1]).unwrap();
+
+        let fixes = super::fix_album(&test_dir, true).unwrap();
+        let expected = vec![
+            super::Fix::SetMeta {
+                path: paths[0].clone(),
+                field: types::Type::DiscCount,
+                old: Some(String::from("2")),
+                new: String::from("1"),
+            },
+            super::Fix::SetMeta {
+                path: paths[2].clone(),
+                field: types::Type::TrackCount,
+                old: Some(String::from("7")),
+                new: String::from("3"),
+            },
+            super::Fix::SetMeta {
+                path: paths[1].clone(),
+                field: types::Type::AlbumArtist,
+                old: Some(String::from("Someone")),
+                new: String::from("KD"),
+            },
+            super::Fix::SetMeta {
+                path: paths[0].clone(),
+                field: types::Type::Date,
+                old: Some(String::from("2025-04-11T10:00:00")),
+                new: String::from("2025-04-11"),
+            },
+            super::Fix::CopyCoverArt {
+                path: paths[1].clone(),
+                source: paths[0].clone(),
+            },
+        ];
+        assert_eq!(expected, fixes);
+        assert_eq!(
+            format!("{}: set ALBUMARTIST from \"Someone\" to \"KD\"", paths[1]),
+            fixes[2].to_string()
+        );
+
+        // The dry run changed nothing
+        assert_eq!(
+            "Someone",
+            crate::meta::metadata::get_meta(types::Type::AlbumArtist, &paths[1]).unwrap()
+        );
+
+        assert_eq!(expected, super::fix_album(&test_dir, false).unwrap());
+        let report = super::album(&test_dir).unwrap();
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert!(super::fix_album(&test_dir, true).unwrap().is_empty());
+    }
+}